@@ -0,0 +1,35 @@
+//! Embeds information about the current build into the binary.
+//!
+//! The git revision is taken from the `GIT_SHA` environment variable if it is set (e.g. in
+//! Docker builds, where `.git` is not available), and from `git rev-parse HEAD` otherwise.
+
+use std::env;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+	println!("cargo:rerun-if-env-changed=GIT_SHA");
+	println!("cargo:rerun-if-changed=.git/HEAD");
+	println!("cargo:rerun-if-changed=.git/refs");
+
+	let git_sha = env::var("GIT_SHA")
+		.ok()
+		.filter(|sha| !sha.is_empty())
+		.or_else(|| {
+			Command::new("git")
+				.args(["rev-parse", "HEAD"])
+				.output()
+				.ok()
+				.filter(|output| output.status.success())
+				.and_then(|output| String::from_utf8(output.stdout).ok())
+				.map(|sha| sha.trim().to_owned())
+		})
+		.unwrap_or_else(|| String::from("unknown"));
+
+	let built_at = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map_or(0, |duration| duration.as_secs());
+
+	println!("cargo:rustc-env=CS2KZ_API_GIT_SHA={git_sha}");
+	println!("cargo:rustc-env=CS2KZ_API_BUILT_AT={built_at}");
+}
@@ -0,0 +1,5 @@
+INSERT INTO
+  PlayerNameHistory (player_id, name, created_on, last_seen_on)
+VALUES
+  (76561198282622073, "AlphaKeks", "2024-01-01", NOW()),
+  (76561198282622073, "kekw", "2023-01-01", "2023-12-31");
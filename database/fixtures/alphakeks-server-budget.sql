@@ -0,0 +1,6 @@
+UPDATE
+  Players
+SET
+  server_budget = 1
+WHERE
+  id = 76561198282622073;
@@ -0,0 +1,4 @@
+INSERT INTO
+  BannedIpRanges (network, last_address, prefix_length, reason, admin_id)
+VALUES
+  ("::ffff:69.69.0.0", "::ffff:69.69.255.255", 112, "ban evasion", 76561198282622073);
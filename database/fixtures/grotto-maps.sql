@@ -0,0 +1,39 @@
+INSERT INTO
+  Maps (id, name, workshop_id, checksum)
+VALUES
+  (1, "kz_grotto", 0, 0),
+  (2, "kz_grotto_v2", 0, 0),
+  (3, "kz_grotto_remake", 0, 0),
+  (4, "bkz_grotto", 0, 0);
+
+INSERT INTO
+  Mappers (map_id, player_id)
+VALUES
+  (1, 76561198282622073),
+  (2, 76561198282622073),
+  (3, 76561198282622073),
+  (4, 76561198282622073);
+
+INSERT INTO
+  Courses (id, name, map_id)
+VALUES
+  (1, "main", 1),
+  (2, "main", 2),
+  (3, "main", 3),
+  (4, "main", 4);
+
+INSERT INTO
+  CourseMappers (course_id, player_id)
+VALUES
+  (1, 76561198282622073),
+  (2, 76561198282622073),
+  (3, 76561198282622073),
+  (4, 76561198282622073);
+
+INSERT INTO
+  CourseFilters (course_id, mode_id, teleports, tier)
+VALUES
+  (1, 1, true, 1),
+  (2, 1, true, 1),
+  (3, 1, true, 1),
+  (4, 1, true, 1);
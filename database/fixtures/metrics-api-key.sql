@@ -0,0 +1,4 @@
+INSERT INTO
+  Credentials (name, `key`)
+VALUES
+  ("metrics", "a5c6f8b2-3d1e-4f7a-9b0c-2e4d6f8a1b3c");
@@ -0,0 +1,7 @@
+UPDATE
+  PluginVersions
+SET
+  yanked_on = NOW(),
+  yanked_reason = "crashes on map change"
+WHERE
+  semver = "0.0.1";
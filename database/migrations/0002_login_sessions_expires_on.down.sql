@@ -0,0 +1 @@
+DROP INDEX IF EXISTS `expires_on` ON `LoginSessions`;
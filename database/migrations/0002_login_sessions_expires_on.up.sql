@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS `expires_on` ON `LoginSessions` (`expires_on`);
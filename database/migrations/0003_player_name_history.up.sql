@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS `PlayerNameHistory` (
+  `player_id` INT8 UNSIGNED NOT NULL,
+  `name` VARCHAR(32) NOT NULL,
+  `created_on` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  `last_seen_on` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (`player_id`, `name`),
+  FOREIGN KEY (`player_id`) REFERENCES `Players` (`id`) ON DELETE CASCADE
+);
+
+INSERT IGNORE INTO
+  `PlayerNameHistory` (`player_id`, `name`, `created_on`, `last_seen_on`)
+SELECT
+  `id`,
+  `name`,
+  `created_on`,
+  `last_seen_on`
+FROM
+  `Players`;
@@ -0,0 +1,6 @@
+ALTER TABLE
+  `Servers`
+DROP
+  COLUMN IF EXISTS `previous_refresh_key_expires_on`,
+DROP
+  COLUMN IF EXISTS `previous_refresh_key`;
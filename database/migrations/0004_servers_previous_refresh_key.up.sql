@@ -0,0 +1,10 @@
+ALTER TABLE
+  `Servers`
+ADD
+  COLUMN IF NOT EXISTS `previous_refresh_key` UUID
+AFTER
+  `refresh_key`,
+ADD
+  COLUMN IF NOT EXISTS `previous_refresh_key_expires_on` TIMESTAMP NULL
+AFTER
+  `previous_refresh_key`;
@@ -0,0 +1,4 @@
+ALTER TABLE
+  `Players`
+DROP
+  COLUMN IF EXISTS `server_budget`;
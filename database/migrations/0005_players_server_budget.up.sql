@@ -0,0 +1,6 @@
+ALTER TABLE
+  `Players`
+ADD
+  COLUMN IF NOT EXISTS `server_budget` INT2 UNSIGNED
+AFTER
+  `permissions`;
@@ -0,0 +1,4 @@
+ALTER TABLE
+  `Bans`
+DROP
+  COLUMN IF EXISTS `note`;
@@ -0,0 +1,6 @@
+ALTER TABLE
+  `Bans`
+ADD
+  COLUMN IF NOT EXISTS `note` TEXT
+AFTER
+  `reason`;
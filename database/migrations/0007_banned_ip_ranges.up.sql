@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS `BannedIpRanges` (
+  `id` INT8 UNSIGNED NOT NULL AUTO_INCREMENT,
+  `network` INET6 NOT NULL,
+  `last_address` INET6 NOT NULL,
+  `prefix_length` INT1 UNSIGNED NOT NULL,
+  `reason` TEXT NOT NULL,
+  `admin_id` INT8 UNSIGNED,
+  `created_on` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  `expires_on` TIMESTAMP NULL,
+  PRIMARY KEY (`id`),
+  INDEX (`network`, `last_address`),
+  FOREIGN KEY (`admin_id`) REFERENCES `Players` (`id`)
+);
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS `Appeals` (
+  `id` INT8 UNSIGNED NOT NULL AUTO_INCREMENT,
+  `ban_id` INT8 UNSIGNED NOT NULL,
+  `message` TEXT NOT NULL,
+  `status` VARCHAR(16) NOT NULL DEFAULT "pending",
+  `admin_id` INT8 UNSIGNED,
+  `response` TEXT,
+  `created_on` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  `resolved_on` TIMESTAMP NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE (`ban_id`),
+  FOREIGN KEY (`ban_id`) REFERENCES `Bans` (`id`),
+  FOREIGN KEY (`admin_id`) REFERENCES `Players` (`id`)
+);
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS `PermissionChanges` (
+  `id` INT8 UNSIGNED NOT NULL AUTO_INCREMENT,
+  `player_id` INT8 UNSIGNED NOT NULL,
+  `admin_id` INT8 UNSIGNED NOT NULL,
+  `added` INT8 UNSIGNED NOT NULL DEFAULT 0,
+  `removed` INT8 UNSIGNED NOT NULL DEFAULT 0,
+  `created_on` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (`id`),
+  FOREIGN KEY (`player_id`) REFERENCES `Players` (`id`) ON DELETE RESTRICT,
+  FOREIGN KEY (`admin_id`) REFERENCES `Players` (`id`)
+);
@@ -0,0 +1,6 @@
+ALTER TABLE
+  `Players`
+ADD
+  COLUMN IF NOT EXISTS `discord_id` INT8 UNSIGNED UNIQUE
+AFTER
+  `server_budget`;
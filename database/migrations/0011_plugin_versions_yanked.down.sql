@@ -0,0 +1,6 @@
+ALTER TABLE
+  `PluginVersions`
+DROP
+  COLUMN IF EXISTS `yanked_reason`,
+DROP
+  COLUMN IF EXISTS `yanked_on`;
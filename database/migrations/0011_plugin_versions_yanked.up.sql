@@ -0,0 +1,6 @@
+ALTER TABLE
+  `PluginVersions`
+ADD
+  COLUMN IF NOT EXISTS `yanked_on` TIMESTAMP NULL DEFAULT NULL,
+ADD
+  COLUMN IF NOT EXISTS `yanked_reason` TEXT;
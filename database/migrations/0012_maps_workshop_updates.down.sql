@@ -0,0 +1,6 @@
+ALTER TABLE
+  `Maps`
+DROP
+  COLUMN IF EXISTS `needs_review`,
+DROP
+  COLUMN IF EXISTS `workshop_updated_on`;
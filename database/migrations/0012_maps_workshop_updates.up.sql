@@ -0,0 +1,10 @@
+ALTER TABLE
+  `Maps`
+ADD
+  COLUMN IF NOT EXISTS `workshop_updated_on` TIMESTAMP NULL DEFAULT NULL
+AFTER
+  `checksum`,
+ADD
+  COLUMN IF NOT EXISTS `needs_review` BOOLEAN NOT NULL DEFAULT FALSE
+AFTER
+  `workshop_updated_on`;
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS `RecordStatusChanges` (
+  `id` INT8 UNSIGNED NOT NULL AUTO_INCREMENT,
+  `record_id` INT8 UNSIGNED NOT NULL,
+  `admin_id` INT8 UNSIGNED NOT NULL,
+  `old_status` VARCHAR(16) NOT NULL,
+  `new_status` VARCHAR(16) NOT NULL,
+  `reason` TEXT,
+  `created_on` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (`id`),
+  FOREIGN KEY (`admin_id`) REFERENCES `Players` (`id`)
+);
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS `ApiTokens` (
+  `id` INT8 UNSIGNED NOT NULL AUTO_INCREMENT,
+  `token` UUID NOT NULL,
+  `player_id` INT8 UNSIGNED NOT NULL,
+  `name` VARCHAR(255) NOT NULL,
+  `scopes` INT8 UNSIGNED NOT NULL DEFAULT 0,
+  `created_on` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  `expires_on` TIMESTAMP NOT NULL,
+  `last_used_on` TIMESTAMP NULL,
+  `revoked_on` TIMESTAMP NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE (`token`),
+  FOREIGN KEY (`player_id`) REFERENCES `Players` (`id`) ON DELETE CASCADE
+);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS `RecordIdempotencyKeys` (
+  `server_id` INT2 UNSIGNED NOT NULL,
+  `idempotency_key` VARCHAR(255) NOT NULL,
+  `record_id` INT8 UNSIGNED NOT NULL,
+  `created_on` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (`server_id`, `idempotency_key`),
+  FOREIGN KEY (`server_id`) REFERENCES `Servers` (`id`) ON DELETE CASCADE
+);
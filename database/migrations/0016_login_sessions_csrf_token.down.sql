@@ -0,0 +1,4 @@
+ALTER TABLE
+  `LoginSessions`
+DROP
+  COLUMN IF EXISTS `csrf_token`;
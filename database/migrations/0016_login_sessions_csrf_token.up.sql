@@ -0,0 +1,6 @@
+ALTER TABLE
+  `LoginSessions`
+ADD
+  COLUMN IF NOT EXISTS `csrf_token` UUID
+AFTER
+  `player_id`;
@@ -4,7 +4,8 @@ use axum::extract::Path;
 use axum::Json;
 use cs2kz::SteamID;
 
-use crate::admins::{Admin, AdminUpdate};
+use crate::admins::{self, Admin, AdminUpdate};
+use crate::authentication::CsrfToken;
 use crate::authorization::{self, Permissions};
 use crate::openapi::responses;
 use crate::openapi::responses::NoContent;
@@ -52,6 +53,8 @@ pub async fn get(state: State, Path(steam_id): Path<SteamID>) -> Result<Json<Adm
 /// Create/Update an admin.
 ///
 /// This endpoint is idempotent!
+///
+/// Requires a valid CSRF token in the `X-CSRF-Token` header.
 #[tracing::instrument(skip(state))]
 #[utoipa::path(
   put,
@@ -64,16 +67,20 @@ pub async fn get(state: State, Path(steam_id): Path<SteamID>) -> Result<Json<Adm
     responses::NoContent,
     responses::BadRequest,
     responses::Unauthorized,
+    responses::Forbidden,
     responses::UnprocessableEntity,
   ),
 )]
 pub async fn put(
 	state: State,
 	session: authentication::Session<authorization::HasPermissions<{ Permissions::ADMIN.value() }>>,
+	_csrf: CsrfToken,
 	Path(steam_id): Path<SteamID>,
 	Json(AdminUpdate { permissions }): Json<AdminUpdate>,
 ) -> Result<NoContent> {
 	let mut transaction = state.transaction().await?;
+	let old_permissions =
+		admins::permissions::fetch_permissions(steam_id, &mut transaction).await?;
 
 	let query_result = sqlx::query! {
 		r#"
@@ -95,6 +102,15 @@ pub async fn put(
 		n => assert_eq!(n, 1, "updated more than 1 admin"),
 	}
 
+	admins::permissions::record_change(
+		steam_id,
+		old_permissions,
+		permissions,
+		session.user().steam_id(),
+		&mut transaction,
+	)
+	.await?;
+
 	transaction.commit().await?;
 
 	tracing::trace!(target: "cs2kz_api::audit_log", %steam_id, ?permissions, "updated admin");
@@ -2,3 +2,5 @@
 
 pub mod root;
 pub mod by_id;
+pub mod sessions;
+pub mod permissions;
@@ -0,0 +1,159 @@
+//! HTTP handlers for the `/admins/{steam_id}/permissions` routes.
+
+use axum::extract::Path;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use cs2kz::SteamID;
+
+use crate::admins::{self, PermissionChange, PermissionsDelta};
+use crate::authentication::CsrfToken;
+use crate::authorization::{self, Permissions};
+use crate::openapi::responses;
+use crate::openapi::responses::NoContent;
+use crate::players::Player;
+use crate::{authentication, Error, Result, State};
+
+/// Fetch the history of changes to an admin's permissions.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  get,
+  path = "/admins/{steam_id}/permissions",
+  tag = "Admins",
+  security(("Browser Session" = ["admins"])),
+  params(SteamID),
+  responses(
+    responses::OkList<PermissionChange>,
+    responses::NoContent,
+    responses::BadRequest,
+    responses::Unauthorized,
+  ),
+)]
+pub async fn get(
+	state: State,
+	session: authentication::Session<authorization::HasPermissions<{ Permissions::ADMIN.value() }>>,
+	Path(steam_id): Path<SteamID>,
+) -> Result<Json<Vec<PermissionChange>>> {
+	let changes = sqlx::query! {
+		r#"
+		SELECT
+		  a.name admin_name,
+		  a.id `admin_id: SteamID`,
+		  c.added `added: Permissions`,
+		  c.removed `removed: Permissions`,
+		  c.created_on `created_on: DateTime<Utc>`
+		FROM
+		  PermissionChanges c
+		  JOIN Players a ON a.id = c.admin_id
+		WHERE
+		  c.player_id = ?
+		ORDER BY
+		  c.id DESC
+		"#,
+		steam_id,
+	}
+	.fetch_all(&state.database)
+	.await?
+	.into_iter()
+	.map(|row| PermissionChange {
+		admin: Player {
+			name: row.admin_name,
+			steam_id: row.admin_id,
+		},
+		added: row.added,
+		removed: row.removed,
+		created_on: row.created_on,
+	})
+	.collect::<Vec<_>>();
+
+	if changes.is_empty() {
+		return Err(Error::no_content());
+	}
+
+	Ok(Json(changes))
+}
+
+/// Grant permissions to an admin.
+///
+/// Permissions the admin already has are left untouched.
+///
+/// Requires a valid CSRF token in the `X-CSRF-Token` header.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  post,
+  path = "/admins/{steam_id}/permissions",
+  tag = "Admins",
+  security(("Browser Session" = ["admins"])),
+  params(SteamID),
+  request_body = PermissionsDelta,
+  responses(
+    responses::NoContent,
+    responses::BadRequest,
+    responses::Unauthorized,
+    responses::Forbidden,
+    responses::UnprocessableEntity,
+  ),
+)]
+pub async fn post(
+	state: State,
+	session: authentication::Session<authorization::HasPermissions<{ Permissions::ADMIN.value() }>>,
+	_csrf: CsrfToken,
+	Path(steam_id): Path<SteamID>,
+	Json(PermissionsDelta { permissions }): Json<PermissionsDelta>,
+) -> Result<NoContent> {
+	let mut transaction = state.transaction().await?;
+
+	admins::grant_permissions(
+		steam_id,
+		permissions,
+		session.user().steam_id(),
+		&mut transaction,
+	)
+	.await?;
+
+	transaction.commit().await?;
+
+	Ok(NoContent)
+}
+
+/// Revoke permissions from an admin.
+///
+/// Permissions not included in the request are left untouched.
+///
+/// Requires a valid CSRF token in the `X-CSRF-Token` header.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  delete,
+  path = "/admins/{steam_id}/permissions",
+  tag = "Admins",
+  security(("Browser Session" = ["admins"])),
+  params(SteamID),
+  request_body = PermissionsDelta,
+  responses(
+    responses::NoContent,
+    responses::BadRequest,
+    responses::Unauthorized,
+    responses::Forbidden,
+    responses::UnprocessableEntity,
+  ),
+)]
+pub async fn delete(
+	state: State,
+	session: authentication::Session<authorization::HasPermissions<{ Permissions::ADMIN.value() }>>,
+	_csrf: CsrfToken,
+	Path(steam_id): Path<SteamID>,
+	Json(PermissionsDelta { permissions }): Json<PermissionsDelta>,
+) -> Result<NoContent> {
+	let mut transaction = state.transaction().await?;
+
+	admins::revoke_permissions(
+		steam_id,
+		permissions,
+		session.user().steam_id(),
+		&mut transaction,
+	)
+	.await?;
+
+	transaction.commit().await?;
+
+	Ok(NoContent)
+}
@@ -88,5 +88,29 @@ pub async fn get(
 	Ok(Json(PaginationResponse {
 		total,
 		results: admins,
+		next_cursor: None,
 	}))
 }
+
+#[cfg(test)]
+mod tests {
+	use crate::admins::Admin;
+
+	#[crate::integration_test]
+	async fn admins_match_schema(ctx: &Context) {
+		let response = ctx.http_client.get(ctx.url("/admins")).send().await?;
+
+		assert_eq!(response.status(), 200);
+
+		let response = response.json::<serde_json::Value>().await?;
+		let admins = response["results"]
+			.as_array()
+			.context("`results` should be an array")?;
+
+		assert!(!admins.is_empty());
+
+		for admin in admins {
+			crate::test::validate_schema::<Admin>(admin)?;
+		}
+	}
+}
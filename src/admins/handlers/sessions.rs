@@ -0,0 +1,52 @@
+//! HTTP handlers for the `/admins/{steam_id}/sessions` routes.
+
+use axum::extract::Path;
+use cs2kz::SteamID;
+
+use crate::authentication::CsrfToken;
+use crate::authorization::{self, Permissions};
+use crate::openapi::responses;
+use crate::openapi::responses::NoContent;
+use crate::{authentication, Result, State};
+
+/// Invalidate all sessions of a specific user.
+///
+/// This will log the user out everywhere, e.g. if their account has been compromised.
+///
+/// Requires a valid CSRF token in the `X-CSRF-Token` header.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  delete,
+  path = "/admins/{steam_id}/sessions",
+  tag = "Admins",
+  security(("Browser Session" = ["admins"])),
+  params(SteamID),
+  responses(
+    responses::NoContent,
+    responses::BadRequest,
+    responses::Unauthorized,
+    responses::Forbidden,
+  ),
+)]
+pub async fn delete(
+	state: State,
+	session: authentication::Session<authorization::HasPermissions<{ Permissions::ADMIN.value() }>>,
+	_csrf: CsrfToken,
+	Path(steam_id): Path<SteamID>,
+) -> Result<NoContent> {
+	let mut transaction = state.transaction().await?;
+	let invalidated =
+		authentication::Session::invalidate_all_for(steam_id, &mut transaction).await?;
+
+	transaction.commit().await?;
+
+	tracing::info! {
+		target: "cs2kz_api::audit_log",
+		%steam_id,
+		invalidated,
+		admin.id = %session.user().steam_id(),
+		"invalidated all sessions for user",
+	};
+
+	Ok(NoContent)
+}
@@ -9,7 +9,10 @@ use crate::middleware::cors;
 use crate::{authorization, State};
 
 mod models;
-pub use models::{Admin, AdminUpdate};
+pub use models::{Admin, AdminUpdate, PermissionChange, PermissionsDelta};
+
+pub mod permissions;
+pub use permissions::{grant_permissions, revoke_permissions};
 
 pub mod handlers;
 
@@ -35,5 +38,29 @@ pub fn router(state: State) -> Router {
 		.route_layer(cors::dashboard([Method::PUT]))
 		.with_state(state.clone());
 
-	root.merge(by_id)
+	let sessions = Router::new()
+		.route(
+			"/:id/sessions",
+			routing::delete(handlers::sessions::delete).route_layer(auth()),
+		)
+		.route_layer(cors::dashboard([Method::DELETE]))
+		.with_state(state.clone());
+
+	let permissions = Router::new()
+		.route(
+			"/:id/permissions",
+			routing::get(handlers::permissions::get).route_layer(auth()),
+		)
+		.route(
+			"/:id/permissions",
+			routing::post(handlers::permissions::post).route_layer(auth()),
+		)
+		.route(
+			"/:id/permissions",
+			routing::delete(handlers::permissions::delete).route_layer(auth()),
+		)
+		.route_layer(cors::dashboard([Method::GET, Method::POST, Method::DELETE]))
+		.with_state(state.clone());
+
+	root.merge(by_id).merge(sessions).merge(permissions)
 }
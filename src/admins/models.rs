@@ -1,11 +1,13 @@
 //! Types for modeling KZ admins.
 
+use chrono::{DateTime, Utc};
 use cs2kz::SteamID;
 use derive_more::Debug;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
 use crate::authorization::Permissions;
+use crate::players::Player;
 
 /// A KZ admin.
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -30,3 +32,34 @@ pub struct AdminUpdate {
 	#[schema(value_type = Vec<String>, example = json!(["bans", "servers"]))]
 	pub permissions: Permissions,
 }
+
+/// Request payload for granting or revoking permissions.
+#[derive(Debug, Clone, Copy, Deserialize, ToSchema)]
+pub struct PermissionsDelta {
+	/// The permissions to grant or revoke.
+	///
+	/// Permissions not included here are left untouched.
+	#[debug("{permissions}")]
+	#[schema(value_type = Vec<String>, example = json!(["bans"]))]
+	pub permissions: Permissions,
+}
+
+/// A recorded change to someone's permissions.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PermissionChange {
+	/// The admin who made the change.
+	pub admin: Player,
+
+	/// Permissions that were added.
+	#[debug("{added}")]
+	#[schema(value_type = Vec<String>, example = json!(["bans"]))]
+	pub added: Permissions,
+
+	/// Permissions that were removed.
+	#[debug("{removed}")]
+	#[schema(value_type = Vec<String>, example = json!(["servers"]))]
+	pub removed: Permissions,
+
+	/// When the change was made.
+	pub created_on: DateTime<Utc>,
+}
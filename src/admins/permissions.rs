@@ -0,0 +1,134 @@
+//! Granting and revoking permissions.
+//!
+//! Every change to someone's permissions is recorded in the `PermissionChanges` table, together
+//! with the admin who made it. This way we can always tell who gave someone a specific
+//! permission.
+
+use cs2kz::SteamID;
+use sqlx::{MySql, Transaction};
+
+use crate::authorization::Permissions;
+use crate::{Error, Result};
+
+/// Grants `permissions` to `steam_id`, on behalf of `admin_id`.
+///
+/// Returns the new set of permissions.
+pub async fn grant_permissions(
+	steam_id: SteamID,
+	permissions: Permissions,
+	admin_id: SteamID,
+	transaction: &mut Transaction<'_, MySql>,
+) -> Result<Permissions> {
+	let current = fetch_permissions(steam_id, transaction).await?;
+	let new = current.union(permissions);
+
+	set_permissions(steam_id, current, new, admin_id, transaction).await?;
+
+	Ok(new)
+}
+
+/// Revokes `permissions` from `steam_id`, on behalf of `admin_id`.
+///
+/// Returns the new set of permissions.
+pub async fn revoke_permissions(
+	steam_id: SteamID,
+	permissions: Permissions,
+	admin_id: SteamID,
+	transaction: &mut Transaction<'_, MySql>,
+) -> Result<Permissions> {
+	let current = fetch_permissions(steam_id, transaction).await?;
+	let new = current.difference(permissions);
+
+	set_permissions(steam_id, current, new, admin_id, transaction).await?;
+
+	Ok(new)
+}
+
+/// Fetches the current permissions of `steam_id`, and locks the row until the transaction ends.
+pub(crate) async fn fetch_permissions(
+	steam_id: SteamID,
+	transaction: &mut Transaction<'_, MySql>,
+) -> Result<Permissions> {
+	sqlx::query_scalar! {
+		r#"
+		SELECT
+		  permissions `permissions: Permissions`
+		FROM
+		  Players
+		WHERE
+		  id = ?
+		FOR UPDATE
+		"#,
+		steam_id,
+	}
+	.fetch_optional(transaction.as_mut())
+	.await?
+	.ok_or_else(|| Error::not_found("admin"))
+}
+
+/// Records the difference between `old` and `new` as a change made by `admin_id`.
+///
+/// Nothing is recorded if the permissions did not change.
+pub(crate) async fn record_change(
+	steam_id: SteamID,
+	old: Permissions,
+	new: Permissions,
+	admin_id: SteamID,
+	transaction: &mut Transaction<'_, MySql>,
+) -> Result<()> {
+	let added = new.difference(old);
+	let removed = old.difference(new);
+
+	if added == Permissions::NONE && removed == Permissions::NONE {
+		return Ok(());
+	}
+
+	sqlx::query! {
+		r#"
+		INSERT INTO
+		  PermissionChanges (player_id, admin_id, added, removed)
+		VALUES
+		  (?, ?, ?, ?)
+		"#,
+		steam_id,
+		admin_id,
+		added,
+		removed,
+	}
+	.execute(transaction.as_mut())
+	.await?;
+
+	tracing::info! {
+		target: "cs2kz_api::audit_log",
+		%steam_id,
+		%admin_id,
+		%added,
+		%removed,
+		"updated permissions",
+	};
+
+	Ok(())
+}
+
+/// Updates the permissions of `steam_id` from `old` to `new`, and records the change.
+async fn set_permissions(
+	steam_id: SteamID,
+	old: Permissions,
+	new: Permissions,
+	admin_id: SteamID,
+	transaction: &mut Transaction<'_, MySql>,
+) -> Result<()> {
+	if old == new {
+		return Ok(());
+	}
+
+	sqlx::query!(
+		"UPDATE Players SET permissions = ? WHERE id = ?",
+		new,
+		steam_id
+	)
+	.execute(transaction.as_mut())
+	.await?;
+
+	record_change(steam_id, old, new, admin_id, transaction).await
+}
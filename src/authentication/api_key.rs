@@ -15,9 +15,15 @@ use uuid::Uuid;
 use crate::{Error, Result, State};
 
 /// An opaque API key.
-#[derive(Debug, Display, Clone, PartialEq, Eq, Hash, Into)]
+///
+/// The key itself is never included in the [`Debug`] or [`Display`] output, so it can't end up
+/// in logs by accident. Comparisons are done in constant time; see [`ApiKey::ct_eq()`].
+///
+/// [`Debug`]: std::fmt::Debug
+/// [`Display`]: std::fmt::Display
+#[derive(Debug, Display, Clone, Eq, Into)]
 #[debug("{name}")]
-#[display("{key} ({name})")]
+#[display("{name}")]
 pub struct ApiKey {
 	/// The key itself.
 	#[into]
@@ -32,6 +38,27 @@ impl ApiKey {
 	pub fn name(&self) -> &str {
 		&self.name
 	}
+
+	/// Compares two keys in constant time.
+	///
+	/// The time this takes does not depend on how many bytes of the two keys match, so it
+	/// can't be used to guess a key byte by byte.
+	pub fn ct_eq(&self, other: &Self) -> bool {
+		let diff = self
+			.key
+			.as_bytes()
+			.iter()
+			.zip(other.key.as_bytes())
+			.fold(0, |diff, (a, b)| diff | (a ^ b));
+
+		std::hint::black_box(diff) == 0
+	}
+}
+
+impl PartialEq for ApiKey {
+	fn eq(&self, other: &Self) -> bool {
+		self.ct_eq(other) && self.name == other.name
+	}
 }
 
 #[async_trait]
@@ -42,7 +69,7 @@ impl FromRequestParts<State> for ApiKey {
 		level = "debug",
 		name = "auth::api_key::from_request_parts",
 		skip_all,
-		fields(name = tracing::field::Empty, value = tracing::field::Empty),
+		fields(name = tracing::field::Empty),
 		err(level = "debug"),
 	)]
 	async fn from_request_parts(parts: &mut request::Parts, state: &State) -> Result<Self> {
@@ -75,9 +102,7 @@ impl FromRequestParts<State> for ApiKey {
 		})
 		.ok_or_else(|| Error::unauthorized())??;
 
-		tracing::Span::current()
-			.record("name", api_key.name())
-			.record("value", format_args!("{}", api_key.key));
+		tracing::Span::current().record("name", api_key.name());
 
 		tracing::debug!("authenticated API key");
 
@@ -0,0 +1,144 @@
+//! API tokens.
+//!
+//! These are long-lived, revocable tokens that users can create for third-party tools, like
+//! stat websites or bots. They are sent as a `Bearer` token in the `Authorization` header and
+//! accepted anywhere a [session] is, so tools can act on behalf of the user without access to
+//! their browser cookies.
+//!
+//! Every token has a set of scopes, which limit the permissions of the user while using the
+//! token. A token without any scopes is read-only, and can't be used for anything but `GET`
+//! requests.
+//!
+//! [session]: crate::authentication::Session
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use cs2kz::SteamID;
+use derive_more::Debug;
+use serde::{Deserialize, Serialize};
+use sqlx::{MySql, Transaction};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::authentication::User;
+use crate::authorization::Permissions;
+use crate::time::Seconds;
+use crate::{make_id, Error, Result};
+
+make_id!(ApiTokenID as u64);
+
+/// How long tokens are valid for if no explicit lifetime is requested.
+pub const DEFAULT_LIFETIME: Duration = Duration::from_secs(60 * 60 * 24 * 30);
+
+/// The maximum lifetime of a token.
+pub const MAX_LIFETIME: Duration = Duration::from_secs(60 * 60 * 24 * 90);
+
+/// An API token.
+///
+/// The token itself is only ever returned once, when it's created.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ApiToken {
+	/// The token's ID.
+	pub id: ApiTokenID,
+
+	/// The name the user gave the token.
+	pub name: String,
+
+	/// The permissions the token is limited to.
+	#[debug("{scopes}")]
+	#[schema(value_type = Vec<String>, example = json!(["bans"]))]
+	pub scopes: Permissions,
+
+	/// When the token was created.
+	pub created_on: DateTime<Utc>,
+
+	/// When the token expires.
+	pub expires_on: DateTime<Utc>,
+
+	/// When the token was last used.
+	pub last_used_on: Option<DateTime<Utc>>,
+}
+
+/// Request payload for creating a new API token.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct NewApiToken {
+	/// A name to identify the token by.
+	pub name: String,
+
+	/// The permissions the token should be limited to.
+	///
+	/// Only permissions the user has can be granted to the token. If this is empty, the token
+	/// is read-only.
+	#[serde(default)]
+	#[debug("{scopes}")]
+	#[schema(value_type = Vec<String>, example = json!(["bans"]))]
+	pub scopes: Permissions,
+
+	/// How many seconds the token should be valid for.
+	///
+	/// Defaults to 30 days, and can be at most 90 days.
+	pub expires_in: Option<Seconds>,
+}
+
+/// Response body for creating a new API token.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct CreatedApiToken {
+	/// The token's ID.
+	pub token_id: ApiTokenID,
+
+	/// The token itself.
+	///
+	/// This should be sent as a `Bearer` token in the `Authorization` header.
+	#[debug("*****")]
+	pub token: Uuid,
+
+	/// When the token expires.
+	pub expires_on: DateTime<Utc>,
+}
+
+/// Looks up the user associated with `token`.
+///
+/// The returned user's permissions are limited to the token's scopes. This also records when
+/// the token was last used.
+#[tracing::instrument(
+	level = "debug",
+	name = "auth::api_token::authenticate",
+	skip_all,
+	err(level = "debug")
+)]
+pub async fn authenticate(
+	token: Uuid,
+	transaction: &mut Transaction<'_, MySql>,
+) -> Result<(ApiTokenID, User)> {
+	let (token_id, user_id, permissions) = sqlx::query! {
+		r#"
+		SELECT
+		  t.id `id: ApiTokenID`,
+		  p.id `user_id: SteamID`,
+		  p.permissions `permissions: Permissions`,
+		  t.scopes `scopes: Permissions`
+		FROM
+		  ApiTokens t
+		  JOIN Players p ON p.id = t.player_id
+		WHERE
+		  t.token = ?
+		  AND t.expires_on > NOW()
+		  AND t.revoked_on IS NULL
+		"#,
+		token,
+	}
+	.fetch_optional(transaction.as_mut())
+	.await?
+	.map(|row| (row.id, row.user_id, row.permissions & row.scopes))
+	.ok_or_else(|| Error::unauthorized())?;
+
+	sqlx::query!(
+		"UPDATE ApiTokens SET last_used_on = NOW() WHERE id = ?",
+		token_id
+	)
+	.execute(transaction.as_mut())
+	.await?;
+
+	Ok((token_id, User::new(user_id, permissions)))
+}
@@ -0,0 +1,241 @@
+//! CSRF protection for session authenticated requests.
+//!
+//! [Sessions] are stored in cookies, which browsers attach to every request, including requests
+//! made by other websites. To protect state-changing endpoints against cross-site request
+//! forgery, we use the "double-submit cookie" pattern:
+//!
+//!    1. When a user logs in, they receive a random [`CsrfToken`] in a cookie that is readable
+//!       by JavaScript (see [`CsrfToken::to_cookie()`])
+//!    2. Every request to a protected endpoint must include the same token in the
+//!       [`X-CSRF-Token`][header] header
+//!    3. The [`CsrfToken`] extractor rejects any requests where the two don't match
+//!
+//! Other websites cannot read our cookies, so they cannot produce a matching header.
+//!
+//! The token is also stored alongside the session it was created for (see
+//! [`CsrfToken::create()`]), and rejected if it doesn't belong to the session in the request's
+//! [session cookie]. This way a token that was planted by e.g. a compromised subdomain, or one
+//! that outlived its session, is useless.
+//!
+//! Requests without a session cookie, like requests authenticated with [API tokens], can't be
+//! forged by browsers, so they only have to pass the double-submit check.
+//!
+//! [Sessions]: crate::authentication::session
+//! [header]: HEADER_NAME
+//! [session cookie]: crate::authentication::session::COOKIE_NAME
+//! [API tokens]: crate::authentication::api_token
+
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::request;
+use axum_extra::extract::cookie::Cookie;
+use axum_extra::extract::CookieJar;
+use derive_more::Debug;
+use sqlx::{MySql, Pool};
+use uuid::Uuid;
+
+use crate::authentication::session::{self, SessionID};
+use crate::{Error, Result, State};
+
+/// The HTTP cookie name that stores the user's [`CsrfToken`].
+pub const COOKIE_NAME: &str = "kz-csrf";
+
+/// The HTTP header that must contain the user's [`CsrfToken`].
+pub const HEADER_NAME: &str = "x-csrf-token";
+
+/// A CSRF token.
+///
+/// This type acts as an [extractor] that will reject requests which do not include a matching
+/// token in both the [cookie] and the [header].
+/// See [module level docs] for more details.
+///
+/// [extractor]: axum::extract
+/// [cookie]: COOKIE_NAME
+/// [header]: HEADER_NAME
+/// [module level docs]: crate::authentication::csrf
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[debug("*****")]
+pub struct CsrfToken(Uuid);
+
+impl CsrfToken {
+	/// Generates a new random [`CsrfToken`] for the given session.
+	///
+	/// NOTE: this stores the token in the database
+	#[tracing::instrument(level = "debug", name = "auth::csrf::create", skip(database))]
+	pub async fn create(session_id: SessionID, database: &Pool<MySql>) -> Result<Self> {
+		let token = Self(Uuid::new_v4());
+
+		sqlx::query!(
+			"UPDATE LoginSessions SET csrf_token = ? WHERE id = ?",
+			token.0,
+			session_id
+		)
+		.execute(database)
+		.await?;
+
+		Ok(token)
+	}
+
+	/// Serializes this token into an HTTP cookie.
+	///
+	/// The cookie is intentionally **not** `HttpOnly`, as the frontend needs to read it in
+	/// order to send it back in the [header].
+	///
+	/// [header]: HEADER_NAME
+	pub fn to_cookie(self, api_config: &crate::Config) -> Cookie<'static> {
+		Cookie::build((COOKIE_NAME, self.0.to_string()))
+			.domain(api_config.cookie_domain.clone())
+			.path("/")
+			.secure(cfg!(feature = "production"))
+			.http_only(false)
+			.permanent()
+			.build()
+	}
+
+	/// Creates a cookie that will remove the token cookie from the user's browser.
+	///
+	/// This is used when logging out.
+	pub fn removal_cookie(api_config: &crate::Config) -> Cookie<'static> {
+		Cookie::build((COOKIE_NAME, ""))
+			.domain(api_config.cookie_domain.clone())
+			.path("/")
+			.removal()
+			.build()
+	}
+}
+
+#[async_trait]
+impl FromRequestParts<State> for CsrfToken {
+	type Rejection = Error;
+
+	#[tracing::instrument(
+		level = "debug",
+		name = "auth::csrf::from_request_parts",
+		skip_all,
+		err(level = "debug")
+	)]
+	async fn from_request_parts(request: &mut request::Parts, state: &State) -> Result<Self> {
+		let cookies = CookieJar::from_headers(&request.headers);
+		let cookie = cookies
+			.get(COOKIE_NAME)
+			.map(|cookie| cookie.value().parse::<Uuid>())
+			.ok_or_else(|| Error::csrf_mismatch().context("missing cookie"))?
+			.map_err(|err| Error::csrf_mismatch().context(err))?;
+
+		let header = request
+			.headers
+			.get(HEADER_NAME)
+			.map(|value| {
+				value
+					.to_str()
+					.map_err(|err| Error::csrf_mismatch().context(err))?
+					.parse::<Uuid>()
+					.map_err(|err| Error::csrf_mismatch().context(err))
+			})
+			.ok_or_else(|| Error::csrf_mismatch().context("missing header"))??;
+
+		if cookie != header {
+			return Err(Error::csrf_mismatch());
+		}
+
+		let Some(session_id) = cookies.get(session::COOKIE_NAME) else {
+			return Ok(Self(cookie));
+		};
+
+		let session_id = session_id
+			.value()
+			.parse::<Uuid>()
+			.map_err(|err| Error::csrf_mismatch().context(err))?;
+
+		let belongs_to_session = sqlx::query! {
+			r#"
+			SELECT
+			  id
+			FROM
+			  LoginSessions
+			WHERE
+			  id = ?
+			  AND csrf_token = ?
+			  AND expires_on > NOW()
+			"#,
+			session_id,
+			cookie,
+		}
+		.fetch_optional(&state.database)
+		.await?
+		.is_some();
+
+		if !belongs_to_session {
+			return Err(Error::csrf_mismatch().context("token does not belong to session"));
+		}
+
+		Ok(Self(cookie))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use axum_extra::extract::cookie::Cookie;
+	use cs2kz::SteamID;
+	use reqwest::header;
+	use serde_json::json;
+
+	use crate::authentication::{csrf, CsrfToken};
+
+	#[crate::integration_test]
+	async fn tokens_are_bound_to_sessions(ctx: &Context) {
+		let alphakeks = SteamID::from_u64(76561198282622073_u64).unwrap();
+		let session = ctx.auth_session(alphakeks).await?;
+		let other_session = ctx.auth_session(alphakeks).await?;
+		let csrf_cookie = CsrfToken::create(other_session.id(), &ctx.database)
+			.await?
+			.to_cookie(&ctx.api_config);
+
+		let response = ctx
+			.http_client
+			.post(ctx.url("/auth/tokens"))
+			.header(
+				header::COOKIE,
+				format!(
+					"{}; {}",
+					Cookie::from(session).encoded(),
+					csrf_cookie.encoded(),
+				),
+			)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
+			.json(&json!({ "name": "stats website" }))
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 403);
+
+		let response = ctx
+			.http_client
+			.get(ctx.url("/auth/logout"))
+			.header(
+				header::COOKIE,
+				format!(
+					"{}; {}",
+					Cookie::from(other_session).encoded(),
+					csrf_cookie.encoded(),
+				),
+			)
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 200);
+
+		let removes_csrf_cookie = response
+			.headers()
+			.get_all(header::SET_COOKIE)
+			.iter()
+			.filter_map(|value| value.to_str().ok())
+			.filter_map(|value| Cookie::parse(value).ok())
+			.any(|cookie| cookie.name() == csrf::COOKIE_NAME && cookie.value().is_empty());
+
+		assert!(
+			removes_csrf_cookie,
+			"logging out should remove the CSRF cookie"
+		);
+	}
+}
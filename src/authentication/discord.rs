@@ -0,0 +1,193 @@
+//! Discord OAuth2 for linking Discord accounts.
+//!
+//! Logged-in users can link their Discord account to their KZ account. To make sure they
+//! actually own the Discord account, they have to authorize us through Discord's [OAuth2 flow]:
+//!
+//!    1. The user hits `/auth/discord/login` and gets redirected to Discord; a random
+//!       [`OAuthState`] is stored in a cookie and sent along with the redirect
+//!    2. Discord redirects back to `/auth/discord/callback` with an authorization code and the
+//!       same state
+//!    3. If the state matches the cookie, we exchange the code for an access token and use it to
+//!       fetch the user's Discord ID
+//!
+//! The state check prevents other websites from linking _their_ Discord account to the user's
+//! KZ account by sending them to the callback with a code of their own.
+//!
+//! [OAuth2 flow]: https://discord.com/developers/docs/topics/oauth2#authorization-code-grant
+
+use axum_extra::extract::cookie::Cookie;
+use derive_more::Debug;
+use reqwest::Response;
+use serde::{Deserialize, Serialize};
+use url::Url;
+use uuid::Uuid;
+
+use crate::{Config, Error, Result};
+
+/// The HTTP cookie name that stores the [`OAuthState`].
+pub const STATE_COOKIE_NAME: &str = "kz-discord-state";
+
+/// The HTTP cookie name that stores the URL to redirect the user back to after linking.
+pub const REDIRECT_COOKIE_NAME: &str = "kz-discord-redirect";
+
+/// The API route that Discord should redirect back to.
+const RETURN_ROUTE: &str = "/auth/discord/callback";
+
+/// Discord URL to redirect the user to for authorization.
+const AUTHORIZE_URL: &str = "https://discord.com/oauth2/authorize";
+
+/// Discord API URL for exchanging authorization codes for access tokens.
+const TOKEN_URL: &str = "https://discord.com/api/oauth2/token";
+
+/// Discord API URL for fetching the authorized user.
+const USER_URL: &str = "https://discord.com/api/users/@me";
+
+/// Discord application credentials.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+	/// The application's client ID.
+	pub client_id: String,
+
+	/// The application's client secret.
+	#[debug("*****")]
+	pub client_secret: String,
+}
+
+impl Credentials {
+	/// Returns the configured credentials.
+	///
+	/// If there are none, linking Discord accounts is unavailable.
+	pub fn from_config(config: &Config) -> Result<&Self> {
+		config
+			.discord_credentials
+			.as_ref()
+			.ok_or_else(|| Error::not_configured("discord integration"))
+	}
+}
+
+/// Random value used to tie an OAuth2 callback to the login request that started it.
+///
+/// See [module level docs] for more details.
+///
+/// [module level docs]: crate::authentication::discord
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[debug("*****")]
+#[serde(transparent)]
+pub struct OAuthState(Uuid);
+
+impl OAuthState {
+	/// Generates a new random [`OAuthState`].
+	pub fn new() -> Self {
+		Self(Uuid::new_v4())
+	}
+
+	/// Parses an [`OAuthState`] from the value of a [cookie].
+	///
+	/// [cookie]: STATE_COOKIE_NAME
+	pub fn from_cookie(cookie: &Cookie<'_>) -> Option<Self> {
+		cookie.value().parse().map(Self).ok()
+	}
+
+	/// Serializes this state into an HTTP cookie.
+	pub fn to_cookie(self, api_config: &Config) -> Cookie<'static> {
+		short_lived_cookie(STATE_COOKIE_NAME, self.0.to_string(), api_config)
+	}
+}
+
+/// Creates a cookie that only lives for the duration of the OAuth2 flow.
+pub fn short_lived_cookie(
+	name: &'static str,
+	value: String,
+	api_config: &Config,
+) -> Cookie<'static> {
+	Cookie::build((name, value))
+		.domain(api_config.cookie_domain.clone())
+		.path("/auth/discord")
+		.secure(cfg!(feature = "production"))
+		.http_only(true)
+		.max_age(time::Duration::minutes(10))
+		.build()
+}
+
+/// Returns the URL to redirect the user to so they can authorize us.
+pub fn authorize_url(credentials: &Credentials, public_url: &Url, state: OAuthState) -> Url {
+	let mut url = Url::parse(AUTHORIZE_URL).expect("this is a valid url");
+
+	url.query_pairs_mut()
+		.append_pair("response_type", "code")
+		.append_pair("client_id", &credentials.client_id)
+		.append_pair("scope", "identify")
+		.append_pair("state", &state.0.to_string())
+		.append_pair("redirect_uri", return_url(public_url).as_str())
+		.append_pair("prompt", "consent");
+
+	url
+}
+
+/// Exchanges an authorization `code` for an access token, and fetches the Discord ID of the user
+/// who authorized it.
+#[tracing::instrument(level = "debug", name = "auth::discord::fetch_user_id", skip_all, err)]
+pub async fn fetch_user_id(
+	code: &str,
+	credentials: &Credentials,
+	public_url: &Url,
+	http_client: &reqwest::Client,
+) -> Result<u64> {
+	#[derive(Serialize)]
+	#[allow(clippy::missing_docs_in_private_items)]
+	struct TokenRequest<'a> {
+		grant_type: &'static str,
+		code: &'a str,
+		redirect_uri: Url,
+		client_id: &'a str,
+		client_secret: &'a str,
+	}
+
+	#[derive(Deserialize)]
+	#[allow(clippy::missing_docs_in_private_items)]
+	struct TokenResponse {
+		access_token: String,
+	}
+
+	#[derive(Deserialize)]
+	#[allow(clippy::missing_docs_in_private_items)]
+	struct DiscordUser {
+		id: String,
+	}
+
+	let TokenResponse { access_token } = http_client
+		.post(TOKEN_URL)
+		.form(&TokenRequest {
+			grant_type: "authorization_code",
+			code,
+			redirect_uri: return_url(public_url),
+			client_id: &credentials.client_id,
+			client_secret: &credentials.client_secret,
+		})
+		.send()
+		.await
+		.and_then(Response::error_for_status)
+		.map_err(Error::external_api_call)?
+		.json()
+		.await
+		.map_err(Error::external_api_call)?;
+
+	let DiscordUser { id } = http_client
+		.get(USER_URL)
+		.bearer_auth(access_token)
+		.send()
+		.await
+		.and_then(Response::error_for_status)
+		.map_err(Error::external_api_call)?
+		.json()
+		.await
+		.map_err(Error::external_api_call)?;
+
+	id.parse::<u64>()
+		.map_err(|err| Error::logic("discord returned an invalid user id").context(err))
+}
+
+/// The full URL of the [`RETURN_ROUTE`].
+fn return_url(public_url: &Url) -> Url {
+	public_url.join(RETURN_ROUTE).expect("this is valid")
+}
@@ -0,0 +1,162 @@
+//! HTTP handlers for the `/auth/discord` routes.
+
+use axum::extract::Query;
+use axum::response::Redirect;
+use axum_extra::extract::CookieJar;
+use serde::Deserialize;
+use url::Url;
+use utoipa::IntoParams;
+
+use crate::authentication::discord::{self, Credentials, OAuthState};
+use crate::authentication::Session;
+use crate::openapi::responses;
+use crate::sqlx::SqlErrorExt;
+use crate::{Error, Result, State};
+
+/// Query parameters for the Discord login endpoint.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct LoginParams {
+	/// URL to redirect the user back to after their Discord account has been linked.
+	redirect_to: Url,
+}
+
+/// Link a Discord account.
+///
+/// This will redirect the user to Discord, where they can authorize the API to read their
+/// Discord ID. After that, they're redirected back to `redirect_to`.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  get,
+  path = "/auth/discord/login",
+  tag = "Auth",
+  security(("Browser Session" = [])),
+  params(LoginParams),
+  responses(
+    responses::SeeOther,
+    responses::BadRequest,
+    responses::Unauthorized,
+    responses::ServiceUnavailable,
+  ),
+)]
+pub async fn login(
+	state: State,
+	session: Session,
+	cookies: CookieJar,
+	Query(LoginParams { redirect_to }): Query<LoginParams>,
+) -> Result<(CookieJar, Redirect)> {
+	let credentials = Credentials::from_config(&state.config)?;
+	let oauth_state = OAuthState::new();
+	let url = discord::authorize_url(credentials, &state.config.public_url, oauth_state);
+	let redirect_cookie = discord::short_lived_cookie(
+		discord::REDIRECT_COOKIE_NAME,
+		redirect_to.to_string(),
+		&state.config,
+	);
+
+	let cookies = cookies
+		.add(oauth_state.to_cookie(&state.config))
+		.add(redirect_cookie);
+
+	Ok((cookies, Redirect::to(url.as_str())))
+}
+
+/// Query parameters sent by Discord after a successful authorization.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct CallbackParams {
+	/// The authorization code.
+	code: String,
+
+	/// The state we sent to Discord in the login redirect.
+	#[param(value_type = String)]
+	state: OAuthState,
+}
+
+/// The endpoint hit by Discord after a successful authorization.
+///
+/// This should not be used directly, and trying to do so will lead to errors.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  get,
+  path = "/auth/discord/callback",
+  tag = "Auth",
+  security(("Browser Session" = [])),
+  params(CallbackParams),
+  responses(
+    responses::SeeOther,
+    responses::BadRequest,
+    responses::Unauthorized,
+    responses::Conflict,
+    responses::BadGateway,
+    responses::ServiceUnavailable,
+  ),
+)]
+pub async fn callback(
+	state: State,
+	session: Session,
+	cookies: CookieJar,
+	Query(CallbackParams {
+		code,
+		state: oauth_state,
+	}): Query<CallbackParams>,
+) -> Result<(CookieJar, Redirect)> {
+	let expected_state = cookies
+		.get(discord::STATE_COOKIE_NAME)
+		.and_then(OAuthState::from_cookie)
+		.ok_or_else(|| Error::csrf_mismatch())?;
+
+	if oauth_state != expected_state {
+		return Err(Error::csrf_mismatch());
+	}
+
+	let redirect_to = cookies
+		.get(discord::REDIRECT_COOKIE_NAME)
+		.and_then(|cookie| cookie.value().parse::<Url>().ok())
+		.unwrap_or_else(|| state.config.public_url.clone());
+
+	let credentials = Credentials::from_config(&state.config)?;
+	let discord_id = discord::fetch_user_id(
+		&code,
+		credentials,
+		&state.config.public_url,
+		&state.http_client,
+	)
+	.await?;
+
+	let steam_id = session.user().steam_id();
+
+	sqlx::query!(
+		"UPDATE Players SET discord_id = ? WHERE id = ?",
+		discord_id,
+		steam_id
+	)
+	.execute(&state.database)
+	.await
+	.map_err(|err| {
+		if err.is_duplicate_entry() {
+			Error::already_exists("link for this discord account").context(err)
+		} else {
+			Error::from(err)
+		}
+	})?;
+
+	tracing::info! {
+		target: "cs2kz_api::audit_log",
+		%steam_id,
+		%discord_id,
+		"linked discord account",
+	};
+
+	let cookies = cookies
+		.remove(discord::short_lived_cookie(
+			discord::STATE_COOKIE_NAME,
+			String::new(),
+			&state.config,
+		))
+		.remove(discord::short_lived_cookie(
+			discord::REDIRECT_COOKIE_NAME,
+			String::new(),
+			&state.config,
+		));
+
+	Ok((cookies, Redirect::to(redirect_to.as_str())))
+}
@@ -6,14 +6,22 @@ use authentication::Session;
 use axum::extract::{ConnectInfo, Query};
 use axum::http::StatusCode;
 use axum::response::Redirect;
+use axum::Json;
 use axum_extra::extract::CookieJar;
-use serde::Deserialize;
+use cs2kz::SteamID;
+use serde::{Deserialize, Serialize};
 use url::Url;
-use utoipa::IntoParams;
+use utoipa::{IntoParams, ToSchema};
 
+use crate::authentication::CsrfToken;
+use crate::authorization::Permissions;
 use crate::openapi::responses;
+use crate::servers::ServerID;
 use crate::{authentication, steam, Result, State};
 
+pub mod discord;
+pub mod tokens;
+
 /// Query parameters for the login endpoint.
 #[derive(Debug, Deserialize, IntoParams)]
 pub struct LoginParams {
@@ -71,10 +79,11 @@ pub struct LogoutParams {
 pub async fn logout(
 	state: State,
 	mut session: Session,
+	cookies: CookieJar,
 	Query(LogoutParams {
 		invalidate_all_sessions,
 	}): Query<LogoutParams>,
-) -> Result<(Session, StatusCode)> {
+) -> Result<(Session, CookieJar, StatusCode)> {
 	let mut transaction = state.transaction().await?;
 
 	session
@@ -83,9 +92,11 @@ pub async fn logout(
 
 	transaction.commit().await?;
 
+	let cookies = cookies.add(CsrfToken::removal_cookie(&state.config));
+
 	tracing::debug!("user logged out");
 
-	Ok((session, StatusCode::OK))
+	Ok((session, cookies, StatusCode::OK))
 }
 
 /// The endpoint hit by Steam after a successful login.
@@ -112,11 +123,133 @@ pub async fn callback(
 ) -> Result<(CookieJar, Redirect)> {
 	let transaction = state.transaction().await?;
 	let session = Session::create(&user, req_addr.ip(), &state.config, transaction).await?;
+	let csrf_token = CsrfToken::create(session.id(), &state.database).await?;
 	let user_cookie = user.to_cookie(&state.config);
-	let cookies = cookies.add(session).add(user_cookie);
+	let csrf_cookie = csrf_token.to_cookie(&state.config);
+	let cookies = cookies.add(session).add(user_cookie).add(csrf_cookie);
 	let redirect = Redirect::to(login.redirect_to.as_str());
 
 	tracing::debug!("user logged in");
 
 	Ok((cookies, redirect))
 }
+
+/// Response body for the "who am I" endpoint.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CurrentUser {
+	/// The user's name.
+	pub name: String,
+
+	/// The user's SteamID.
+	pub steam_id: SteamID,
+
+	/// The user's permissions.
+	#[schema(value_type = Vec<String>, example = json!(["bans", "servers"]))]
+	pub permissions: Permissions,
+
+	/// IDs of the servers owned by the user.
+	pub owned_servers: Vec<ServerID>,
+
+	/// Whether the user is a mapper of at least one map.
+	pub is_mapper: bool,
+}
+
+/// Fetch information about the currently logged in user.
+///
+/// This is meant for websites to figure out what to show the user, without having to make a
+/// separate request for everything.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  get,
+  path = "/auth/me",
+  tag = "Auth",
+  security(("Browser Session" = [])),
+  responses(
+    responses::Ok<CurrentUser>,
+    responses::Unauthorized,
+  ),
+)]
+pub async fn me(state: State, session: Session) -> Result<Json<CurrentUser>> {
+	let user = session.user();
+	let steam_id = user.steam_id();
+
+	let name = sqlx::query_scalar!("SELECT name FROM Players WHERE id = ?", steam_id)
+		.fetch_one(&state.database)
+		.await?;
+
+	let owned_servers = sqlx::query_scalar! {
+		r#"
+		SELECT
+		  id `id: ServerID`
+		FROM
+		  Servers
+		WHERE
+		  owner_id = ?
+		ORDER BY
+		  id
+		"#,
+		steam_id,
+	}
+	.fetch_all(&state.database)
+	.await?;
+
+	let mapped_maps = sqlx::query_scalar! {
+		r#"
+		SELECT
+		  CAST(COUNT(map_id) AS UNSIGNED) `mapped_maps!: u64`
+		FROM
+		  Mappers
+		WHERE
+		  player_id = ?
+		"#,
+		steam_id,
+	}
+	.fetch_one(&state.database)
+	.await?;
+
+	Ok(Json(CurrentUser {
+		name,
+		steam_id,
+		permissions: user.permissions(),
+		owned_servers,
+		is_mapper: mapped_maps > 0,
+	}))
+}
+
+#[cfg(test)]
+mod tests {
+	use axum_extra::extract::cookie::Cookie;
+	use cs2kz::SteamID;
+	use reqwest::header;
+
+	use super::CurrentUser;
+	use crate::authorization::Permissions;
+
+	#[crate::integration_test]
+	async fn me(ctx: &Context) {
+		let response = ctx.http_client.get(ctx.url("/auth/me")).send().await?;
+
+		assert_eq!(response.status(), 401);
+
+		let alphakeks = SteamID::from_u64(76561198282622073_u64).unwrap();
+		let session = ctx.auth_session(alphakeks).await?;
+		let session_cookie = Cookie::from(session).encoded().to_string();
+
+		let response = ctx
+			.http_client
+			.get(ctx.url("/auth/me"))
+			.header(header::COOKIE, session_cookie)
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 200);
+
+		let me = response.json::<CurrentUser>().await?;
+
+		assert_eq!(me.name, "AlphaKeks");
+		assert_eq!(me.steam_id, alphakeks);
+		assert!(me.permissions.contains(Permissions::MAPS));
+		assert_eq!(me.owned_servers.len(), 1);
+		assert!(!me.is_mapper);
+	}
+}
@@ -0,0 +1,287 @@
+//! HTTP handlers for the `/auth/tokens` routes.
+
+use axum::extract::Path;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::authentication::api_token::{self, ApiToken, ApiTokenID, CreatedApiToken, NewApiToken};
+use crate::authentication::{CsrfToken, Session};
+use crate::authorization::Permissions;
+use crate::openapi::responses;
+use crate::openapi::responses::{Created, NoContent};
+use crate::{Error, Result, State};
+
+/// Fetch your API tokens.
+///
+/// Expired and revoked tokens are not included.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  get,
+  path = "/auth/tokens",
+  tag = "Auth",
+  security(("Browser Session" = [])),
+  responses(
+    responses::OkList<ApiToken>,
+    responses::Unauthorized,
+  ),
+)]
+pub async fn get(state: State, session: Session) -> Result<Json<Vec<ApiToken>>> {
+	let tokens = sqlx::query_as! {
+		ApiToken,
+		r#"
+		SELECT
+		  id `id: ApiTokenID`,
+		  name,
+		  scopes `scopes: Permissions`,
+		  created_on `created_on: DateTime<Utc>`,
+		  expires_on `expires_on: DateTime<Utc>`,
+		  last_used_on `last_used_on: DateTime<Utc>`
+		FROM
+		  ApiTokens
+		WHERE
+		  player_id = ?
+		  AND expires_on > NOW()
+		  AND revoked_on IS NULL
+		ORDER BY
+		  id DESC
+		"#,
+		session.user().steam_id(),
+	}
+	.fetch_all(&state.database)
+	.await?;
+
+	Ok(Json(tokens))
+}
+
+/// Create a new API token.
+///
+/// The token can be used in place of a session cookie by sending it as a `Bearer` token in the
+/// `Authorization` header. Tokens can't be used to create other tokens.
+///
+/// Requires a valid CSRF token in the `X-CSRF-Token` header.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  post,
+  path = "/auth/tokens",
+  tag = "Auth",
+  security(("Browser Session" = [])),
+  request_body = NewApiToken,
+  responses(
+    responses::Created<CreatedApiToken>,
+    responses::BadRequest,
+    responses::Unauthorized,
+    responses::Forbidden,
+  ),
+)]
+pub async fn post(
+	state: State,
+	session: Session,
+	_csrf: CsrfToken,
+	Json(NewApiToken {
+		name,
+		scopes,
+		expires_in,
+	}): Json<NewApiToken>,
+) -> Result<Created<Json<CreatedApiToken>>> {
+	if session.api_token().is_some() {
+		return Err(Error::unauthorized().context("API tokens cannot create other tokens"));
+	}
+
+	let user = session.user();
+
+	if !user.permissions().contains(scopes) {
+		return Err(Error::insufficient_permissions(scopes));
+	}
+
+	if name.is_empty() {
+		return Err(Error::invalid("name").context("token name cannot be empty"));
+	}
+
+	let lifetime = expires_in.map_or(api_token::DEFAULT_LIFETIME, |seconds| seconds.0);
+
+	if lifetime.is_zero() || lifetime > api_token::MAX_LIFETIME {
+		return Err(Error::invalid("expires_in").context(format!(
+			"token lifetime must be between 0 and {} seconds",
+			api_token::MAX_LIFETIME.as_secs(),
+		)));
+	}
+
+	let token = Uuid::new_v4();
+	let expires_on =
+		Utc::now() + chrono::Duration::from_std(lifetime).expect("lifetime is at most 90 days");
+
+	let token_id: ApiTokenID = sqlx::query! {
+		r#"
+		INSERT INTO
+		  ApiTokens (token, player_id, name, scopes, expires_on)
+		VALUES
+		  (?, ?, ?, ?, ?)
+		"#,
+		token,
+		user.steam_id(),
+		name,
+		scopes,
+		expires_on,
+	}
+	.execute(&state.database)
+	.await?
+	.last_insert_id()
+	.into();
+
+	tracing::info! {
+		target: "cs2kz_api::audit_log",
+		%token_id,
+		user.id = %user.steam_id(),
+		%name,
+		%scopes,
+		%expires_on,
+		"created API token",
+	};
+
+	Ok(Created(Json(CreatedApiToken {
+		token_id,
+		token,
+		expires_on,
+	})))
+}
+
+/// Revoke one of your API tokens.
+///
+/// Requires a valid CSRF token in the `X-CSRF-Token` header.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  delete,
+  path = "/auth/tokens/{token_id}",
+  tag = "Auth",
+  security(("Browser Session" = [])),
+  params(("token_id" = u64, Path, description = "The token's ID")),
+  responses(
+    responses::NoContent,
+    responses::BadRequest,
+    responses::Unauthorized,
+    responses::Forbidden,
+  ),
+)]
+pub async fn delete(
+	state: State,
+	session: Session,
+	_csrf: CsrfToken,
+	Path(token_id): Path<ApiTokenID>,
+) -> Result<NoContent> {
+	if session.api_token().is_some() {
+		return Err(Error::unauthorized().context("API tokens cannot revoke tokens"));
+	}
+
+	let user_id = session.user().steam_id();
+	let query_result = sqlx::query! {
+		r#"
+		UPDATE
+		  ApiTokens
+		SET
+		  revoked_on = NOW()
+		WHERE
+		  id = ?
+		  AND player_id = ?
+		  AND revoked_on IS NULL
+		"#,
+		token_id,
+		user_id,
+	}
+	.execute(&state.database)
+	.await?;
+
+	if query_result.rows_affected() == 0 {
+		return Err(Error::not_found("token"));
+	}
+
+	tracing::info! {
+		target: "cs2kz_api::audit_log",
+		%token_id,
+		user.id = %user_id,
+		"revoked API token",
+	};
+
+	Ok(NoContent)
+}
+
+#[cfg(test)]
+mod tests {
+	use axum_extra::extract::cookie::Cookie;
+	use cs2kz::SteamID;
+	use reqwest::header;
+	use serde_json::json;
+
+	use crate::authentication::api_token::CreatedApiToken;
+	use crate::authentication::{csrf, CsrfToken};
+
+	#[crate::integration_test]
+	async fn read_only_tokens(ctx: &Context) {
+		let alphakeks = SteamID::from_u64(76561198282622073_u64).unwrap();
+		let session = ctx.auth_session(alphakeks).await?;
+		let csrf_cookie = CsrfToken::create(session.id(), &ctx.database)
+			.await?
+			.to_cookie(&ctx.api_config);
+		let csrf_token = csrf_cookie.value().to_owned();
+		let cookies = format!(
+			"{}; {}",
+			Cookie::from(session).encoded(),
+			csrf_cookie.encoded(),
+		);
+
+		let response = ctx
+			.http_client
+			.post(ctx.url("/auth/tokens"))
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, &csrf_token)
+			.json(&json!({ "name": "stats website" }))
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 201);
+
+		let CreatedApiToken {
+			token_id, token, ..
+		} = response.json().await?;
+		let bearer = format!("Bearer {token}");
+
+		let response = ctx
+			.http_client
+			.get(ctx.url("/auth/me"))
+			.header(header::AUTHORIZATION, &bearer)
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 200);
+
+		// read-only tokens can't do anything other than `GET` requests
+		let response = ctx
+			.http_client
+			.post(ctx.url("/auth/tokens"))
+			.header(header::AUTHORIZATION, &bearer)
+			.json(&json!({ "name": "another one" }))
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 401);
+
+		let response = ctx
+			.http_client
+			.delete(ctx.url(format_args!("/auth/tokens/{token_id}")))
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, &csrf_token)
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 204);
+
+		let response = ctx
+			.http_client
+			.get(ctx.url("/auth/me"))
+			.header(header::AUTHORIZATION, &bearer)
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 401);
+	}
+}
@@ -1,12 +1,12 @@
 //! Everything related to authentication.
 //!
 //! This module contains types, traits, and HTTP handlers related to authentication.
-//! This includes JWT, sessions, and opaque API keys.
+//! This includes JWT, sessions, CSRF tokens, and opaque API keys.
 
 use axum::http::Method;
 use axum::{routing, Router};
 
-use crate::middleware::cors;
+use crate::middleware::{cors, rate_limit};
 use crate::State;
 
 mod jwt;
@@ -21,11 +21,18 @@ pub use session::Session;
 pub mod api_key;
 pub use api_key::ApiKey;
 
+pub mod api_token;
+
+pub mod csrf;
+pub use csrf::CsrfToken;
+
 mod user;
 pub use user::User;
 
 pub mod steam;
 
+pub mod discord;
+
 pub mod handlers;
 
 /// Returns a [Router] with all the `/auth` handlers.
@@ -35,10 +42,40 @@ pub fn router(state: State) -> Router {
 		.route_layer(cors::dashboard([Method::GET]))
 		.with_state(state.clone());
 
+	let discord = Router::new()
+		.route("/discord/login", routing::get(handlers::discord::login))
+		.route(
+			"/discord/callback",
+			routing::get(handlers::discord::callback),
+		)
+		.route_layer(cors::dashboard([Method::GET]))
+		.with_state(state.clone());
+
+	let me = Router::new()
+		.route("/me", routing::get(handlers::me))
+		.route_layer(cors::dashboard([Method::GET]))
+		.with_state(state.clone());
+
+	let tokens = Router::new()
+		.route(
+			"/tokens",
+			routing::get(handlers::tokens::get).post(handlers::tokens::post),
+		)
+		.route("/tokens/:id", routing::delete(handlers::tokens::delete))
+		.route_layer(cors::dashboard([Method::GET, Method::POST, Method::DELETE]))
+		.with_state(state.clone());
+
 	Router::new()
 		.route("/login", routing::get(handlers::login))
 		.route("/callback", routing::get(handlers::callback))
 		.route_layer(cors::permissive())
 		.with_state(state.clone())
 		.merge(logout)
+		.merge(discord)
+		.layer(rate_limit::layer!(
+			state.config.auth_rate_limit_per_minute,
+			state.clone(),
+		))
+		.merge(me)
+		.merge(tokens)
 }
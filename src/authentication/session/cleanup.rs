@@ -0,0 +1,64 @@
+//! Periodic cleanup of expired sessions.
+//!
+//! Sessions are never deleted when they expire or get [invalidated]; only their expiration date
+//! is updated. To keep the `LoginSessions` table from growing forever, a background task spawned
+//! by [`spawn()`] regularly deletes rows that have expired.
+//!
+//! [invalidated]: super::Session::invalidate
+
+use std::time::Duration;
+
+use sqlx::{MySql, Pool};
+use tokio::task;
+
+/// How often the cleanup task runs.
+const INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How many sessions are deleted per query.
+///
+/// Deleting in batches keeps us from locking the table for too long if there are a lot of
+/// expired sessions.
+const BATCH_SIZE: u64 = 1000;
+
+/// Spawns a background task that periodically deletes expired sessions from the database.
+pub fn spawn(database: Pool<MySql>) -> task::JoinHandle<()> {
+	task::spawn(async move {
+		let mut interval = tokio::time::interval(INTERVAL);
+
+		loop {
+			interval.tick().await;
+
+			if let Err(error) = delete_expired(&database).await {
+				tracing::error!(%error, "failed to delete expired sessions");
+			}
+		}
+	})
+}
+
+/// Deletes all expired sessions from the database, in batches of [`BATCH_SIZE`].
+///
+/// Returns the total amount of deleted sessions.
+#[tracing::instrument(level = "debug", name = "auth::session::cleanup", skip_all, err)]
+pub async fn delete_expired(database: &Pool<MySql>) -> sqlx::Result<u64> {
+	let mut total = 0;
+
+	loop {
+		let deleted = sqlx::query!(
+			"DELETE FROM LoginSessions WHERE expires_on <= NOW() LIMIT ?",
+			BATCH_SIZE
+		)
+		.execute(database)
+		.await?
+		.rows_affected();
+
+		total += deleted;
+
+		if deleted < BATCH_SIZE {
+			break;
+		}
+	}
+
+	tracing::debug!(total, "deleted expired sessions");
+
+	Ok(total)
+}
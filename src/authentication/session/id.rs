@@ -19,6 +19,11 @@ use uuid::Uuid;
 pub struct SessionID(Uuid);
 
 impl SessionID {
+	/// The ID of sessions created from [API tokens], which aren't stored in the database.
+	///
+	/// [API tokens]: crate::authentication::api_token
+	pub const NIL: Self = Self(Uuid::nil());
+
 	/// Generates a new [`SessionID`].
 	pub fn new() -> Self {
 		Self(Uuid::new_v4())
@@ -26,17 +26,32 @@
 //! [`Session::invalidate()`] before returning it in the response. This will set the session's
 //! expiration date to "now" both in the database and the cookie that will be returned to the user.
 //!
+//! Sessions of other users can be invalidated using [`Session::invalidate_all_for()`].
+//!
+//! # API Tokens
+//!
+//! If a request does not contain a session cookie, but an [API token] in the `Authorization`
+//! header, a [`Session`] is created from that instead. These sessions are not stored in the
+//! database and are never sent back as cookies; their user's permissions are limited to the
+//! token's scopes.
+//!
+//! # Expired Sessions
+//!
+//! Expired sessions are periodically deleted from the database by a background task; see
+//! [`cleanup`].
+//!
 //! [extractor]: axum::extract
 //! [session ID]: SessionID
 //! [cookie]: COOKIE_NAME
 //! [user]: User
 //! [logout]: crate::authentication::handlers::logout
+//! [API token]: crate::authentication::api_token
 
 use std::marker::PhantomData;
 use std::net::IpAddr;
 
 use axum::extract::FromRequestParts;
-use axum::http::{header, request};
+use axum::http::{header, request, Method};
 use axum::response::{IntoResponseParts, ResponseParts};
 use axum::{async_trait, http};
 use axum_extra::extract::cookie::Cookie;
@@ -46,6 +61,7 @@ use sqlx::{MySql, Transaction};
 use time::OffsetDateTime;
 use uuid::Uuid;
 
+use crate::authentication::api_token::{self, ApiTokenID};
 use crate::authentication::User;
 use crate::authorization::{self, AuthorizeSession, Permissions};
 use crate::sqlx::SqlErrorExt;
@@ -54,6 +70,8 @@ use crate::{steam, Error, Result, State};
 mod id;
 pub use id::SessionID;
 
+pub mod cleanup;
+
 /// The HTTP cookie name that stores the user's [session ID].
 ///
 /// [session ID]: SessionID
@@ -82,6 +100,9 @@ pub struct Session<A = authorization::None> {
 	#[into]
 	cookie: Cookie<'static>,
 
+	/// The API token this session was created from, if it wasn't extracted from a cookie.
+	api_token: Option<ApiTokenID>,
+
 	/// Marker to tie an authorization method to any given [`Session`] without actually storing
 	/// anything.
 	#[debug(skip)]
@@ -99,6 +120,11 @@ impl<A> Session<A> {
 		self.user
 	}
 
+	/// Returns the ID of the API token this session was created from, if any.
+	pub const fn api_token(&self) -> Option<ApiTokenID> {
+		self.api_token
+	}
+
 	/// Generates a new expiration date for any given session.
 	fn expires_on() -> OffsetDateTime {
 		OffsetDateTime::now_utc() + time::Duration::WEEK
@@ -223,9 +249,26 @@ impl Session {
 			id: session_id,
 			user,
 			cookie,
+			api_token: None,
 			_authorization: PhantomData,
 		})
 	}
+
+	/// Invalidates every (still valid) session associated with the given user.
+	///
+	/// This is used to force-logout a user, e.g. if their account has been compromised.
+	/// Returns the amount of sessions that were invalidated.
+	#[tracing::instrument(level = "debug", name = "auth::session::revoke_user", skip(database))]
+	pub async fn invalidate_all_for(
+		user_id: SteamID,
+		database: &mut Transaction<'_, MySql>,
+	) -> Result<u64> {
+		let invalidated = expire_sessions(user_id, None, database).await?;
+
+		tracing::debug!(user.id = %user_id, invalidated, "invalidated all sessions for user");
+
+		Ok(invalidated)
+	}
 }
 
 impl<A> Session<A>
@@ -239,32 +282,22 @@ where
 	///
 	/// If `invalid_all` is `true`, **every** session in the database associated with this
 	/// session's user will be invalidated.
+	///
+	/// Sessions created from API tokens can't be invalidated; the token has to be revoked
+	/// instead.
 	#[tracing::instrument(level = "debug", name = "auth::session::logout", skip(database))]
 	pub async fn invalidate(
 		&mut self,
 		invalidate_all: bool,
 		database: &mut Transaction<'_, MySql>,
 	) -> Result<()> {
-		sqlx::query! {
-			r#"
-			UPDATE
-			  LoginSessions
-			SET
-			  expires_on = NOW()
-			WHERE
-			  player_id = ?
-			  AND expires_on > NOW()
-			  AND (
-			    id = ?
-			    OR ?
-			  )
-			"#,
-			self.user.steam_id(),
-			self.id,
-			invalidate_all,
+		if self.api_token.is_some() {
+			return Err(Error::unauthorized().context("cannot invalidate API token sessions"));
 		}
-		.execute(database.as_mut())
-		.await?;
+
+		let session_id = (!invalidate_all).then_some(self.id);
+
+		expire_sessions(self.user.steam_id(), session_id, database).await?;
 
 		self.cookie.set_expires(OffsetDateTime::now_utc());
 
@@ -281,6 +314,40 @@ where
 	}
 }
 
+/// Sets the expiration date of `user_id`'s sessions to "now".
+///
+/// If `session_id` is specified, only that session is expired; otherwise every session of the
+/// user is. Returns the amount of sessions that were expired.
+async fn expire_sessions(
+	user_id: SteamID,
+	session_id: Option<SessionID>,
+	transaction: &mut Transaction<'_, MySql>,
+) -> Result<u64> {
+	let expired = sqlx::query! {
+		r#"
+		UPDATE
+		  LoginSessions
+		SET
+		  expires_on = NOW()
+		WHERE
+		  player_id = ?
+		  AND expires_on > NOW()
+		  AND (
+		    id = ?
+		    OR ? IS NULL
+		  )
+		"#,
+		user_id,
+		session_id,
+		session_id,
+	}
+	.execute(transaction.as_mut())
+	.await?
+	.rows_affected();
+
+	Ok(expired)
+}
+
 #[async_trait]
 impl<A> FromRequestParts<State> for Session<A>
 where
@@ -301,7 +368,7 @@ where
 			return Ok(session);
 		}
 
-		let (cookie, session_id) = request
+		let Some((cookie, session_id)) = request
 			.headers
 			.get_all(header::COOKIE)
 			.into_iter()
@@ -329,7 +396,9 @@ where
 					.map(|session_id| (cookie, session_id))
 					.ok()
 			})
-			.ok_or_else(|| Error::missing_session_id())?;
+		else {
+			return Self::from_api_token(request, state).await;
+		};
 
 		let current_span = tracing::Span::current();
 
@@ -368,6 +437,7 @@ where
 			id: session.id,
 			user: User::new(session.user_id, session.permissions),
 			cookie,
+			api_token: None,
 			_authorization: PhantomData,
 		};
 
@@ -410,6 +480,60 @@ where
 	}
 }
 
+impl<A> Session<A>
+where
+	A: AuthorizeSession,
+{
+	/// Creates a session from an [API token] in the `Authorization` header.
+	///
+	/// [API token]: crate::authentication::api_token
+	async fn from_api_token(request: &mut request::Parts, state: &State) -> Result<Self> {
+		let token = request
+			.headers
+			.get(header::AUTHORIZATION)
+			.and_then(|value| value.to_str().ok())
+			.and_then(|value| value.strip_prefix("Bearer "))
+			.ok_or_else(|| Error::missing_session_id())?
+			.trim()
+			.parse::<Uuid>()
+			.map_err(|err| Error::invalid("API token").context(err))?;
+
+		let mut transaction = state.transaction().await?;
+		let (token_id, user) = api_token::authenticate(token, &mut transaction).await?;
+
+		tracing::Span::current().record("session.user.id", format_args!("{}", user.steam_id()));
+
+		let is_read_only = user.permissions() == Permissions::NONE;
+		let is_safe_method = matches!(request.method, Method::GET | Method::HEAD | Method::OPTIONS);
+
+		if is_read_only && !is_safe_method {
+			tracing::debug!(%token_id, "rejecting read-only token");
+			return Err(Error::unauthorized());
+		}
+
+		A::authorize_session(&user, request, &mut transaction).await?;
+
+		transaction.commit().await?;
+
+		tracing::info! {
+			target: "cs2kz_api::audit_log",
+			%token_id,
+			user.id = %user.steam_id(),
+			method = %request.method,
+			path = %request.uri.path(),
+			"authenticated API token",
+		};
+
+		Ok(Self {
+			id: SessionID::NIL,
+			user,
+			cookie: Cookie::new(COOKIE_NAME, ""),
+			api_token: Some(token_id),
+			_authorization: PhantomData,
+		})
+	}
+}
+
 impl<A> IntoResponseParts for Session<A> {
 	type Error = Error;
 
@@ -420,6 +544,10 @@ impl<A> IntoResponseParts for Session<A> {
 		fields(cookie = tracing::field::Empty),
 	)]
 	fn into_response_parts(self, mut response: ResponseParts) -> Result<ResponseParts> {
+		if self.api_token.is_some() {
+			return Ok(response);
+		}
+
 		let cookie = Cookie::from(self)
 			.encoded()
 			.to_string()
@@ -441,6 +569,7 @@ impl<A> Clone for Session<A> {
 			id: self.id,
 			user: self.user,
 			cookie: self.cookie.clone(),
+			api_token: self.api_token,
 			_authorization: PhantomData,
 		}
 	}
@@ -0,0 +1,33 @@
+//! Combining two authorization methods, requiring both of them to succeed.
+
+use std::marker::PhantomData;
+
+use axum::http::request;
+use sqlx::{MySql, Transaction};
+
+use super::AuthorizeSession;
+use crate::{authentication, Result};
+
+/// An authorization method that succeeds if both `A` **and** `B` succeed.
+///
+/// `A` is checked first; if it fails, `B` will not be checked and `A`'s error is returned.
+#[derive(Debug, Clone, Copy)]
+pub struct And<A, B>(PhantomData<(A, B)>);
+
+impl<A, B> AuthorizeSession for And<A, B>
+where
+	A: AuthorizeSession,
+	B: AuthorizeSession,
+{
+	#[tracing::instrument(level = "debug", name = "auth::and", skip_all)]
+	async fn authorize_session(
+		user: &authentication::User,
+		req: &mut request::Parts,
+		transaction: &mut Transaction<'_, MySql>,
+	) -> Result<()> {
+		A::authorize_session(user, req, transaction).await?;
+		B::authorize_session(user, req, transaction).await?;
+
+		Ok(())
+	}
+}
@@ -1,32 +1,34 @@
-//! Authorization for `/servers` routes, checking if the requesting user is either an admin or the
-//! owner of the server that is being modified.
+//! Authorization for `/servers` routes, checking if the requesting user is the owner of the server
+//! that is being modified.
 
 use axum::extract::{FromRequestParts, Path};
 use axum::http::request;
 use sqlx::{MySql, Transaction};
 
 use super::AuthorizeSession;
-use crate::authorization::{self, Permissions};
+use crate::authorization::{HasPermissions, Or, Permissions};
 use crate::servers::ServerID;
 use crate::{authentication, Error, Result};
 
+/// An authorization method that checks if the requesting user is the owner of the server that is
+/// supposed to be modified by the request.
+#[derive(Debug, Clone, Copy)]
+pub struct IsServerOwner;
+
 /// An authorization method that checks if the requesting user is either an admin with the
 /// [`SERVERS`] permission, or the owner of the server that is supposed to be modified by the
 /// request.
 ///
 /// [`SERVERS`]: Permissions::SERVERS
-#[derive(Debug, Clone, Copy)]
-pub struct IsServerAdminOrOwner;
+pub type IsServerAdminOrOwner = Or<HasPermissions<{ Permissions::SERVERS.value() }>, IsServerOwner>;
 
-impl AuthorizeSession for IsServerAdminOrOwner {
+impl AuthorizeSession for IsServerOwner {
 	#[tracing::instrument(
 		level = "debug",
-		name = "auth::is_server_admin_or_owner",
+		name = "auth::is_server_owner",
 		skip_all,
 		fields(
 			user.id = %user.steam_id(),
-			user.permissions = %user.permissions(),
-			has_required_permissions = tracing::field::Empty,
 			server.id = tracing::field::Empty,
 			is_server_owner = tracing::field::Empty,
 		),
@@ -37,20 +39,6 @@ impl AuthorizeSession for IsServerAdminOrOwner {
 		transaction: &mut Transaction<'_, MySql>,
 	) -> Result<()> {
 		let current_span = tracing::Span::current();
-
-		if authorization::HasPermissions::<{ Permissions::SERVERS.value() }>::authorize_session(
-			user,
-			req,
-			transaction,
-		)
-		.await
-		.is_ok()
-		{
-			current_span.record("has_required_permissions", true);
-
-			return Ok(());
-		}
-
 		let Path(server_id) = Path::<ServerID>::from_request_parts(req, &()).await?;
 
 		current_span.record("server.id", format_args!("{server_id}"));
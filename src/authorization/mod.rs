@@ -5,6 +5,9 @@
 //! trait. This `A` parameter can be used to control the authorization method for any given
 //! [session] at the type system level.
 //!
+//! Authorization methods can be combined using [`And`] and [`Or`], e.g.
+//! `Or<HasPermissions<{ Permissions::SERVERS.value() }>, IsServerOwner>`.
+//!
 //! [session]: crate::authentication::Session
 
 use std::future::Future;
@@ -23,8 +26,14 @@ pub use none::None;
 mod has_permissions;
 pub use has_permissions::HasPermissions;
 
-mod is_server_admin_or_owner;
-pub use is_server_admin_or_owner::IsServerAdminOrOwner;
+mod and;
+pub use and::And;
+
+mod or;
+pub use or::Or;
+
+mod is_server_owner;
+pub use is_server_owner::{IsServerAdminOrOwner, IsServerOwner};
 
 /// A trait used for authorizing a [session].
 ///
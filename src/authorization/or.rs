@@ -0,0 +1,37 @@
+//! Combining two authorization methods, requiring at least one of them to succeed.
+
+use std::marker::PhantomData;
+
+use axum::http::request;
+use sqlx::{MySql, Transaction};
+
+use super::AuthorizeSession;
+use crate::{authentication, Result};
+
+/// An authorization method that succeeds if either `A` **or** `B` succeeds.
+///
+/// `A` is checked first; if it succeeds, `B` will not be checked. If both fail, `B`'s error is
+/// returned, with `A`'s error attached as context.
+#[derive(Debug, Clone, Copy)]
+pub struct Or<A, B>(PhantomData<(A, B)>);
+
+impl<A, B> AuthorizeSession for Or<A, B>
+where
+	A: AuthorizeSession,
+	B: AuthorizeSession,
+{
+	#[tracing::instrument(level = "debug", name = "auth::or", skip_all)]
+	async fn authorize_session(
+		user: &authentication::User,
+		req: &mut request::Parts,
+		transaction: &mut Transaction<'_, MySql>,
+	) -> Result<()> {
+		let Err(error) = A::authorize_session(user, req, transaction).await else {
+			return Ok(());
+		};
+
+		B::authorize_session(user, req, transaction)
+			.await
+			.map_err(|err| err.context(error))
+	}
+}
@@ -0,0 +1,74 @@
+//! Ban appeals.
+//!
+//! Players can appeal their own bans, once per ban, while the ban is still active. Admins can
+//! then either accept the appeal, which reverts the ban, or reject it.
+
+use chrono::{DateTime, Utc};
+use cs2kz::SteamID;
+use sqlx::{MySql, Transaction};
+
+use crate::bans::{AppealID, BanID, UnbanID};
+use crate::sqlx::SqlErrorExt;
+use crate::{Error, Result};
+
+/// Submits an appeal for the ban with the given ID on behalf of `steam_id`.
+///
+/// This fails if the ban does not belong to `steam_id`, is not active anymore, or has already
+/// been appealed.
+pub async fn submit_appeal(
+	ban_id: BanID,
+	steam_id: SteamID,
+	message: &str,
+	transaction: &mut Transaction<'_, MySql>,
+) -> Result<AppealID> {
+	let (player_id, expires_on, unban_id) = sqlx::query! {
+		r#"
+		SELECT
+		  b.player_id `player_id: SteamID`,
+		  b.expires_on `expires_on: DateTime<Utc>`,
+		  ub.id `unban_id: UnbanID`
+		FROM
+		  Bans b
+		  LEFT JOIN Unbans ub ON ub.ban_id = b.id
+		WHERE
+		  b.id = ?
+		FOR UPDATE
+		"#,
+		ban_id,
+	}
+	.fetch_optional(transaction.as_mut())
+	.await?
+	.map(|row| (row.player_id, row.expires_on, row.unban_id))
+	.ok_or_else(|| Error::not_found("ban"))?;
+
+	if player_id != steam_id {
+		return Err(Error::not_banned_player(ban_id));
+	}
+
+	if let Some(unban_id) = unban_id {
+		return Err(Error::ban_already_reverted(ban_id, unban_id));
+	}
+
+	if expires_on.is_some_and(|expires_on| expires_on <= Utc::now()) {
+		return Err(Error::ban_not_active(ban_id));
+	}
+
+	let appeal_id = sqlx::query!(
+		"INSERT INTO Appeals (ban_id, message) VALUES (?, ?)",
+		ban_id,
+		message
+	)
+	.execute(transaction.as_mut())
+	.await
+	.map_err(|err| {
+		if err.is_duplicate_entry() {
+			Error::already_exists("appeal").context(err)
+		} else {
+			Error::from(err)
+		}
+	})?
+	.last_insert_id()
+	.into();
+
+	Ok(appeal_id)
+}
@@ -0,0 +1,469 @@
+//! HTTP handlers for the `/bans/{ban_id}/appeal` and `/bans/appeals` routes.
+
+use axum::extract::{Path, Query};
+use axum::Json;
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use super::by_id::revert_ban;
+use crate::authentication::CsrfToken;
+use crate::authorization::{self, Permissions};
+use crate::bans::{
+	self, queries, Appeal, AppealID, AppealResolution, AppealStatus, BanID, CreatedAppeal,
+	NewAppeal,
+};
+use crate::openapi::parameters::{Limit, Offset};
+use crate::openapi::responses;
+use crate::openapi::responses::{Created, NoContent, PaginationResponse};
+use crate::sqlx::{query, FilteredQuery, QueryBuilderExt};
+use crate::{authentication, Error, Result, State};
+
+/// Query parameters for `/bans/appeals`.
+#[derive(Debug, Clone, Copy, Deserialize, IntoParams)]
+pub struct GetParams {
+	/// Filter by status.
+	status: Option<AppealStatus>,
+
+	/// Maximum number of results to return.
+	#[serde(default)]
+	limit: Limit,
+
+	/// Pagination offset.
+	#[serde(default)]
+	offset: Offset,
+}
+
+/// Fetch ban appeals.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  get,
+  path = "/bans/appeals",
+  tag = "Bans",
+  security(("Browser Session" = ["bans"])),
+  params(GetParams),
+  responses(
+    responses::Ok<PaginationResponse<Appeal>>,
+    responses::NoContent,
+    responses::BadRequest,
+    responses::Unauthorized,
+  ),
+)]
+pub async fn get(
+	state: State,
+	session: authentication::Session<authorization::HasPermissions<{ Permissions::BANS.value() }>>,
+	Query(GetParams {
+		status,
+		limit,
+		offset,
+	}): Query<GetParams>,
+) -> Result<Json<PaginationResponse<Appeal>>> {
+	let mut query = FilteredQuery::new(queries::SELECT_APPEALS);
+	let mut transaction = state.transaction().await?;
+
+	if let Some(status) = status {
+		query.filter(" ap.status = ", status);
+	}
+
+	query.push(" ORDER BY ap.id DESC ");
+	query.push_limits(limit, offset);
+
+	let appeals = query
+		.build_query_as::<Appeal>()
+		.fetch_all(transaction.as_mut())
+		.await?;
+
+	if appeals.is_empty() {
+		return Err(Error::no_content());
+	}
+
+	let total = query::total_rows(&mut transaction).await?;
+
+	transaction.commit().await?;
+
+	Ok(Json(PaginationResponse {
+		total,
+		results: appeals,
+		next_cursor: None,
+	}))
+}
+
+/// Appeal a ban.
+///
+/// Players can only appeal their own bans, and only while they are active. Every ban can only be
+/// appealed once.
+///
+/// Requires a valid CSRF token in the `X-CSRF-Token` header.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  post,
+  path = "/bans/{ban_id}/appeal",
+  tag = "Bans",
+  security(("Browser Session" = [])),
+  params(("ban_id" = u64, Path, description = "The ban's ID")),
+  request_body = NewAppeal,
+  responses(
+    responses::Created<CreatedAppeal>,
+    responses::BadRequest,
+    responses::Unauthorized,
+    responses::Forbidden,
+    responses::NotFound,
+    responses::Conflict,
+    responses::UnprocessableEntity,
+  ),
+)]
+pub async fn post(
+	state: State,
+	session: authentication::Session,
+	_csrf: CsrfToken,
+	Path(ban_id): Path<BanID>,
+	Json(NewAppeal { message }): Json<NewAppeal>,
+) -> Result<Created<Json<CreatedAppeal>>> {
+	let player_id = session.user().steam_id();
+	let mut transaction = state.transaction().await?;
+	let appeal_id = bans::submit_appeal(ban_id, player_id, &message, &mut transaction).await?;
+
+	transaction.commit().await?;
+
+	tracing::info! {
+		target: "cs2kz_api::audit_log",
+		%appeal_id,
+		%ban_id,
+		%player_id,
+		"submitted ban appeal",
+	};
+
+	Ok(Created(Json(CreatedAppeal { appeal_id })))
+}
+
+/// Resolve a ban appeal.
+///
+/// Accepting an appeal will revert the corresponding ban. Rejecting it will add the response to
+/// the ban's note, so the decision can be seen when looking at the ban.
+///
+/// Requires a valid CSRF token in the `X-CSRF-Token` header.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  put,
+  path = "/bans/appeals/{appeal_id}",
+  tag = "Bans",
+  security(("Browser Session" = ["bans"])),
+  params(("appeal_id" = u64, Path, description = "The appeal's ID")),
+  request_body = AppealResolution,
+  responses(
+    responses::NoContent,
+    responses::BadRequest,
+    responses::Unauthorized,
+    responses::Forbidden,
+    responses::NotFound,
+    responses::Conflict,
+    responses::UnprocessableEntity,
+  ),
+)]
+pub async fn put(
+	state: State,
+	session: authentication::Session<authorization::HasPermissions<{ Permissions::BANS.value() }>>,
+	_csrf: CsrfToken,
+	Path(appeal_id): Path<AppealID>,
+	Json(AppealResolution { status, response }): Json<AppealResolution>,
+) -> Result<NoContent> {
+	if status == AppealStatus::Pending {
+		return Err(Error::invalid("appeal status"));
+	}
+
+	let admin_id = session.user().steam_id();
+	let mut transaction = state.transaction().await?;
+
+	let (ban_id, current_status) = sqlx::query! {
+		r#"
+		SELECT
+		  ban_id `ban_id: BanID`,
+		  status `status: AppealStatus`
+		FROM
+		  Appeals
+		WHERE
+		  id = ?
+		FOR UPDATE
+		"#,
+		appeal_id,
+	}
+	.fetch_optional(transaction.as_mut())
+	.await?
+	.map(|row| (row.ban_id, row.status))
+	.ok_or_else(|| Error::not_found("appeal"))?;
+
+	if current_status != AppealStatus::Pending {
+		return Err(Error::already_exists("resolution for this appeal"));
+	}
+
+	sqlx::query! {
+		r#"
+		UPDATE
+		  Appeals
+		SET
+		  status = ?,
+		  response = ?,
+		  admin_id = ?,
+		  resolved_on = NOW()
+		WHERE
+		  id = ?
+		"#,
+		status,
+		response,
+		admin_id,
+		appeal_id,
+	}
+	.execute(transaction.as_mut())
+	.await?;
+
+	if status == AppealStatus::Accepted {
+		revert_ban(ban_id, &response, admin_id, &mut transaction).await?;
+	} else {
+		sqlx::query! {
+			r#"
+			UPDATE
+			  Bans
+			SET
+			  note = CONCAT_WS('\n', note, ?)
+			WHERE
+			  id = ?
+			"#,
+			format!("appeal #{appeal_id} rejected: {response}"),
+			ban_id,
+		}
+		.execute(transaction.as_mut())
+		.await?;
+	}
+
+	transaction.commit().await?;
+
+	tracing::info! {
+		target: "cs2kz_api::audit_log",
+		%appeal_id,
+		%ban_id,
+		?status,
+		%admin_id,
+		"resolved ban appeal",
+	};
+
+	Ok(NoContent)
+}
+
+#[cfg(test)]
+mod tests {
+	use axum_extra::extract::cookie::Cookie;
+	use cs2kz::SteamID;
+	use reqwest::header;
+	use serde_json::json;
+	use sqlx::{MySql, Pool};
+
+	use crate::authentication::{csrf, CsrfToken};
+	use crate::bans::BanID;
+
+	/// Bans `player_id` for a week.
+	async fn ban(player_id: SteamID, database: &Pool<MySql>) -> anyhow::Result<BanID> {
+		let ban_id = sqlx::query! {
+			r#"
+			INSERT INTO
+			  Bans (
+			    player_id,
+			    player_ip,
+			    reason,
+			    plugin_version_id,
+			    expires_on
+			  )
+			VALUES
+			  (?, "127.0.0.1", "auto_bhop", 1, NOW() + INTERVAL 1 WEEK)
+			"#,
+			player_id,
+		}
+		.execute(database)
+		.await?
+		.last_insert_id();
+
+		Ok(BanID(ban_id))
+	}
+
+	#[crate::integration_test]
+	async fn appeal_lifecycle(ctx: &Context) {
+		let alphakeks = SteamID::from_u64(76561198282622073_u64).unwrap();
+		let ban_id = ban(alphakeks, &ctx.database).await?;
+		let session = ctx.auth_session(alphakeks).await?;
+		let csrf_cookie = CsrfToken::create(session.id(), &ctx.database)
+			.await?
+			.to_cookie(&ctx.api_config);
+		let cookies = format!(
+			"{}; {}",
+			Cookie::from(session).encoded(),
+			csrf_cookie.encoded(),
+		);
+
+		let missing_csrf = ctx
+			.http_client
+			.post(ctx.url(format_args!("/bans/{ban_id}/appeal")))
+			.header(
+				header::COOKIE,
+				Cookie::from(ctx.auth_session(alphakeks).await?)
+					.encoded()
+					.to_string(),
+			)
+			.json(&json!({ "message": "I didn't do it" }))
+			.send()
+			.await?;
+
+		assert_eq!(missing_csrf.status(), 403);
+
+		let response = ctx
+			.http_client
+			.post(ctx.url(format_args!("/bans/{ban_id}/appeal")))
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
+			.json(&json!({ "message": "I didn't do it" }))
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 201);
+
+		let appeal_id = response.json::<serde_json::Value>().await?["appeal_id"]
+			.as_u64()
+			.context("response should contain the appeal's ID")?;
+
+		let duplicate = ctx
+			.http_client
+			.post(ctx.url(format_args!("/bans/{ban_id}/appeal")))
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
+			.json(&json!({ "message": "I really didn't do it" }))
+			.send()
+			.await?;
+
+		assert_eq!(duplicate.status(), 409);
+
+		let response = ctx
+			.http_client
+			.put(ctx.url(format_args!("/bans/appeals/{appeal_id}")))
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
+			.json(&json!({ "status": "accepted", "response": "sorry" }))
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 204);
+
+		let unban_reason =
+			sqlx::query_scalar!("SELECT reason FROM Unbans WHERE ban_id = ?", ban_id)
+				.fetch_optional(&ctx.database)
+				.await?;
+
+		assert_eq!(unban_reason.as_deref(), Some("sorry"));
+
+		let resolved_twice = ctx
+			.http_client
+			.put(ctx.url(format_args!("/bans/appeals/{appeal_id}")))
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
+			.json(&json!({ "status": "rejected", "response": "never mind" }))
+			.send()
+			.await?;
+
+		assert_eq!(resolved_twice.status(), 409);
+	}
+
+	#[crate::integration_test]
+	async fn rejections_are_noted_on_the_ban(ctx: &Context) {
+		let alphakeks = SteamID::from_u64(76561198282622073_u64).unwrap();
+		let ban_id = ban(alphakeks, &ctx.database).await?;
+		let session = ctx.auth_session(alphakeks).await?;
+		let csrf_cookie = CsrfToken::create(session.id(), &ctx.database)
+			.await?
+			.to_cookie(&ctx.api_config);
+		let cookies = format!(
+			"{}; {}",
+			Cookie::from(session).encoded(),
+			csrf_cookie.encoded(),
+		);
+
+		let response = ctx
+			.http_client
+			.post(ctx.url(format_args!("/bans/{ban_id}/appeal")))
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
+			.json(&json!({ "message": "I didn't do it" }))
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 201);
+
+		let appeal_id = response.json::<serde_json::Value>().await?["appeal_id"]
+			.as_u64()
+			.context("response should contain the appeal's ID")?;
+
+		let response = ctx
+			.http_client
+			.put(ctx.url(format_args!("/bans/appeals/{appeal_id}")))
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
+			.json(&json!({ "status": "rejected", "response": "you did" }))
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 204);
+
+		let ban = sqlx::query! {
+			r#"
+			SELECT
+			  note,
+			  expires_on > NOW() `active!: bool`
+			FROM
+			  Bans
+			WHERE
+			  id = ?
+			"#,
+			ban_id,
+		}
+		.fetch_one(&ctx.database)
+		.await?;
+
+		assert!(ban.active);
+		assert_eq!(
+			ban.note.as_deref(),
+			Some(format!("appeal #{appeal_id} rejected: you did").as_str()),
+		);
+	}
+
+	#[crate::integration_test]
+	async fn cannot_appeal_other_players_bans(ctx: &Context) {
+		sqlx::query! {
+			"INSERT INTO Players (id, name, ip_address) VALUES (?, ?, ?)",
+			SteamID::MAX,
+			"not AlphaKeks",
+			"127.0.0.1",
+		}
+		.execute(&ctx.database)
+		.await?;
+
+		let ban_id = ban(SteamID::MAX, &ctx.database).await?;
+		let alphakeks = SteamID::from_u64(76561198282622073_u64).unwrap();
+		let session = ctx.auth_session(alphakeks).await?;
+		let csrf_cookie = CsrfToken::create(session.id(), &ctx.database)
+			.await?
+			.to_cookie(&ctx.api_config);
+
+		let response = ctx
+			.http_client
+			.post(ctx.url(format_args!("/bans/{ban_id}/appeal")))
+			.header(
+				header::COOKIE,
+				format!(
+					"{}; {}",
+					Cookie::from(session).encoded(),
+					csrf_cookie.encoded(),
+				),
+			)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
+			.json(&json!({ "message": "unban my friend" }))
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 403);
+	}
+}
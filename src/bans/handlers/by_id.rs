@@ -2,8 +2,10 @@
 
 use axum::extract::Path;
 use axum::Json;
-use sqlx::{MySqlExecutor, QueryBuilder};
+use cs2kz::SteamID;
+use sqlx::{MySql, MySqlExecutor, QueryBuilder, Transaction};
 
+use crate::authentication::CsrfToken;
 use crate::authorization::{self, Permissions};
 use crate::bans::{queries, Ban, BanID, BanUpdate, CreatedUnban, NewUnban, UnbanID};
 use crate::openapi::responses;
@@ -17,6 +19,7 @@ use crate::{authentication, Error, Result, State};
   get,
   path = "/bans/{ban_id}",
   tag = "Bans",
+  security((), ("Browser Session" = ["bans"])),
   params(("ban_id" = u64, Path, description = "The ban's ID")),
   responses(
     responses::Ok<Ban>,
@@ -24,21 +27,33 @@ use crate::{authentication, Error, Result, State};
     responses::BadRequest,
   ),
 )]
-pub async fn get(state: State, Path(ban_id): Path<BanID>) -> Result<Json<Ban>> {
+pub async fn get(
+	state: State,
+	session: Option<
+		authentication::Session<authorization::HasPermissions<{ Permissions::BANS.value() }>>,
+	>,
+	Path(ban_id): Path<BanID>,
+) -> Result<Json<Ban>> {
 	let mut query = QueryBuilder::new(queries::SELECT);
 
 	query.push(" WHERE b.id = ").push_bind(ban_id);
 
-	let ban = query
+	let mut ban = query
 		.build_query_as::<Ban>()
 		.fetch_optional(&state.database)
 		.await?
 		.ok_or_else(|| Error::not_found("ban"))?;
 
+	if session.is_none() {
+		ban.note = None;
+	}
+
 	Ok(Json(ban))
 }
 
 /// Update an existing ban.
+///
+/// Requires a valid CSRF token in the `X-CSRF-Token` header.
 #[tracing::instrument(skip(state))]
 #[utoipa::path(
   patch,
@@ -50,6 +65,7 @@ pub async fn get(state: State, Path(ban_id): Path<BanID>) -> Result<Json<Ban>> {
     responses::NoContent,
     responses::BadRequest,
     responses::Unauthorized,
+    responses::Forbidden,
     responses::Conflict,
     responses::UnprocessableEntity,
   ),
@@ -57,10 +73,15 @@ pub async fn get(state: State, Path(ban_id): Path<BanID>) -> Result<Json<Ban>> {
 pub async fn patch(
 	state: State,
 	session: authentication::Session<authorization::HasPermissions<{ Permissions::BANS.value() }>>,
+	_csrf: CsrfToken,
 	Path(ban_id): Path<BanID>,
-	Json(BanUpdate { reason, expires_on }): Json<BanUpdate>,
+	Json(BanUpdate {
+		reason,
+		expires_on,
+		note,
+	}): Json<BanUpdate>,
 ) -> Result<NoContent> {
-	if reason.is_none() && expires_on.is_none() {
+	if reason.is_none() && expires_on.is_none() && note.is_none() {
 		return Ok(NoContent);
 	}
 
@@ -80,6 +101,10 @@ pub async fn patch(
 		query.set(" expires_on ", expires_on);
 	}
 
+	if let Some(note) = note {
+		query.set(" note ", note);
+	}
+
 	query.push(" WHERE id = ").push_bind(ban_id);
 
 	let query_result = query.build().execute(transaction.as_mut()).await?;
@@ -97,6 +122,8 @@ pub async fn patch(
 }
 
 /// Revert a ban.
+///
+/// Requires a valid CSRF token in the `X-CSRF-Token` header.
 #[tracing::instrument(skip(state))]
 #[utoipa::path(
   delete,
@@ -108,17 +135,36 @@ pub async fn patch(
     responses::Created<CreatedUnban>,
     responses::BadRequest,
     responses::Unauthorized,
+    responses::Forbidden,
     responses::Conflict,
   ),
 )]
 pub async fn delete(
 	state: State,
 	session: authentication::Session<authorization::HasPermissions<{ Permissions::BANS.value() }>>,
+	_csrf: CsrfToken,
 	Path(ban_id): Path<BanID>,
 	Json(NewUnban { reason }): Json<NewUnban>,
 ) -> Result<Created<Json<CreatedUnban>>> {
 	let mut transaction = state.transaction().await?;
+	let unban_id = revert_ban(ban_id, &reason, session.user().steam_id(), &mut transaction).await?;
+
+	transaction.commit().await?;
+
+	Ok(Created(Json(CreatedUnban { unban_id })))
+}
 
+/// Reverts a ban by creating a corresponding unban.
+///
+/// This is used both for reverting bans directly and for accepting [appeals].
+///
+/// [appeals]: crate::bans::appeals
+pub(super) async fn revert_ban(
+	ban_id: BanID,
+	reason: &str,
+	admin_id: SteamID,
+	transaction: &mut Transaction<'_, MySql>,
+) -> Result<UnbanID> {
 	if let Some(unban_id) = is_already_unbanned(ban_id, transaction.as_mut()).await? {
 		return Err(Error::ban_already_reverted(ban_id, unban_id));
 	}
@@ -153,18 +199,16 @@ pub async fn delete(
 		"#,
 		ban_id,
 		reason,
-		session.user().steam_id(),
+		admin_id,
 	}
 	.execute(transaction.as_mut())
 	.await?
 	.last_insert_id()
 	.into();
 
-	transaction.commit().await?;
-
 	tracing::info!(target: "cs2kz_api::audit_log", %ban_id, %unban_id, "created unban");
 
-	Ok(Created(Json(CreatedUnban { unban_id })))
+	Ok(unban_id)
 }
 
 /// Checks if a ban has already been reverted, and returns the corresponding [`UnbanID`].
@@ -0,0 +1,81 @@
+//! HTTP handlers for the `/bans/ip-ranges` routes.
+
+use axum::Json;
+
+use crate::authentication::CsrfToken;
+use crate::authorization::{self, Permissions};
+use crate::bans::{ip_ranges, CreatedIpRangeBan, IpRangeBan, NewIpRangeBan};
+use crate::openapi::responses;
+use crate::openapi::responses::Created;
+use crate::{authentication, Error, Result, State};
+
+/// Fetch all active IP range bans.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  get,
+  path = "/bans/ip-ranges",
+  tag = "Bans",
+  security(("Browser Session" = ["bans"])),
+  responses(
+    responses::OkList<IpRangeBan>,
+    responses::NoContent,
+    responses::Unauthorized,
+  ),
+)]
+pub async fn get(
+	state: State,
+	session: authentication::Session<authorization::HasPermissions<{ Permissions::BANS.value() }>>,
+) -> Result<Json<Vec<IpRangeBan>>> {
+	let bans = ip_ranges::fetch_active(&state.database).await?;
+
+	if bans.is_empty() {
+		return Err(Error::no_content());
+	}
+
+	Ok(Json(bans))
+}
+
+/// Ban an entire range of IP addresses.
+///
+/// New players connecting from an address in this range will not be able to register.
+///
+/// Requires a valid CSRF token in the `X-CSRF-Token` header.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  post,
+  path = "/bans/ip-ranges",
+  tag = "Bans",
+  security(("Browser Session" = ["bans"])),
+  request_body = NewIpRangeBan,
+  responses(
+    responses::Created<CreatedIpRangeBan>,
+    responses::BadRequest,
+    responses::Unauthorized,
+    responses::Forbidden,
+    responses::UnprocessableEntity,
+  ),
+)]
+pub async fn post(
+	state: State,
+	session: authentication::Session<authorization::HasPermissions<{ Permissions::BANS.value() }>>,
+	_csrf: CsrfToken,
+	Json(NewIpRangeBan {
+		range,
+		reason,
+		duration,
+	}): Json<NewIpRangeBan>,
+) -> Result<Created<Json<CreatedIpRangeBan>>> {
+	let admin_id = session.user().steam_id();
+	let ban_id =
+		ip_ranges::ban_ip_range(range, &reason, duration, admin_id, &state.database).await?;
+
+	tracing::info! {
+		target: "cs2kz_api::audit_log",
+		%ban_id,
+		%range,
+		%admin_id,
+		"banned ip range",
+	};
+
+	Ok(Created(Json(CreatedIpRangeBan { ban_id })))
+}
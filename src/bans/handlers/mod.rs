@@ -2,3 +2,6 @@
 
 pub mod root;
 pub mod by_id;
+pub mod ip_ranges;
+pub mod related;
+pub mod appeals;
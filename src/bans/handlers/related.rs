@@ -0,0 +1,82 @@
+//! HTTP handlers for the `/bans/{ban_id}/related` routes.
+
+use axum::extract::{Path, Query};
+use axum::Json;
+use cs2kz::SteamID;
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::authorization::{self, Permissions};
+use crate::bans::{self, BanID, RelatedAccount};
+use crate::openapi::parameters::Limit;
+use crate::openapi::responses;
+use crate::{authentication, Error, Result, State};
+
+/// Query parameters for `/bans/{ban_id}/related`.
+#[derive(Debug, Clone, Copy, Deserialize, IntoParams)]
+pub struct GetParams {
+	/// Maximum number of results to return.
+	#[serde(default)]
+	limit: Limit<100, 10>,
+}
+
+/// Fetch accounts that might belong to the same person as the banned player.
+///
+/// Accounts are considered related if they have been seen with the same IP address(es) as the
+/// banned player. These are only candidates for review; nobody is banned automatically.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  get,
+  path = "/bans/{ban_id}/related",
+  tag = "Bans",
+  security(("Browser Session" = ["bans"])),
+  params(("ban_id" = u64, Path, description = "The ban's ID"), GetParams),
+  responses(
+    responses::OkList<RelatedAccount>,
+    responses::NoContent,
+    responses::BadRequest,
+    responses::Unauthorized,
+    responses::NotFound,
+  ),
+)]
+pub async fn get(
+	state: State,
+	session: authentication::Session<authorization::HasPermissions<{ Permissions::BANS.value() }>>,
+	Path(ban_id): Path<BanID>,
+	Query(GetParams { limit }): Query<GetParams>,
+) -> Result<Json<Vec<RelatedAccount>>> {
+	let mut transaction = state.transaction().await?;
+
+	let player_id = sqlx::query_scalar! {
+		r#"
+		SELECT
+		  player_id `player_id: SteamID`
+		FROM
+		  Bans
+		WHERE
+		  id = ?
+		"#,
+		ban_id,
+	}
+	.fetch_optional(transaction.as_mut())
+	.await?
+	.ok_or_else(|| Error::not_found("ban"))?;
+
+	let accounts = bans::find_related_accounts(player_id, *limit, transaction.as_mut()).await?;
+
+	transaction.commit().await?;
+
+	if accounts.is_empty() {
+		return Err(Error::no_content());
+	}
+
+	tracing::info! {
+		target: "cs2kz_api::audit_log",
+		%ban_id,
+		%player_id,
+		admin_id = %session.user().steam_id(),
+		"looked up related accounts",
+	};
+
+	Ok(Json(accounts))
+}
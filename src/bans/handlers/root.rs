@@ -10,7 +10,7 @@ use serde::Deserialize;
 use time::OffsetDateTime;
 use utoipa::IntoParams;
 
-use crate::authentication::Jwt;
+use crate::authentication::{CsrfToken, Jwt};
 use crate::authorization::Permissions;
 use crate::bans::{queries, Ban, BanReason, CreatedBan, NewBan};
 use crate::openapi::parameters::{Limit, Offset};
@@ -18,6 +18,7 @@ use crate::openapi::responses;
 use crate::openapi::responses::{Created, PaginationResponse};
 use crate::plugin::PluginVersionID;
 use crate::sqlx::{query, FetchID, FilteredQuery, QueryBuilderExt, SqlErrorExt};
+use crate::time::TimeRange;
 use crate::{authentication, authorization, Error, Result, State};
 
 /// Query parameters for `/bans`.
@@ -47,6 +48,13 @@ pub struct GetParams {
 	/// Only include bans submitted before this date.
 	created_before: Option<DateTime<Utc>>,
 
+	/// Only include bans submitted within this range.
+	///
+	/// This is an alternative to `created_after` / `created_before`, e.g.
+	/// `2024-01-01..2024-02-01`.
+	#[param(value_type = Option<String>)]
+	range: Option<TimeRange>,
+
 	/// Maximum number of results to return.
 	#[serde(default)]
 	limit: Limit,
@@ -62,6 +70,7 @@ pub struct GetParams {
   get,
   path = "/bans",
   tag = "Bans",
+  security((), ("Browser Session" = ["bans"])),
   params(GetParams),
   responses(
     responses::Ok<PaginationResponse<Ban>>,
@@ -71,6 +80,9 @@ pub struct GetParams {
 )]
 pub async fn get(
 	state: State,
+	session: Option<
+		authentication::Session<authorization::HasPermissions<{ Permissions::BANS.value() }>>,
+	>,
 	Query(GetParams {
 		player,
 		server,
@@ -80,6 +92,7 @@ pub async fn get(
 		unbanned_by,
 		created_after,
 		created_before,
+		range,
 		limit,
 		offset,
 	}): Query<GetParams>,
@@ -88,13 +101,13 @@ pub async fn get(
 	let mut transaction = state.transaction().await?;
 
 	if let Some(player) = player {
-		let steam_id = player.fetch_id(transaction.as_mut()).await?;
+		let steam_id = player.fetch_id_unchecked(transaction.as_mut()).await?;
 
 		query.filter(" b.player_id = ", steam_id);
 	}
 
 	if let Some(server) = server {
-		let server_id = server.fetch_id(transaction.as_mut()).await?;
+		let server_id = server.fetch_id_unchecked(transaction.as_mut()).await?;
 
 		query.filter(" b.server_id = ", server_id);
 	}
@@ -107,17 +120,14 @@ pub async fn get(
 		query.filter_is_null(" ub.id ", !unbanned);
 	}
 
-	if let Some(created_after) = created_after {
-		query.filter(" b.created_on > ", created_after);
-	}
-
-	if let Some(created_before) = created_before {
-		query.filter(" b.created_on < ", created_before);
-	}
+	query.filter_range(
+		"b.created_on",
+		TimeRange::between(created_after, created_before).intersect(range.unwrap_or_default()),
+	);
 
 	query.push_limits(limit, offset);
 
-	let bans = query
+	let mut bans = query
 		.build_query_as::<Ban>()
 		.fetch_all(transaction.as_mut())
 		.await?;
@@ -130,24 +140,35 @@ pub async fn get(
 
 	transaction.commit().await?;
 
+	if session.is_none() {
+		for ban in &mut bans {
+			ban.note = None;
+		}
+	}
+
 	Ok(Json(PaginationResponse {
 		total,
 		results: bans,
+		next_cursor: None,
 	}))
 }
 
 /// Create a new ban.
+///
+/// Requests authenticated with a session require a valid CSRF token in the `X-CSRF-Token`
+/// header.
 #[tracing::instrument(skip(state))]
 #[utoipa::path(
   post,
   path = "/bans",
   tag = "Bans",
-  security(("Browser Session" = ["bans"])),
+  security(("CS2 Server" = []), ("Browser Session" = ["bans"])),
   request_body = NewBan,
   responses(
     responses::Created<CreatedBan>,
     responses::BadRequest,
     responses::Unauthorized,
+    responses::Forbidden,
     responses::UnprocessableEntity,
   ),
 )]
@@ -157,14 +178,19 @@ pub async fn post(
 	session: Option<
 		authentication::Session<authorization::HasPermissions<{ Permissions::BANS.value() }>>,
 	>,
+	csrf: Option<CsrfToken>,
 	Json(NewBan {
 		player_id,
 		player_ip,
 		reason,
+		note,
 	}): Json<NewBan>,
 ) -> Result<Created<Json<CreatedBan>>> {
 	let (server, admin) = match (server, session) {
 		(Some(server), None) => (Some(server.into_payload()), None),
+		(None, Some(_)) if csrf.is_none() => {
+			return Err(Error::csrf_mismatch());
+		}
 		(None, Some(session)) => (None, Some(session.user())),
 		(None, None) => {
 			return Err(Error::unauthorized());
@@ -255,17 +281,19 @@ pub async fn post(
 		    player_ip,
 		    server_id,
 		    reason,
+		    note,
 		    admin_id,
 		    plugin_version_id,
 		    expires_on
 		  )
 		VALUES
-		  (?, ?, ?, ?, ?, ?, ?)
+		  (?, ?, ?, ?, ?, ?, ?, ?)
 		"#,
 		player_id,
 		player_ip,
 		server.map(|server| server.id()),
 		reason,
+		note,
 		admin.map(|admin| admin.steam_id()),
 		plugin_version_id,
 		expires_on,
@@ -298,3 +326,64 @@ pub async fn post(
 
 	Ok(Created(Json(CreatedBan { ban_id })))
 }
+
+#[cfg(test)]
+mod tests {
+	use crate::bans::Ban;
+
+	#[crate::integration_test]
+	async fn bans_match_schema(ctx: &Context) {
+		let mut ban_ids = Vec::new();
+
+		for _ in 0..2 {
+			let ban_id = sqlx::query! {
+				r#"
+				INSERT INTO
+				  Bans (
+				    player_id,
+				    player_ip,
+				    server_id,
+				    reason,
+				    plugin_version_id,
+				    expires_on
+				  )
+				VALUES
+				  (76561198282622073, "127.0.0.1", 1, "auto_bhop", 1, NOW() + INTERVAL 1 WEEK)
+				"#,
+			}
+			.execute(&ctx.database)
+			.await?
+			.last_insert_id();
+
+			ban_ids.push(ban_id);
+		}
+
+		// one of them is reverted, so `unban` is populated
+		sqlx::query! {
+			r#"
+			INSERT INTO
+			  Unbans (ban_id, reason, admin_id)
+			VALUES
+			  (?, "false ban", 76561198282622073)
+			"#,
+			ban_ids.first(),
+		}
+		.execute(&ctx.database)
+		.await?;
+
+		let response = ctx.http_client.get(ctx.url("/bans")).send().await?;
+
+		assert_eq!(response.status(), 200);
+
+		let response = response.json::<serde_json::Value>().await?;
+		let bans = response["results"]
+			.as_array()
+			.context("`results` should be an array")?;
+
+		assert_eq!(bans.len(), 2);
+
+		for ban in bans {
+			crate::test::validate_schema::<Ban>(ban)?;
+		}
+	}
+}
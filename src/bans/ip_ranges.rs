@@ -0,0 +1,106 @@
+//! Bans for entire IP ranges.
+//!
+//! Players who got banned can simply create a new account and keep playing. Most of the time
+//! they will be connecting from the same network though, so admins can ban the entire range of
+//! addresses instead. Players are checked against these bans when they are registered, and
+//! whenever a server updates them.
+//!
+//! Every ban stores the first and last address of its range, so looking up the ban for a
+//! specific address is a simple range query.
+
+use std::net::IpAddr;
+
+use cs2kz::SteamID;
+use sqlx::{MySqlExecutor, QueryBuilder};
+
+use crate::bans::{queries, IpRange, IpRangeBan, IpRangeBanID};
+use crate::time::Seconds;
+use crate::{Error, Result};
+
+/// Bans every address in `range`.
+///
+/// If no `duration` is specified, the ban is permanent.
+pub async fn ban_ip_range<'c, E>(
+	range: IpRange,
+	reason: &str,
+	duration: Option<Seconds>,
+	admin_id: SteamID,
+	executor: E,
+) -> Result<IpRangeBanID>
+where
+	E: MySqlExecutor<'c>,
+{
+	let ban_id = sqlx::query! {
+		r#"
+		INSERT INTO
+		  BannedIpRanges (
+		    network,
+		    last_address,
+		    prefix_length,
+		    reason,
+		    admin_id,
+		    expires_on
+		  )
+		VALUES
+		  (?, ?, ?, ?, ?, NOW() + INTERVAL ? SECOND)
+		"#,
+		IpAddr::V6(range.network()),
+		IpAddr::V6(range.last_address()),
+		range.prefix_length(),
+		reason,
+		admin_id,
+		duration.map(|duration| duration.as_secs()),
+	}
+	.execute(executor)
+	.await?
+	.last_insert_id()
+	.into();
+
+	Ok(ban_id)
+}
+
+/// Finds an active ban for a range that contains `ip`.
+pub async fn find_ip_range_ban<'c, E>(ip: IpAddr, executor: E) -> Result<Option<IpRangeBan>>
+where
+	E: MySqlExecutor<'c>,
+{
+	// ranges are stored as IPv6, so IPv4 addresses have to be mapped to compare correctly
+	let ip = match ip {
+		IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+		IpAddr::V6(ip) => ip,
+	};
+
+	let mut query = QueryBuilder::new(queries::SELECT_IP_RANGES);
+
+	query
+		.push(" WHERE ")
+		.push_bind(IpAddr::V6(ip))
+		.push(" BETWEEN b.network AND b.last_address ")
+		.push(" AND (b.expires_on IS NULL OR b.expires_on > NOW()) ")
+		.push(" ORDER BY b.id DESC ")
+		.push(" LIMIT 1 ");
+
+	query
+		.build_query_as::<IpRangeBan>()
+		.fetch_optional(executor)
+		.await
+		.map_err(Error::from)
+}
+
+/// Fetches all IP range bans that haven't expired yet.
+pub async fn fetch_active<'c, E>(executor: E) -> Result<Vec<IpRangeBan>>
+where
+	E: MySqlExecutor<'c>,
+{
+	let mut query = QueryBuilder::new(queries::SELECT_IP_RANGES);
+
+	query
+		.push(" WHERE b.expires_on IS NULL OR b.expires_on > NOW() ")
+		.push(" ORDER BY b.id DESC ");
+
+	query
+		.build_query_as::<IpRangeBan>()
+		.fetch_all(executor)
+		.await
+		.map_err(Error::from)
+}
@@ -10,10 +10,22 @@ use crate::{authorization, State};
 
 mod models;
 pub use models::{
-	Ban, BanID, BanReason, BanUpdate, CreatedBan, CreatedUnban, NewBan, NewUnban, Unban, UnbanID,
+	Appeal, AppealID, AppealResolution, AppealStatus, Ban, BanID, BanReason, BanSummary, BanUpdate,
+	CreatedAppeal, CreatedBan, CreatedIpRangeBan, CreatedUnban, InvalidAppealStatus,
+	InvalidIpRange, IpRange, IpRangeBan, IpRangeBanID, NewAppeal, NewBan, NewIpRangeBan, NewUnban,
+	RelatedAccount, Unban, UnbanID,
 };
 
-mod queries;
+pub(crate) mod queries;
+pub mod ip_ranges;
+pub use ip_ranges::{ban_ip_range, find_ip_range_ban};
+
+pub mod related_accounts;
+pub use related_accounts::find_related_accounts;
+
+pub mod appeals;
+pub use appeals::submit_appeal;
+
 pub mod handlers;
 
 /// Returns an [`axum::Router`] for the `/bans` routes.
@@ -23,6 +35,8 @@ pub fn router(state: State) -> Router {
 		state.clone(),
 	);
 
+	let user_auth = session_auth!(authorization::None, state.clone());
+
 	let root = Router::new()
 		.route("/", routing::get(handlers::root::get))
 		.route_layer(cors::permissive())
@@ -44,5 +58,44 @@ pub fn router(state: State) -> Router {
 		.route_layer(cors::dashboard([Method::PATCH, Method::DELETE]))
 		.with_state(state.clone());
 
+	let related = Router::new()
+		.route(
+			"/:id/related",
+			routing::get(handlers::related::get).route_layer(auth()),
+		)
+		.route_layer(cors::dashboard([Method::GET]))
+		.with_state(state.clone());
+
+	let appeal = Router::new()
+		.route(
+			"/:id/appeal",
+			routing::post(handlers::appeals::post).route_layer(user_auth()),
+		)
+		.route(
+			"/appeals",
+			routing::get(handlers::appeals::get).route_layer(auth()),
+		)
+		.route(
+			"/appeals/:appeal_id",
+			routing::put(handlers::appeals::put).route_layer(auth()),
+		)
+		.route_layer(cors::dashboard([Method::GET, Method::POST, Method::PUT]))
+		.with_state(state.clone());
+
+	let ip_ranges = Router::new()
+		.route(
+			"/ip-ranges",
+			routing::get(handlers::ip_ranges::get).route_layer(auth()),
+		)
+		.route(
+			"/ip-ranges",
+			routing::post(handlers::ip_ranges::post).route_layer(auth()),
+		)
+		.route_layer(cors::dashboard([Method::GET, Method::POST]))
+		.with_state(state.clone());
+
 	root.merge(by_id)
+		.merge(related)
+		.merge(appeal)
+		.merge(ip_ranges)
 }
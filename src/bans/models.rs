@@ -1,11 +1,12 @@
 //! Types for modeling KZ player bans.
 
-use std::net::IpAddr;
+use std::fmt;
+use std::net::{IpAddr, Ipv6Addr};
 use std::str::FromStr;
 
 use chrono::{DateTime, Utc};
 use cs2kz::SteamID;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use sqlx::mysql::MySqlRow;
 use sqlx::{database, FromRow, MySql, Row};
 use thiserror::Error;
@@ -15,9 +16,12 @@ use utoipa::ToSchema;
 use crate::make_id;
 use crate::players::Player;
 use crate::servers::ServerInfo;
+use crate::time::Seconds;
 
 make_id!(BanID as u64);
 make_id!(UnbanID as u64);
+make_id!(IpRangeBanID as u64);
+make_id!(AppealID as u64);
 
 /// A player ban.
 #[derive(Debug, Serialize, ToSchema)]
@@ -35,6 +39,12 @@ pub struct Ban {
 	/// The reason the player was banned for.
 	pub reason: BanReason,
 
+	/// Free-text note left by admins.
+	///
+	/// This is only included if the request was made by an admin with the `bans` permission.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub note: Option<String>,
+
 	/// The admin who banned the player.
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub admin: Option<Player>,
@@ -57,6 +67,7 @@ impl FromRow<'_, MySqlRow> for Ban {
 			player: Player::from_row(row)?,
 			server: ServerInfo::from_row(row).ok(),
 			reason: row.try_get("reason")?,
+			note: row.try_get("note")?,
 			admin: row
 				.try_get("admin_name")
 				.and_then(|name| Ok((name, row.try_get("admin_id")?)))
@@ -141,6 +152,17 @@ impl<'q> sqlx::Decode<'q, MySql> for BanReason {
 	}
 }
 
+/// A summary of a player's bans.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BanSummary {
+	/// The player's currently active ban (if any).
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub active_ban: Option<Ban>,
+
+	/// The amount of bans that have already expired or been reverted.
+	pub previous_bans: u64,
+}
+
 /// Reversion of a `Ban`.
 #[derive(Debug, Serialize, ToSchema)]
 pub struct Unban {
@@ -174,7 +196,7 @@ impl FromRow<'_, MySqlRow> for Unban {
 }
 
 /// Request payload for submitting a new ban.
-#[derive(Debug, Clone, Copy, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 pub struct NewBan {
 	/// The SteamID of the player who should be banned.
 	pub player_id: SteamID,
@@ -185,6 +207,9 @@ pub struct NewBan {
 
 	/// The reason for the ban.
 	pub reason: BanReason,
+
+	/// An initial note for the ban.
+	pub note: Option<String>,
 }
 
 /// Response body for submitting a new ban.
@@ -195,7 +220,7 @@ pub struct CreatedBan {
 }
 
 /// Request payload for updating an existing ban.
-#[derive(Debug, Clone, Copy, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 pub struct BanUpdate {
 	/// A new ban reason.
 	pub reason: Option<BanReason>,
@@ -206,6 +231,12 @@ pub struct BanUpdate {
 	/// If it is explicitly set to `null`, the expiration date will be set to `NULL`
 	/// (permanent).
 	pub expires_on: Option<Option<DateTime<Utc>>>,
+
+	/// A new note.
+	///
+	/// If this field is omitted, nothing will happen.
+	/// If it is explicitly set to `null`, the note will be removed.
+	pub note: Option<Option<String>>,
 }
 
 /// Request payload for submitting an unban.
@@ -221,3 +252,356 @@ pub struct CreatedUnban {
 	/// The unban's ID.
 	pub unban_id: UnbanID,
 }
+
+/// A range of IP addresses in CIDR notation, e.g. `192.168.0.0/16`.
+///
+/// IPv4 ranges are stored as their IPv4-mapped IPv6 equivalent (`::ffff:192.168.0.0/112`), so
+/// addresses of both families can be matched the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpRange {
+	/// The first address in the range.
+	network: Ipv6Addr,
+
+	/// How many leading bits of an address have to match [`IpRange::network`].
+	prefix_length: u8,
+}
+
+impl IpRange {
+	/// Creates a new [`IpRange`].
+	///
+	/// `prefix_length` is interpreted relative to the address family of `network`, so `/16` on
+	/// an IPv4 address covers the same addresses it usually would. Any bits after the prefix
+	/// are ignored.
+	pub fn new(network: IpAddr, prefix_length: u8) -> Result<Self, InvalidIpRange> {
+		let (network, prefix_length) = match network {
+			IpAddr::V4(ip) if prefix_length <= 32 => (ip.to_ipv6_mapped(), prefix_length + 96),
+			IpAddr::V6(ip) if prefix_length <= 128 => (ip, prefix_length),
+			_ => return Err(InvalidIpRange::PrefixLength(prefix_length)),
+		};
+
+		let network = Ipv6Addr::from(u128::from(network) & Self::mask(prefix_length));
+
+		Ok(Self {
+			network,
+			prefix_length,
+		})
+	}
+
+	/// The first address in the range.
+	pub const fn network(&self) -> Ipv6Addr {
+		self.network
+	}
+
+	/// The last address in the range.
+	pub fn last_address(&self) -> Ipv6Addr {
+		Ipv6Addr::from(u128::from(self.network) | !Self::mask(self.prefix_length))
+	}
+
+	/// The prefix length, relative to [`IpRange::network()`].
+	pub const fn prefix_length(&self) -> u8 {
+		self.prefix_length
+	}
+
+	/// Returns a bitmask with the leading `prefix_length` bits set.
+	fn mask(prefix_length: u8) -> u128 {
+		u128::MAX
+			.checked_shl(128 - u32::from(prefix_length))
+			.unwrap_or_default()
+	}
+}
+
+impl fmt::Display for IpRange {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self.network.to_ipv4_mapped() {
+			Some(ip) if self.prefix_length >= 96 => write!(f, "{ip}/{}", self.prefix_length - 96),
+			_ => write!(f, "{}/{}", self.network, self.prefix_length),
+		}
+	}
+}
+
+/// An error for parsing IP ranges.
+#[derive(Debug, Error)]
+pub enum InvalidIpRange {
+	/// The `/<prefix length>` suffix was missing.
+	#[error("IP range is missing a prefix length")]
+	MissingPrefixLength,
+
+	/// The address part was not a valid IP address.
+	#[error("invalid IP address: {0}")]
+	Address(#[from] std::net::AddrParseError),
+
+	/// The prefix length was longer than the address.
+	#[error("invalid prefix length `{0}`")]
+	PrefixLength(u8),
+
+	/// The prefix length was not a number.
+	#[error("invalid prefix length: {0}")]
+	ParsePrefixLength(#[from] std::num::ParseIntError),
+}
+
+impl FromStr for IpRange {
+	type Err = InvalidIpRange;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		let (network, prefix_length) = value
+			.split_once('/')
+			.ok_or(InvalidIpRange::MissingPrefixLength)?;
+
+		Self::new(network.parse()?, prefix_length.parse()?)
+	}
+}
+
+impl Serialize for IpRange {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		serializer.collect_str(self)
+	}
+}
+
+impl<'de> Deserialize<'de> for IpRange {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		use serde::de::Error as E;
+
+		String::deserialize(deserializer)?
+			.parse()
+			.map_err(E::custom)
+	}
+}
+
+/// A ban for an entire range of IP addresses.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct IpRangeBan {
+	/// The ban's ID.
+	pub id: IpRangeBanID,
+
+	/// The banned IP range, in CIDR notation.
+	#[schema(value_type = String)]
+	pub range: IpRange,
+
+	/// The reason for the ban.
+	pub reason: String,
+
+	/// The admin who issued the ban.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub admin: Option<Player>,
+
+	/// When this ban was submitted.
+	pub created_on: DateTime<Utc>,
+
+	/// When this ban will expire.
+	pub expires_on: Option<DateTime<Utc>>,
+}
+
+impl FromRow<'_, MySqlRow> for IpRangeBan {
+	fn from_row(row: &MySqlRow) -> sqlx::Result<Self> {
+		let range = IpRange::new(row.try_get("network")?, row.try_get("prefix_length")?).map_err(
+			|err| sqlx::Error::ColumnDecode {
+				index: String::from("prefix_length"),
+				source: Box::new(err),
+			},
+		)?;
+
+		Ok(Self {
+			id: row.try_get("id")?,
+			range,
+			reason: row.try_get("reason")?,
+			admin: row
+				.try_get("admin_name")
+				.and_then(|name| Ok((name, row.try_get("admin_id")?)))
+				.map(|(name, steam_id)| Player { name, steam_id })
+				.ok(),
+			created_on: row.try_get("created_on")?,
+			expires_on: row.try_get("expires_on")?,
+		})
+	}
+}
+
+/// Request payload for banning an IP range.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct NewIpRangeBan {
+	/// The IP range to ban, in CIDR notation.
+	#[schema(value_type = String, example = "192.168.0.0/16")]
+	pub range: IpRange,
+
+	/// The reason for the ban.
+	pub reason: String,
+
+	/// How long the ban should last.
+	///
+	/// If this is omitted, the ban is permanent.
+	pub duration: Option<Seconds>,
+}
+
+/// Response body for banning an IP range.
+#[derive(Debug, Clone, Copy, Serialize, ToSchema)]
+pub struct CreatedIpRangeBan {
+	/// The ban's ID.
+	pub ban_id: IpRangeBanID,
+}
+
+/// An account that might belong to the same person as a banned player.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RelatedAccount {
+	/// The player.
+	pub player: Player,
+
+	/// How many of the banned player's IP addresses this account has been seen with.
+	pub shared_addresses: u64,
+
+	/// The last time this account was seen with one of the shared addresses.
+	pub last_seen_on: DateTime<Utc>,
+}
+
+/// A banned player's appeal of their ban.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Appeal {
+	/// The appeal's ID.
+	pub id: AppealID,
+
+	/// The ID of the ban that is being appealed.
+	pub ban_id: BanID,
+
+	/// The banned player.
+	pub player: Player,
+
+	/// The player's message.
+	pub message: String,
+
+	/// The current status of the appeal.
+	pub status: AppealStatus,
+
+	/// The admin who resolved the appeal.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub admin: Option<Player>,
+
+	/// The admin's response.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub response: Option<String>,
+
+	/// When this appeal was submitted.
+	pub created_on: DateTime<Utc>,
+
+	/// When this appeal was resolved.
+	pub resolved_on: Option<DateTime<Utc>>,
+}
+
+impl FromRow<'_, MySqlRow> for Appeal {
+	fn from_row(row: &MySqlRow) -> sqlx::Result<Self> {
+		Ok(Self {
+			id: row.try_get("id")?,
+			ban_id: row.try_get("ban_id")?,
+			player: Player::from_row(row)?,
+			message: row.try_get("message")?,
+			status: row.try_get("status")?,
+			admin: row
+				.try_get("admin_name")
+				.and_then(|name| Ok((name, row.try_get("admin_id")?)))
+				.map(|(name, steam_id)| Player { name, steam_id })
+				.ok(),
+			response: row.try_get("response")?,
+			created_on: row.try_get("created_on")?,
+			resolved_on: row.try_get("resolved_on")?,
+		})
+	}
+}
+
+/// The status of an [`Appeal`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AppealStatus {
+	/// The appeal has not been looked at yet.
+	Pending,
+
+	/// The appeal was accepted, and the ban has been reverted.
+	Accepted,
+
+	/// The appeal was rejected.
+	Rejected,
+}
+
+impl AppealStatus {
+	/// Stringified version that is also expected when parsing a string into an
+	/// [`AppealStatus`].
+	pub const fn as_str(&self) -> &'static str {
+		match self {
+			AppealStatus::Pending => "pending",
+			AppealStatus::Accepted => "accepted",
+			AppealStatus::Rejected => "rejected",
+		}
+	}
+}
+
+/// An error for parsing appeal statuses.
+#[derive(Debug, Error)]
+#[error("`{0}` is not a valid appeal status")]
+pub struct InvalidAppealStatus(String);
+
+impl FromStr for AppealStatus {
+	type Err = InvalidAppealStatus;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		match value {
+			"pending" => Ok(Self::Pending),
+			"accepted" => Ok(Self::Accepted),
+			"rejected" => Ok(Self::Rejected),
+			invalid => Err(InvalidAppealStatus(invalid.to_owned())),
+		}
+	}
+}
+
+impl sqlx::Type<MySql> for AppealStatus {
+	fn type_info() -> <MySql as sqlx::Database>::TypeInfo {
+		<str as sqlx::Type<MySql>>::type_info()
+	}
+}
+
+impl<'q> sqlx::Encode<'q, MySql> for AppealStatus {
+	fn encode_by_ref(
+		&self,
+		buf: &mut <MySql as database::HasArguments<'q>>::ArgumentBuffer,
+	) -> sqlx::encode::IsNull {
+		<&'q str as sqlx::Encode<'q, MySql>>::encode_by_ref(&self.as_str(), buf)
+	}
+}
+
+impl<'q> sqlx::Decode<'q, MySql> for AppealStatus {
+	fn decode(
+		value: <MySql as database::HasValueRef<'q>>::ValueRef,
+	) -> Result<Self, sqlx::error::BoxDynError> {
+		Ok(<&'q str as sqlx::Decode<'q, MySql>>::decode(value)
+			.map(|value| value.parse::<Self>())??)
+	}
+}
+
+/// Request payload for appealing a ban.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct NewAppeal {
+	/// Why the ban should be reverted.
+	pub message: String,
+}
+
+/// Response body for appealing a ban.
+#[derive(Debug, Clone, Copy, Serialize, ToSchema)]
+pub struct CreatedAppeal {
+	/// The appeal's ID.
+	pub appeal_id: AppealID,
+}
+
+/// Request payload for resolving an appeal.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AppealResolution {
+	/// Whether the appeal is accepted or rejected.
+	///
+	/// Accepting an appeal reverts the ban.
+	pub status: AppealStatus,
+
+	/// A response to the player.
+	///
+	/// If the appeal is accepted, this is also used as the reason for the unban.
+	pub response: String,
+}
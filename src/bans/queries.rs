@@ -9,6 +9,7 @@ pub static SELECT: &str = r#"
 	  s.name server_name,
 	  s.id server_id,
 	  b.reason,
+	  b.note,
 	  a.name admin_name,
 	  a.id admin_id,
 	  b.created_on,
@@ -26,3 +27,40 @@ pub static SELECT: &str = r#"
 	  LEFT JOIN Unbans ub ON ub.ban_id = b.id
 	  LEFT JOIN Players a2 ON a2.id = ub.admin_id
 "#;
+
+/// SQL query for `SELECT`ing IP range bans from the database.
+pub static SELECT_IP_RANGES: &str = r#"
+	SELECT
+	  b.id,
+	  b.network,
+	  b.prefix_length,
+	  b.reason,
+	  a.name admin_name,
+	  a.id admin_id,
+	  b.created_on,
+	  b.expires_on
+	FROM
+	  BannedIpRanges b
+	  LEFT JOIN Players a ON a.id = b.admin_id
+"#;
+
+/// SQL query for `SELECT`ing ban appeals from the database.
+pub static SELECT_APPEALS: &str = r#"
+	SELECT SQL_CALC_FOUND_ROWS
+	  ap.id,
+	  ap.ban_id,
+	  p.name player_name,
+	  p.id player_id,
+	  ap.message,
+	  ap.status,
+	  a.name admin_name,
+	  a.id admin_id,
+	  ap.response,
+	  ap.created_on,
+	  ap.resolved_on
+	FROM
+	  Appeals ap
+	  JOIN Bans b ON b.id = ap.ban_id
+	  JOIN Players p ON p.id = b.player_id
+	  LEFT JOIN Players a ON a.id = ap.admin_id
+"#;
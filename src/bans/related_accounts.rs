@@ -0,0 +1,98 @@
+//! Finding accounts that might be used to evade bans.
+//!
+//! Banned players often come back on a new account, and more often than not they connect from
+//! the same IP address. This module only surfaces candidates for admins to review; nobody gets
+//! banned automatically.
+
+use chrono::{DateTime, Utc};
+use cs2kz::SteamID;
+use sqlx::MySqlExecutor;
+
+use crate::bans::RelatedAccount;
+use crate::players::Player;
+use crate::Result;
+
+/// Finds other accounts that have been seen with the same IP address(es) as `player_id`.
+///
+/// We know a player's IP address from their profile, as well as from every ban they received.
+/// Accounts sharing more addresses are ranked higher, followed by accounts seen more recently.
+pub async fn find_related_accounts<'c, E>(
+	player_id: SteamID,
+	limit: u64,
+	executor: E,
+) -> Result<Vec<RelatedAccount>>
+where
+	E: MySqlExecutor<'c>,
+{
+	let accounts = sqlx::query! {
+		r#"
+		WITH
+		  BannedAddresses AS (
+		    SELECT
+		      ip_address ip
+		    FROM
+		      Players
+		    WHERE
+		      id = ?
+		    UNION
+		    SELECT
+		      player_ip
+		    FROM
+		      Bans
+		    WHERE
+		      player_id = ?
+		  ),
+		  SeenAddresses AS (
+		    SELECT
+		      id player_id,
+		      ip_address ip,
+		      last_seen_on seen_on
+		    FROM
+		      Players
+		    UNION ALL
+		    SELECT
+		      player_id,
+		      player_ip,
+		      created_on
+		    FROM
+		      Bans
+		  )
+		SELECT
+		  p.name player_name,
+		  p.id `player_id: SteamID`,
+		  CAST(COUNT(DISTINCT s.ip) AS UNSIGNED) `shared_addresses!: u64`,
+		  MAX(s.seen_on) `last_seen_on!: DateTime<Utc>`
+		FROM
+		  SeenAddresses s
+		  JOIN BannedAddresses b ON b.ip = s.ip
+		  JOIN Players p ON p.id = s.player_id
+		WHERE
+		  s.player_id != ?
+		GROUP BY
+		  p.id
+		ORDER BY
+		  shared_addresses DESC,
+		  last_seen_on DESC
+		LIMIT
+		  ?
+		"#,
+		player_id,
+		player_id,
+		player_id,
+		limit,
+	}
+	.fetch_all(executor)
+	.await?
+	.into_iter()
+	.map(|row| RelatedAccount {
+		player: Player {
+			name: row.player_name,
+			steam_id: row.player_id,
+		},
+		shared_addresses: row.shared_addresses,
+		last_seen_on: row.last_seen_on,
+	})
+	.collect();
+
+	Ok(accounts)
+}
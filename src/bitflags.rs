@@ -51,6 +51,16 @@ macro_rules! bitflags {
 				(self.0 & other.0) == other.0
 			}
 
+			/// Returns all the bits that are set in either `self` or `other`.
+			pub const fn union(self, other: Self) -> Self {
+				Self(self.0 | other.0)
+			}
+
+			/// Returns all the bits that are set in `self` but not in `other`.
+			pub const fn difference(self, other: Self) -> Self {
+				Self(self.0 & !other.0)
+			}
+
 			/// The all-zeroes bit pattern.
 			pub const NONE: Self = Self(0);
 
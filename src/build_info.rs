@@ -0,0 +1,49 @@
+//! Information about the running build.
+//!
+//! This is embedded at compile time by `build.rs`, and lets us correlate the behavior of a
+//! running instance with a specific commit.
+
+use axum::http::HeaderValue;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Information about the running build.
+#[derive(Debug, Clone, Copy, Serialize, ToSchema)]
+pub struct BuildInfo {
+	/// The API's version.
+	pub version: &'static str,
+
+	/// The git revision the API was built from.
+	///
+	/// This is `"unknown"` if it could not be determined at build time.
+	pub git_sha: &'static str,
+
+	/// When the API was built.
+	pub built_at: DateTime<Utc>,
+}
+
+impl BuildInfo {
+	/// Returns information about the running build.
+	pub fn current() -> Self {
+		let built_at = env!("CS2KZ_API_BUILT_AT")
+			.parse::<i64>()
+			.ok()
+			.and_then(|secs| DateTime::from_timestamp(secs, 0))
+			.unwrap_or_default();
+
+		Self {
+			version: env!("CARGO_PKG_VERSION"),
+			git_sha: env!("CS2KZ_API_GIT_SHA"),
+			built_at,
+		}
+	}
+
+	/// Returns the value we send in the `Server` header of every response.
+	pub fn server_header(&self) -> HeaderValue {
+		let git_sha = self.git_sha.get(..7).unwrap_or(self.git_sha);
+
+		HeaderValue::from_str(&format!("cs2kz-api/{} ({git_sha})", self.version))
+			.unwrap_or_else(|_| HeaderValue::from_static("cs2kz-api"))
+	}
+}
@@ -0,0 +1,100 @@
+//! CRC32 checksums.
+//!
+//! Map files are identified by the CRC32 checksum of their `.vpk` file, which the plugin compares
+//! against the file it has loaded.
+
+use std::fmt;
+use std::str::FromStr;
+
+use derive_more::{From, Into};
+use thiserror::Error;
+use tokio::io::{self, AsyncRead, AsyncReadExt};
+
+/// How many bytes are read at once when hashing a reader.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// A CRC32 checksum (the IEEE polynomial, as used by zlib & friends).
+///
+/// Checksums are 4 bytes long. Their textual representation, as produced by the [`Display`]
+/// implementation and accepted by the [`FromStr`] implementation, is exactly 8 lowercase hex
+/// digits, e.g. `0badc0de`.
+///
+/// In the database and JSON responses checksums are stored as plain integers; use the
+/// [`From`] / [`Into`] implementations to convert.
+///
+/// [`Display`]: fmt::Display
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, From, Into)]
+pub struct Checksum(u32);
+
+impl Checksum {
+	/// The length of a checksum, in bytes.
+	pub const LEN: usize = 4;
+
+	/// Returns the checksum as a raw integer.
+	pub const fn get(self) -> u32 {
+		self.0
+	}
+}
+
+impl fmt::Display for Checksum {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{:08x}", self.0)
+	}
+}
+
+/// An error for parsing a [`Checksum`] from a string.
+#[derive(Debug, Clone, Copy, Error)]
+pub enum ParseChecksumError {
+	/// The string did not have the correct length.
+	#[error("checksum must be exactly {} hex digits, but got {actual}", Checksum::LEN * 2)]
+	InvalidLength {
+		/// The length of the string we got.
+		actual: usize,
+	},
+
+	/// The string contained something other than hex digits.
+	#[error("checksum must only contain hex digits")]
+	InvalidDigit,
+}
+
+impl FromStr for Checksum {
+	type Err = ParseChecksumError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		if s.len() != Self::LEN * 2 {
+			return Err(ParseChecksumError::InvalidLength { actual: s.len() });
+		}
+
+		// `from_str_radix` would also accept a leading `+`
+		if !s.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+			return Err(ParseChecksumError::InvalidDigit);
+		}
+
+		u32::from_str_radix(s, 16)
+			.map(Self)
+			.map_err(|_| ParseChecksumError::InvalidDigit)
+	}
+}
+
+/// Computes the checksum of everything that can be read from `reader`.
+///
+/// The data is hashed incrementally as it is read, so it never has to be fully buffered in
+/// memory. This also means it can be used to hash data while it is still being downloaded.
+pub async fn from_async_reader<R>(reader: &mut R) -> io::Result<Checksum>
+where
+	R: AsyncRead + Unpin + ?Sized,
+{
+	let mut hasher = crc32fast::Hasher::new();
+	let mut buf = vec![0; CHUNK_SIZE];
+
+	loop {
+		let n = reader.read(&mut buf).await?;
+		let Some(data) = buf.get(..n).filter(|data| !data.is_empty()) else {
+			break;
+		};
+
+		hasher.update(data);
+	}
+
+	Ok(Checksum(hasher.finalize()))
+}
@@ -3,16 +3,49 @@
 //! When starting up, the API will read configuration values from the environment to construct a
 //! [`Config`]. It will then be part of the global application state.
 
+use std::collections::HashMap;
 use std::env;
 use std::error::Error as StdError;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::num::NonZeroU32;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::Duration;
 
 use anyhow::Context;
 use derive_more::Debug;
 use url::Url;
 
+use crate::authentication::discord;
+use crate::steam;
+
+/// The default value for [`Config::slow_query_threshold`].
+const DEFAULT_SLOW_QUERY_THRESHOLD: Duration = Duration::from_secs(1);
+
+/// The default value for [`Config::default_server_budget`].
+const DEFAULT_SERVER_BUDGET: u16 = 3;
+
+/// The default value for [`Config::duplicate_record_window`].
+const DEFAULT_DUPLICATE_RECORD_WINDOW: Duration = Duration::from_secs(5);
+
+/// The default value for [`Config::max_body_bytes`].
+const DEFAULT_MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// The default value for [`Config::max_map_body_bytes`].
+const DEFAULT_MAX_MAP_BODY_BYTES: usize = 1024 * 1024;
+
+/// The default value for [`Config::rate_limit_per_minute`].
+const DEFAULT_RATE_LIMIT_PER_MINUTE: NonZeroU32 = match NonZeroU32::new(600) {
+	Some(n) => n,
+	None => unreachable!(),
+};
+
+/// The default value for [`Config::auth_rate_limit_per_minute`].
+const DEFAULT_AUTH_RATE_LIMIT_PER_MINUTE: NonZeroU32 = match NonZeroU32::new(30) {
+	Some(n) => n,
+	None => unreachable!(),
+};
+
 /// The API's runtime configuration.
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -24,6 +57,55 @@ pub struct Config {
 	#[debug("{}", database_url.as_str())]
 	pub database_url: Url,
 
+	/// The URL of a read replica to use for expensive read-only queries.
+	///
+	/// If this is not set, those queries run against [`Config::database_url`] instead.
+	#[debug("{:?}", database_read_url.as_ref().map(Url::as_str))]
+	pub database_read_url: Option<Url>,
+
+	/// Database queries that take longer than this will be logged as warnings, and counted in
+	/// the [metrics].
+	///
+	/// [metrics]: crate::metrics
+	#[debug("{slow_query_threshold:?}")]
+	pub slow_query_threshold: Duration,
+
+	/// How many servers a player can own if they don't have an explicit budget.
+	#[debug("{default_server_budget}")]
+	pub default_server_budget: u16,
+
+	/// Records identical to one submitted by the same server less than this long ago are
+	/// rejected as duplicates.
+	///
+	/// A zero duration disables this check.
+	#[debug("{duplicate_record_window:?}")]
+	pub duplicate_record_window: Duration,
+
+	/// The maximum size of request bodies, in bytes.
+	#[debug("{max_body_bytes}")]
+	pub max_body_bytes: usize,
+
+	/// The maximum size of request bodies sent to the `/maps` routes, in bytes.
+	///
+	/// Maps can have a lot of courses and filters, so their payloads are larger than usual.
+	#[debug("{max_map_body_bytes}")]
+	pub max_map_body_bytes: usize,
+
+	/// How many requests a single client can make per minute.
+	#[debug("{rate_limit_per_minute}")]
+	pub rate_limit_per_minute: NonZeroU32,
+
+	/// How many requests a single client can make to the `/auth` routes per minute.
+	#[debug("{auth_rate_limit_per_minute}")]
+	pub auth_rate_limit_per_minute: NonZeroU32,
+
+	/// Reverse proxies the API runs behind.
+	///
+	/// Requests coming from one of these addresses are rate limited based on the client address
+	/// in their `X-Forwarded-For` header, rather than the proxy's address.
+	#[debug("{trusted_proxies:?}")]
+	pub trusted_proxies: Vec<IpAddr>,
+
 	/// The public URL of the API.
 	#[debug("{}", public_url.as_str())]
 	pub public_url: Url,
@@ -38,6 +120,9 @@ pub struct Config {
 	#[debug("*****")]
 	pub steam_api_key: String,
 
+	/// How failed requests to the Steam Web API are retried.
+	pub steam_retry_policy: steam::api::RetryPolicy,
+
 	/// Path to a directory where downloaded Workshop assets should be stored.
 	#[cfg(not(feature = "production"))]
 	pub workshop_artifacts_path: Option<PathBuf>,
@@ -61,55 +146,208 @@ pub struct Config {
 	/// JWT secret for encoding/decoding tokens.
 	#[debug("*****")]
 	pub jwt_secret: String,
+
+	/// Discord application credentials for linking Discord accounts.
+	///
+	/// If these are missing, linking Discord accounts is disabled.
+	pub discord_credentials: Option<discord::Credentials>,
 }
 
 impl Config {
 	/// Creates a new [`Config`] by reading environment variables.
 	pub fn new() -> anyhow::Result<Self> {
+		Self::with_overrides(&HashMap::new())
+	}
+
+	/// Creates a new [`Config`] by reading environment variables, preferring the values in
+	/// `overrides` if they exist.
+	///
+	/// Unlike loading a `.env` file into the environment, this does not modify the process'
+	/// environment, which is unsound once other threads are running.
+	pub fn with_overrides(overrides: &HashMap<String, String>) -> anyhow::Result<Self> {
 		tracing::debug!("loading configuration from environment");
 
-		let ip_addr = parse_from_env("KZ_API_IP")?;
-		let port = parse_from_env("KZ_API_PORT")?;
+		let ip_addr = parse_from_env(overrides, "KZ_API_IP")?;
+		let port = parse_from_env(overrides, "KZ_API_PORT")?;
 		let addr = SocketAddr::new(ip_addr, port);
-		let database_url = parse_from_env("DATABASE_URL")?;
-		let public_url = parse_from_env("KZ_API_PUBLIC_URL")?;
-		let cookie_domain = parse_from_env("KZ_API_COOKIE_DOMAIN")?;
-		let steam_api_key = parse_from_env("STEAM_WEB_API_KEY")?;
+		let database_url = parse_from_env(overrides, "DATABASE_URL")?;
+		let database_read_url = parse_from_env_opt(overrides, "DATABASE_READ_URL")?;
+		let slow_query_threshold = parse_from_env_opt(overrides, "KZ_API_SLOW_QUERY_THRESHOLD_MS")?
+			.map_or(DEFAULT_SLOW_QUERY_THRESHOLD, Duration::from_millis);
+		let default_server_budget = parse_from_env_opt(overrides, "KZ_API_DEFAULT_SERVER_BUDGET")?
+			.unwrap_or(DEFAULT_SERVER_BUDGET);
+		let duplicate_record_window =
+			parse_from_env_opt(overrides, "KZ_API_DUPLICATE_RECORD_WINDOW_MS")?
+				.map_or(DEFAULT_DUPLICATE_RECORD_WINDOW, Duration::from_millis);
+		let max_body_bytes = parse_from_env_opt(overrides, "KZ_API_MAX_BODY_BYTES")?
+			.unwrap_or(DEFAULT_MAX_BODY_BYTES);
+		let max_map_body_bytes = parse_from_env_opt(overrides, "KZ_API_MAX_MAP_BODY_BYTES")?
+			.unwrap_or(DEFAULT_MAX_MAP_BODY_BYTES);
+		let rate_limit_per_minute = parse_from_env_opt(overrides, "KZ_API_RATE_LIMIT_PER_MINUTE")?
+			.unwrap_or(DEFAULT_RATE_LIMIT_PER_MINUTE);
+		let auth_rate_limit_per_minute =
+			parse_from_env_opt(overrides, "KZ_API_AUTH_RATE_LIMIT_PER_MINUTE")?
+				.unwrap_or(DEFAULT_AUTH_RATE_LIMIT_PER_MINUTE);
+		let trusted_proxies = parse_from_env_opt::<String>(overrides, "KZ_API_TRUSTED_PROXIES")?
+			.map(|proxies| {
+				proxies
+					.split(',')
+					.map(|addr| addr.trim().parse::<IpAddr>())
+					.collect::<Result<Vec<_>, _>>()
+			})
+			.transpose()
+			.context("failed to parse `KZ_API_TRUSTED_PROXIES`")?
+			.unwrap_or_default();
+		let public_url = parse_from_env(overrides, "KZ_API_PUBLIC_URL")?;
+		let cookie_domain = parse_from_env::<String>(overrides, "KZ_API_COOKIE_DOMAIN")?;
+		let steam_api_key = parse_from_env(overrides, "STEAM_WEB_API_KEY")?;
+		let steam_retry_policy = {
+			let default = steam::api::RetryPolicy::default();
+
+			steam::api::RetryPolicy {
+				max_retries: parse_from_env_opt(overrides, "KZ_API_STEAM_MAX_RETRIES")?
+					.unwrap_or(default.max_retries),
+				initial_backoff: parse_from_env_opt(overrides, "KZ_API_STEAM_INITIAL_BACKOFF_MS")?
+					.map_or(default.initial_backoff, Duration::from_millis),
+				max_backoff: parse_from_env_opt(overrides, "KZ_API_STEAM_MAX_BACKOFF_MS")?
+					.map_or(default.max_backoff, Duration::from_millis),
+			}
+		};
 
 		#[cfg(not(feature = "production"))]
-		let workshop_artifacts_path = parse_from_env_opt("KZ_API_WORKSHOP_PATH")?;
+		let workshop_artifacts_path = parse_from_env_opt(overrides, "KZ_API_WORKSHOP_PATH")?;
 
 		#[cfg(feature = "production")]
-		let workshop_artifacts_path = parse_from_env("KZ_API_WORKSHOP_PATH")?;
+		let workshop_artifacts_path = parse_from_env(overrides, "KZ_API_WORKSHOP_PATH")?;
 
 		#[cfg(not(feature = "production"))]
-		let depot_downloader_path = parse_from_env_opt("DEPOT_DOWNLOADER_PATH")?;
+		let depot_downloader_path = parse_from_env_opt(overrides, "DEPOT_DOWNLOADER_PATH")?;
 
 		#[cfg(feature = "production")]
-		let depot_downloader_path = parse_from_env("DEPOT_DOWNLOADER_PATH")?;
+		let depot_downloader_path = parse_from_env(overrides, "DEPOT_DOWNLOADER_PATH")?;
 
-		let jwt_secret = parse_from_env("KZ_API_JWT_SECRET")?;
+		let jwt_secret = parse_from_env(overrides, "KZ_API_JWT_SECRET")?;
+		let discord_credentials = match (
+			parse_from_env_opt(overrides, "DISCORD_CLIENT_ID")?,
+			parse_from_env_opt(overrides, "DISCORD_CLIENT_SECRET")?,
+		) {
+			(Some(client_id), Some(client_secret)) => Some(discord::Credentials {
+				client_id,
+				client_secret,
+			}),
+			(None, None) => None,
+			(Some(_), None) | (None, Some(_)) => {
+				anyhow::bail!(
+					"`DISCORD_CLIENT_ID` and `DISCORD_CLIENT_SECRET` must either both be set or \
+					 both be unset"
+				);
+			}
+		};
+
+		if steam_retry_policy.initial_backoff > steam_retry_policy.max_backoff {
+			anyhow::bail!(
+				"`KZ_API_STEAM_INITIAL_BACKOFF_MS` cannot be larger than \
+				 `KZ_API_STEAM_MAX_BACKOFF_MS`"
+			);
+		}
+
+		if !is_cookie_domain_for(&cookie_domain, &public_url) {
+			anyhow::bail!(
+				"`KZ_API_COOKIE_DOMAIN` (`{cookie_domain}`) does not match the host of \
+				 `KZ_API_PUBLIC_URL` (`{public_url}`)"
+			);
+		}
 
 		Ok(Self {
 			addr,
 			database_url,
+			database_read_url,
+			slow_query_threshold,
+			default_server_budget,
+			duplicate_record_window,
+			max_body_bytes,
+			max_map_body_bytes,
+			rate_limit_per_minute,
+			auth_rate_limit_per_minute,
+			trusted_proxies,
 			public_url,
 			cookie_domain,
 			steam_api_key,
+			steam_retry_policy,
 			workshop_artifacts_path,
 			depot_downloader_path,
 			jwt_secret,
+			discord_credentials,
 		})
 	}
 }
 
+impl Config {
+	/// Fields that can't be changed without restarting the API.
+	pub const STRUCTURAL_FIELDS: &'static [&'static str] =
+		&["addr", "database_url", "database_read_url"];
+
+	/// Returns the names of all the fields that differ between `self` and `other`.
+	pub fn changed_fields(&self, other: &Self) -> Vec<&'static str> {
+		#[allow(clippy::missing_docs_in_private_items)]
+		macro_rules! changed {
+			($($field:ident),* $(,)?) => {
+				[$((stringify!($field), self.$field != other.$field)),*]
+					.into_iter()
+					.filter_map(|(field, changed)| changed.then_some(field))
+					.collect()
+			};
+		}
+
+		changed![
+			addr,
+			database_url,
+			database_read_url,
+			slow_query_threshold,
+			default_server_budget,
+			duplicate_record_window,
+			max_body_bytes,
+			max_map_body_bytes,
+			rate_limit_per_minute,
+			auth_rate_limit_per_minute,
+			trusted_proxies,
+			public_url,
+			cookie_domain,
+			steam_api_key,
+			steam_retry_policy,
+			workshop_artifacts_path,
+			depot_downloader_path,
+			jwt_secret,
+			discord_credentials,
+		]
+	}
+}
+
+/// Checks whether cookies with the `Domain` field set to `cookie_domain` will be sent to `url`.
+fn is_cookie_domain_for(cookie_domain: &str, url: &Url) -> bool {
+	let cookie_domain = cookie_domain.trim_start_matches('.');
+
+	url.host_str().is_some_and(|host| {
+		host == cookie_domain
+			|| host
+				.strip_suffix(cookie_domain)
+				.is_some_and(|subdomain| subdomain.ends_with('.'))
+	})
+}
+
+/// Reads a variable from `overrides`, or the environment if it's not in there.
+fn env_var(overrides: &HashMap<String, String>, var: &str) -> Option<String> {
+	overrides.get(var).cloned().or_else(|| env::var(var).ok())
+}
+
 /// Parses a value from the environment.
-fn parse_from_env<T>(var: &str) -> anyhow::Result<T>
+fn parse_from_env<T>(overrides: &HashMap<String, String>, var: &str) -> anyhow::Result<T>
 where
 	T: FromStr,
 	T::Err: StdError + Send + Sync + 'static,
 {
-	let value = env::var(var).with_context(|| format!("missing `{var}` environment variable"))?;
+	let value =
+		env_var(overrides, var).with_context(|| format!("missing `{var}` environment variable"))?;
 
 	if value.is_empty() {
 		anyhow::bail!("`{var}` cannot be empty");
@@ -122,13 +360,15 @@ where
 ///
 /// Returns `Ok(None)` if the value does not exist, and `Err` if the value does exist, and parsing
 /// it failed.
-#[cfg(not(feature = "production"))]
-fn parse_from_env_opt<T>(var: &str) -> anyhow::Result<Option<T>>
+fn parse_from_env_opt<T>(
+	overrides: &HashMap<String, String>,
+	var: &str,
+) -> anyhow::Result<Option<T>>
 where
 	T: FromStr,
 	T::Err: StdError + Send + Sync + 'static,
 {
-	let Some(value) = env::var(var).ok() else {
+	let Some(value) = env_var(overrides, var) else {
 		return Ok(None);
 	};
 
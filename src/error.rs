@@ -15,21 +15,26 @@
 use std::fmt::{self, Formatter};
 use std::io;
 use std::panic::Location;
+use std::time::Duration;
 
 use axum::extract::rejection::PathRejection;
-use axum::http::StatusCode;
+use axum::http::{header, StatusCode};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
 use axum_extra::typed_header::TypedHeaderRejection;
+use cs2kz::Tier;
 use derive_more::Display;
 use itertools::Itertools;
 use serde_json::json;
 use thiserror::Error;
 
 use crate::authorization::Permissions;
-use crate::bans::{BanID, UnbanID};
+use crate::bans::{BanID, IpRangeBanID, UnbanID};
 use crate::make_id::ConvertIDError;
 use crate::maps::{CourseID, FilterID, MapID};
+use crate::plugin::PluginVersionID;
+use crate::records::RecordID;
+use crate::sqlx::SqlErrorExt;
 
 /// Type alias for a [`Result<T, E>`] with its `E` parameter set to [`Error`].
 ///
@@ -97,6 +102,12 @@ enum ErrorKind {
 	#[error("no content")]
 	NoContent,
 
+	#[error("too many requests; try again in {} seconds", retry_after.as_secs().max(1))]
+	RateLimited { retry_after: Duration },
+
+	#[error("request body is too large (limit is {max_bytes} bytes)")]
+	PayloadTooLarge { max_bytes: usize },
+
 	#[error("could not find {what}")]
 	NotFound { what: String },
 
@@ -112,6 +123,9 @@ enum ErrorKind {
 	#[error("you are not logged in")]
 	MissingSessionID,
 
+	#[error("missing or invalid CSRF token")]
+	CsrfMismatch,
+
 	#[error("{UNAUTHORIZED_MSG}")]
 	InsufficientPermissions { required_permissions: Permissions },
 
@@ -135,6 +149,18 @@ enum ErrorKind {
 		course_id: CourseID,
 	},
 
+	#[error("filter `{filter_id}` cannot be ranked; tier `{tier}` is too high")]
+	InvalidRankedFilter { filter_id: FilterID, tier: Tier },
+
+	#[error("ip address is banned (ip range ban `{ban_id}`)")]
+	BannedIpAddress { ban_id: IpRangeBanID },
+
+	#[error("ban `{ban_id}` is not active anymore")]
+	BanNotActive { ban_id: BanID },
+
+	#[error("ban `{ban_id}` belongs to another player")]
+	NotBannedPlayer { ban_id: BanID },
+
 	#[error("ban `{ban_id}` was already reverted by unban `{unban_id}`")]
 	BanAlreadyReverted { ban_id: BanID, unban_id: UnbanID },
 
@@ -144,6 +170,18 @@ enum ErrorKind {
 		latest: semver::Version,
 	},
 
+	#[error("plugin version `{plugin_version_id}` has been yanked, please upgrade: {reason}")]
+	YankedPluginVersion {
+		plugin_version_id: PluginVersionID,
+		reason: String,
+	},
+
+	#[error("server owner already owns {current} servers (budget is {budget})")]
+	ServerBudgetExceeded { budget: u16, current: u64 },
+
+	#[error("this record was already submitted as record #{record_id}")]
+	DuplicateRecord { record_id: RecordID },
+
 	#[error("logic assertion failed: {0}")]
 	Logic(String),
 
@@ -177,6 +215,12 @@ enum ErrorKind {
 	#[error("external api call failed: {0}")]
 	ExternalApiCall(reqwest::Error),
 
+	#[error("steam api is unavailable (gave up after {attempts} attempts)")]
+	SteamApiRetriesExhausted { attempts: u32 },
+
+	#[error("{what} is not configured on this instance")]
+	NotConfigured { what: &'static str },
+
 	#[error(transparent)]
 	Header(#[from] TypedHeaderRejection),
 
@@ -255,6 +299,13 @@ impl Error {
 		self
 	}
 
+	/// Checks whether this error was caused by a transient database failure, like a deadlock.
+	///
+	/// Retrying the transaction that caused such an error may succeed.
+	pub(crate) fn is_transient(&self) -> bool {
+		matches!(&self.kind, ErrorKind::Database(error) if error.is_deadlock() || error.is_lock_wait_timeout())
+	}
+
 	/// A generic `204 No Content` error.
 	///
 	/// This should be returned from `PUT` / `PATCH` / `DELETE` handlers, as well as `GET`
@@ -320,6 +371,16 @@ impl Error {
 		Self::new(ErrorKind::MissingSessionID)
 	}
 
+	/// An error signaling a missing or mismatching CSRF token.
+	///
+	/// For more information about CSRF protection, see [`crate::authentication::csrf`].
+	///
+	/// Produces a `403 Forbidden` status.
+	#[track_caller]
+	pub(crate) fn csrf_mismatch() -> Self {
+		Self::new(ErrorKind::CsrfMismatch)
+	}
+
 	/// An error signaling an authorization failure caused by insufficient permissions.
 	///
 	/// For more information about permissions, see [`crate::authorization::Permissions`] and
@@ -336,7 +397,7 @@ impl Error {
 	/// An error signaling an authorization failure caused by the requesting user not
 	/// being a server owner.
 	///
-	/// For more information, see [`crate::authorization::IsServerAdminOrOwner`].
+	/// For more information, see [`crate::authorization::IsServerOwner`].
 	///
 	/// Produces a `401 Unauthorized` status.
 	#[track_caller]
@@ -396,6 +457,20 @@ impl Error {
 		})
 	}
 
+	/// An error that can occur when updating [maps].
+	///
+	/// Filters with a tier above [`Tier::Death`] are not humanly possible, and must not be
+	/// ranked. This is already checked for new maps, but updates can change the tier and
+	/// ranked status of existing filters independently.
+	///
+	/// Produces a `409 Conflict` status.
+	///
+	/// [maps]: crate::maps
+	#[track_caller]
+	pub(crate) fn invalid_ranked_filter(filter_id: FilterID, tier: Tier) -> Self {
+		Self::new(ErrorKind::InvalidRankedFilter { filter_id, tier })
+	}
+
 	/// An error that can occur when [unbanning] players.
 	///
 	/// Any given ban can only ever be reverted once. When an unban request is made for a ban
@@ -409,6 +484,44 @@ impl Error {
 		Self::new(ErrorKind::BanAlreadyReverted { ban_id, unban_id })
 	}
 
+	/// An error that can occur when [appealing] bans.
+	///
+	/// Only bans that are currently active can be appealed.
+	///
+	/// Produces a `409 Conflict` status.
+	///
+	/// [appealing]: crate::bans::appeals
+	#[track_caller]
+	pub(crate) fn ban_not_active(ban_id: BanID) -> Self {
+		Self::new(ErrorKind::BanNotActive { ban_id })
+	}
+
+	/// An error that can occur when [appealing] bans.
+	///
+	/// Players can only appeal their own bans.
+	///
+	/// Produces a `403 Forbidden` status.
+	///
+	/// [appealing]: crate::bans::appeals
+	#[track_caller]
+	pub(crate) fn not_banned_player(ban_id: BanID) -> Self {
+		Self::new(ErrorKind::NotBannedPlayer { ban_id })
+	}
+
+	/// An error that can occur when registering new [players].
+	///
+	/// If the player is connecting from an IP address that is part of a [banned range], they
+	/// should not be able to create a new account.
+	///
+	/// Produces a `403 Forbidden` status.
+	///
+	/// [players]: crate::players
+	/// [banned range]: crate::bans::ip_ranges
+	#[track_caller]
+	pub(crate) fn banned_ip_address(ban_id: IpRangeBanID) -> Self {
+		Self::new(ErrorKind::BannedIpAddress { ban_id })
+	}
+
 	/// An error that can occur when submitting new CS2KZ plugin versions.
 	///
 	/// The API keeps track of all the versions, and if a new version is submitted that is
@@ -423,6 +536,52 @@ impl Error {
 		Self::new(ErrorKind::OutdatedPluginVersion { submitted, latest })
 	}
 
+	/// An error that can occur when CS2 servers authenticate with the API.
+	///
+	/// Servers running a [yanked] plugin version are rejected, and told why, so they know to
+	/// upgrade.
+	///
+	/// Produces a `403 Forbidden` status.
+	///
+	/// [yanked]: crate::plugin::yank
+	#[track_caller]
+	pub(crate) fn yanked_plugin_version(
+		plugin_version_id: PluginVersionID,
+		reason: String,
+	) -> Self {
+		Self::new(ErrorKind::YankedPluginVersion {
+			plugin_version_id,
+			reason,
+		})
+	}
+
+	/// An error that can occur when creating new [servers].
+	///
+	/// Every player can only own a limited amount of servers. If the owner of a new server
+	/// already owns as many servers as their budget allows, that should produce an error.
+	///
+	/// Produces a `409 Conflict` status.
+	///
+	/// [servers]: crate::servers
+	#[track_caller]
+	pub(crate) fn server_budget_exceeded(budget: u16, current: u64) -> Self {
+		Self::new(ErrorKind::ServerBudgetExceeded { budget, current })
+	}
+
+	/// An error that can occur when submitting new [records].
+	///
+	/// Servers should only submit every run once. If a record is identical to one the same
+	/// server submitted just before, it is most likely a retry or a misbehaving server, and
+	/// it is rejected instead of being inserted again.
+	///
+	/// Produces a `409 Conflict` status.
+	///
+	/// [records]: crate::records
+	#[track_caller]
+	pub(crate) fn duplicate_record(record_id: RecordID) -> Self {
+		Self::new(ErrorKind::DuplicateRecord { record_id })
+	}
+
 	/// A generic `500 Internal Server Error`.
 	///
 	/// This constructor is reserved for errors that _should not_ occur, but _may_ occur. If
@@ -509,13 +668,57 @@ impl Error {
 		Self::new(ErrorKind::Checksum(source))
 	}
 
+	/// An error that is returned by the [body limit] middleware if a request body is too large.
+	///
+	/// Produces a `413 Payload Too Large` status.
+	///
+	/// [body limit]: crate::middleware::body_limit
+	#[track_caller]
+	pub(crate) fn payload_too_large(max_bytes: usize) -> Self {
+		Self::new(ErrorKind::PayloadTooLarge { max_bytes })
+	}
+
+	/// An error that is returned by the [rate limiter] if a client sent too many requests.
+	///
+	/// Produces a `429 Too Many Requests` status with a `Retry-After` header.
+	///
+	/// [rate limiter]: crate::middleware::rate_limit
+	#[track_caller]
+	pub(crate) fn rate_limited(retry_after: Duration) -> Self {
+		Self::new(ErrorKind::RateLimited { retry_after })
+	}
+
 	/// An error that can occur when making HTTP requests to external APIs such as the Steam
 	/// Web API.
 	///
+	/// The request URL is stripped from the error, as it may contain API keys.
+	///
 	/// Produces a `502 Bad Gateway` status.
 	#[track_caller]
 	pub(crate) fn external_api_call(source: reqwest::Error) -> Self {
-		Self::new(ErrorKind::ExternalApiCall(source))
+		Self::new(ErrorKind::ExternalApiCall(source.without_url()))
+	}
+
+	/// An error that can occur when making requests to the [Steam Web API].
+	///
+	/// Requests that fail because of rate limits or server errors are retried a few times; if
+	/// none of the attempts succeed, this error is returned.
+	///
+	/// Produces a `503 Service Unavailable` status.
+	///
+	/// [Steam Web API]: crate::steam::api
+	#[track_caller]
+	pub(crate) fn steam_api_retries_exhausted(attempts: u32) -> Self {
+		Self::new(ErrorKind::SteamApiRetriesExhausted { attempts })
+	}
+
+	/// An error signaling that an optional integration (e.g. Discord) was not configured, so
+	/// the endpoints relying on it are unavailable.
+	///
+	/// Produces a `503 Service Unavailable` status.
+	#[track_caller]
+	pub(crate) fn not_configured(what: &'static str) -> Self {
+		Self::new(ErrorKind::NotConfigured { what })
 	}
 }
 
@@ -533,13 +736,23 @@ impl IntoResponse for Error {
 			| E::MissingSessionID
 			| E::InsufficientPermissions { .. }
 			| E::MustBeServerOwner => StatusCode::UNAUTHORIZED,
+			E::CsrfMismatch
+			| E::NotBannedPlayer { .. }
+			| E::BannedIpAddress { .. }
+			| E::YankedPluginVersion { .. } => StatusCode::FORBIDDEN,
 			E::NotFound { .. } => StatusCode::NOT_FOUND,
+			E::PayloadTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+			E::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
 			E::AlreadyExists { .. }
 			| E::MustHaveMappers
 			| E::MismatchingMapCourse { .. }
 			| E::MismatchingCourseFilter { .. }
+			| E::InvalidRankedFilter { .. }
+			| E::BanNotActive { .. }
 			| E::BanAlreadyReverted { .. }
-			| E::OutdatedPluginVersion { .. } => StatusCode::CONFLICT,
+			| E::OutdatedPluginVersion { .. }
+			| E::ServerBudgetExceeded { .. }
+			| E::DuplicateRecord { .. } => StatusCode::CONFLICT,
 			E::Logic(_)
 			| E::Database(_)
 			| E::Jwt(_)
@@ -554,6 +767,9 @@ impl IntoResponse for Error {
 			}
 
 			E::ExternalApiCall(_) => StatusCode::BAD_GATEWAY,
+			E::SteamApiRetriesExhausted { .. } | E::NotConfigured { .. } => {
+				StatusCode::SERVICE_UNAVAILABLE
+			}
 			E::Path(ref rej) => rej.status(),
 		};
 
@@ -582,6 +798,11 @@ impl IntoResponse for Error {
 				.into();
 		}
 
+		if let E::RateLimited { retry_after } = self.kind {
+			let retry_after = retry_after.as_secs().max(1).to_string();
+			return (status, [(header::RETRY_AFTER, retry_after)], Json(json)).into_response();
+		}
+
 		(status, Json(json)).into_response()
 	}
 }
@@ -603,6 +824,9 @@ impl From<sqlx::Error> for Error {
 impl From<reqwest::Error> for Error {
 	#[track_caller]
 	fn from(error: reqwest::Error) -> Self {
+		// request URLs may contain API keys
+		let error = error.without_url();
+
 		if matches!(error.status(), Some(status) if status.is_server_error()) {
 			Self::new(ErrorKind::ExternalApiCall(error))
 		} else {
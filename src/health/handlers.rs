@@ -0,0 +1,49 @@
+//! HTTP handlers for the `/health` routes.
+
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use axum::Json;
+
+use crate::build_info::BuildInfo;
+use crate::health::{DatabaseHealth, Health};
+use crate::openapi::responses;
+use crate::sqlx::pool;
+use crate::State;
+
+/// How long we wait for the database to respond before considering it unhealthy.
+const DATABASE_PING_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Check the API's health.
+///
+/// If the API is unable to serve requests, e.g. because the database is unreachable, this
+/// returns a `503 Service Unavailable` status.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  get,
+  path = "/health",
+  tag = "Health",
+  responses(
+    responses::Ok<Health>,
+    responses::ServiceUnavailable,
+  ),
+)]
+pub async fn get(state: State) -> (StatusCode, Json<Health>) {
+	let reachable = pool::ping(&state.database, DATABASE_PING_TIMEOUT).await;
+	let health = Health {
+		healthy: reachable,
+		database: DatabaseHealth {
+			reachable,
+			pool: pool::stats(&state.database),
+		},
+		build: BuildInfo::current(),
+	};
+
+	let status = if health.healthy {
+		StatusCode::OK
+	} else {
+		StatusCode::SERVICE_UNAVAILABLE
+	};
+
+	(status, Json(health))
+}
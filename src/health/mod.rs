@@ -0,0 +1,22 @@
+//! Health checks.
+//!
+//! The `/health` route is used by load balancers and monitoring to check whether an API instance
+//! is able to serve requests.
+
+use axum::{routing, Router};
+
+use crate::middleware::cors;
+use crate::State;
+
+mod models;
+pub use models::{DatabaseHealth, Health};
+
+pub mod handlers;
+
+/// Returns an [`axum::Router`] for the `/health` routes.
+pub fn router(state: State) -> Router {
+	Router::new()
+		.route("/", routing::get(handlers::get))
+		.route_layer(cors::permissive())
+		.with_state(state.clone())
+}
@@ -0,0 +1,30 @@
+//! Types for modeling health check results.
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::build_info::BuildInfo;
+use crate::sqlx::PoolStats;
+
+/// The result of a health check.
+#[derive(Debug, Clone, Copy, Serialize, ToSchema)]
+pub struct Health {
+	/// Whether the API is healthy.
+	pub healthy: bool,
+
+	/// Database health.
+	pub database: DatabaseHealth,
+
+	/// Information about the running build.
+	pub build: BuildInfo,
+}
+
+/// Database health.
+#[derive(Debug, Clone, Copy, Serialize, ToSchema)]
+pub struct DatabaseHealth {
+	/// Whether the database responded to a ping in time.
+	pub reachable: bool,
+
+	/// Connection pool statistics.
+	pub pool: PoolStats,
+}
@@ -13,6 +13,7 @@ use crate::openapi::parameters::{Limit, Offset};
 use crate::openapi::responses;
 use crate::openapi::responses::{Created, PaginationResponse};
 use crate::sqlx::{query, FetchID, FilteredQuery, QueryBuilderExt, SqlErrorExt};
+use crate::time::TimeRange;
 use crate::{Error, Result, State};
 
 /// Query parameters for `/jumpstats`.
@@ -40,6 +41,13 @@ pub struct GetParams {
 	/// Only include jumpstats submitted before this date.
 	created_before: Option<DateTime<Utc>>,
 
+	/// Only include jumpstats submitted within this range.
+	///
+	/// This is an alternative to `created_after` / `created_before`, e.g.
+	/// `2024-01-01..2024-02-01`.
+	#[param(value_type = Option<String>)]
+	range: Option<TimeRange>,
+
 	/// Maximum number of results to return.
 	#[serde(default)]
 	limit: Limit,
@@ -72,6 +80,7 @@ pub async fn get(
 		server,
 		created_after,
 		created_before,
+		range,
 		limit,
 		offset,
 	}): Query<GetParams>,
@@ -92,24 +101,21 @@ pub async fn get(
 	}
 
 	if let Some(player) = player {
-		let steam_id = player.fetch_id(transaction.as_mut()).await?;
+		let steam_id = player.fetch_id_unchecked(transaction.as_mut()).await?;
 
 		query.filter(" j.player_id = ", steam_id);
 	}
 
 	if let Some(server) = server {
-		let server_id = server.fetch_id(transaction.as_mut()).await?;
+		let server_id = server.fetch_id_unchecked(transaction.as_mut()).await?;
 
 		query.filter(" j.server_id = ", server_id);
 	}
 
-	if let Some(created_after) = created_after {
-		query.filter(" j.created_on > ", created_after);
-	}
-
-	if let Some(created_before) = created_before {
-		query.filter(" j.created_on < ", created_before);
-	}
+	query.filter_range(
+		"j.created_on",
+		TimeRange::between(created_after, created_before).intersect(range.unwrap_or_default()),
+	);
 
 	query.push_limits(limit, offset);
 
@@ -129,6 +135,7 @@ pub async fn get(
 	Ok(Json(PaginationResponse {
 		total,
 		results: jumpstats,
+		next_cursor: None,
 	}))
 }
 
@@ -256,3 +263,75 @@ pub async fn post(
 
 	Ok(Created(Json(CreatedJumpstat { jumpstat_id })))
 }
+
+#[cfg(test)]
+mod tests {
+	use crate::jumpstats::Jumpstat;
+
+	#[crate::integration_test]
+	async fn jumpstats_match_schema(ctx: &Context) {
+		sqlx::query! {
+			r#"
+			INSERT INTO
+			  Jumpstats (
+			    type,
+			    mode_id,
+			    strafes,
+			    distance,
+			    sync,
+			    pre,
+			    max,
+			    overlap,
+			    bad_angles,
+			    dead_air,
+			    height,
+			    airpath,
+			    deviation,
+			    average_width,
+			    airtime,
+			    player_id,
+			    server_id,
+			    plugin_version_id
+			  )
+			VALUES
+			  (
+			    1,
+			    1,
+			    5,
+			    250.0,
+			    90.0,
+			    276.0,
+			    300.0,
+			    0.0,
+			    0.0,
+			    0.0,
+			    64.0,
+			    1.0,
+			    0.0,
+			    10.0,
+			    0.75,
+			    76561198282622073,
+			    1,
+			    1
+			  )
+			"#,
+		}
+		.execute(&ctx.database)
+		.await?;
+
+		let response = ctx.http_client.get(ctx.url("/jumpstats")).send().await?;
+
+		assert_eq!(response.status(), 200);
+
+		let response = response.json::<serde_json::Value>().await?;
+		let jumpstats = response["results"]
+			.as_array()
+			.context("`results` should be an array")?;
+
+		assert!(!jumpstats.is_empty());
+
+		for jumpstat in jumpstats {
+			crate::test::validate_schema::<Jumpstat>(jumpstat)?;
+		}
+	}
+}
@@ -5,13 +5,18 @@
 use std::fmt::Write;
 use std::future::Future;
 use std::net::SocketAddr;
+use std::sync::Arc;
 
 use anyhow::Context;
 use axum::extract::connect_info::IntoMakeServiceWithConnectInfo;
 use axum::extract::ConnectInfo;
+use axum::http::header;
 use axum::{routing, Router};
 use tokio::net::TcpListener;
 use tokio::signal;
+use tower_http::set_header::SetResponseHeaderLayer;
+
+use crate::build_info::BuildInfo;
 
 mod error;
 pub use error::{Error, Result};
@@ -36,6 +41,8 @@ pub mod sqlx;
 pub mod steam;
 pub mod serde;
 pub mod time;
+pub mod checksum;
+pub mod build_info;
 pub mod make_id;
 pub mod bitflags;
 pub mod kz;
@@ -49,6 +56,8 @@ pub mod bans;
 pub mod game_sessions;
 pub mod admins;
 pub mod plugin;
+pub mod health;
+pub mod metrics;
 
 #[allow(clippy::missing_docs_in_private_items)]
 type Server = axum::serve::Serve<
@@ -104,6 +113,10 @@ async fn server(config: Config) -> anyhow::Result<Server> {
 	tracing::info!(%addr, prod = cfg!(feature = "production"), "listening for requests");
 
 	let state = State::new(config).await.context("initialize state")?;
+
+	authentication::session::cleanup::spawn(state.database.clone());
+	maps::workshop_updates::spawn(state.clone());
+
 	let spec = openapi::Spec::new();
 	let mut routes_message = String::from("registering routes:\n");
 
@@ -117,7 +130,6 @@ async fn server(config: Config) -> anyhow::Result<Server> {
 	let api_service = Router::new()
 		.route("/", routing::get(|| async { "(͡ ͡° ͜ つ ͡͡°)" }))
 		.nest("/players", players::router(state.clone()))
-		.nest("/maps", maps::router(state.clone()))
 		.nest("/servers", servers::router(state.clone()))
 		.nest("/jumpstats", jumpstats::router(state.clone()))
 		.nest("/records", records::router(state.clone()))
@@ -126,7 +138,21 @@ async fn server(config: Config) -> anyhow::Result<Server> {
 		.nest("/auth", authentication::router(state.clone()))
 		.nest("/admins", admins::router(state.clone()))
 		.nest("/plugin", plugin::router(state.clone()))
+		.nest("/health", health::router(state.clone()))
+		.layer(middleware::body_limit::layer!(state.config.max_body_bytes))
+		// `/maps` has its own body limit, so it has to be added after the default one
+		.nest("/maps", maps::router(state.clone()))
+		.nest("/metrics", metrics::router(state.clone()))
+		.layer(metrics::layer!(Arc::clone(&state.metrics)))
+		.layer(middleware::rate_limit::layer!(
+			state.config.rate_limit_per_minute,
+			state.clone(),
+		))
 		.layer(middleware::logging::layer!())
+		.layer(SetResponseHeaderLayer::overriding(
+			header::SERVER,
+			BuildInfo::current().server_header(),
+		))
 		.merge(spec.swagger_ui())
 		.into_make_service_with_connect_info::<SocketAddr>();
 
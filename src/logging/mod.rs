@@ -6,17 +6,23 @@ use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 
 mod stderr;
+pub use stderr::{filter as stderr_filter, FilterHandle};
+
 mod files;
 
 /// Initializes [`tracing-subscriber`].
 ///
+/// The returned [`FilterHandle`] can be used to change the filter for logs emitted to stderr.
+///
 /// NOTE: the returned [`WorkerGuard`] will perform cleanup for the tracing layer that emits logs
 ///       to files, which means it has to stay alive until the program exits!
-pub fn init() -> anyhow::Result<WorkerGuard> {
+pub fn init() -> anyhow::Result<(WorkerGuard, FilterHandle)> {
 	let (files_layer, guard, log_dir) = files::layer().context("files layer")?;
+	let (stderr_layer, stderr_filter) = stderr::layer();
 	let registry = tracing_subscriber::registry()
-		.with(stderr::layer())
-		.with(files_layer);
+		.with(stderr_layer)
+		.with(files_layer)
+		.with(cs2kz_api::sqlx::pool::slow_query_layer());
 
 	#[cfg(feature = "console")]
 	let registry = {
@@ -32,5 +38,5 @@ pub fn init() -> anyhow::Result<WorkerGuard> {
 		"initialized logging",
 	};
 
-	Ok(guard)
+	Ok((guard, stderr_filter))
 }
@@ -3,22 +3,40 @@
 use std::io;
 
 use tracing_subscriber::fmt::format::FmtSpan;
-use tracing_subscriber::registry::LookupSpan;
-use tracing_subscriber::{EnvFilter, Layer};
+use tracing_subscriber::{reload, EnvFilter, Layer, Registry};
+
+/// The log filter used in production if `RUST_LOG` is not set.
+const PRODUCTION_FILTER: &str = "cs2kz_api::audit_log=trace,warn";
+
+/// A handle for changing the filter of the stderr layer at runtime.
+pub type FilterHandle = reload::Handle<EnvFilter, Registry>;
 
 /// Creates a tracing layer that will emit logs to stderr.
-pub fn layer<S>() -> impl tracing_subscriber::Layer<S>
-where
-	S: tracing::Subscriber + for<'a> LookupSpan<'a>,
-{
-	tracing_subscriber::fmt::layer()
+///
+/// The returned [`FilterHandle`] can be used to replace the layer's filter later.
+pub fn layer() -> (impl tracing_subscriber::Layer<Registry>, FilterHandle) {
+	let (filter, handle) = reload::Layer::new(filter(None));
+	let layer = tracing_subscriber::fmt::layer()
 		.with_target(true)
 		.with_writer(io::stderr)
 		.with_span_events(FmtSpan::NEW | FmtSpan::CLOSE)
 		.pretty()
-		.with_filter(if cfg!(feature = "production") {
-			EnvFilter::new("cs2kz_api::audit_log=trace,warn")
+		.with_filter(filter);
+
+	(layer, handle)
+}
+
+/// Creates the filter for the stderr layer based on the `RUST_LOG` environment variable.
+///
+/// If `directives` is specified, it is used instead of `RUST_LOG`.
+pub fn filter(directives: Option<&str>) -> EnvFilter {
+	let Some(directives) = directives else {
+		return if cfg!(feature = "production") {
+			EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(PRODUCTION_FILTER))
 		} else {
 			EnvFilter::from_default_env()
-		})
+		};
+	};
+
+	EnvFilter::new(directives)
 }
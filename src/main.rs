@@ -14,22 +14,62 @@
 //! If not, see <https://www.gnu.org/licenses/>.
 
 use std::backtrace::Backtrace;
+#[cfg(unix)]
+use std::collections::HashMap;
 use std::panic;
+use std::path::PathBuf;
+use std::process::ExitCode;
 
 use anyhow::Context;
+use clap::{Parser, Subcommand};
+#[cfg(unix)]
+use tokio::signal::unix::{signal, SignalKind};
 use tracing::Instrument;
 
 mod logging;
 
+/// CS2KZ API.
+#[derive(Parser)]
+struct Args {
+	/// What to do; starts the API if omitted.
+	#[command(subcommand)]
+	command: Option<Command>,
+}
+
+/// Subcommands.
+#[derive(Subcommand)]
+enum Command {
+	/// Start the API.
+	Serve,
+
+	/// Validate the configuration without starting the API.
+	///
+	/// This does not connect to the database or bind any ports. If the configuration is
+	/// invalid, an error is printed and the program exits with code 1.
+	CheckConfig {
+		/// Read the configuration from this file instead of `.env`.
+		///
+		/// Variables that are already set in the environment take precedence.
+		#[arg(long)]
+		env_file: Option<PathBuf>,
+	},
+}
+
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
+async fn main() -> anyhow::Result<ExitCode> {
+	let args = Args::parse();
+
+	if let Some(Command::CheckConfig { env_file }) = args.command {
+		return Ok(check_config(env_file));
+	}
+
 	// `.env` files missing is not necessarily an issue (e.g. when running tests in CI), but we
 	// log it to stderr just in case.
 	if dotenvy::dotenv().is_err() {
 		eprintln!("WARNING: no `.env` file found");
 	}
 
-	let _guard = logging::init().context("initialize logging")?;
+	let (_guard, log_filter) = logging::init().context("initialize logging")?;
 	let runtime_span = tracing::info_span!("runtime::startup");
 	let api_config = runtime_span
 		.in_scope(cs2kz_api::Config::new)
@@ -50,10 +90,130 @@ async fn main() -> anyhow::Result<()> {
 		old_panic_hook(info)
 	}));
 
+	#[cfg(unix)]
+	tokio::spawn(reload_on_sighup(api_config.clone(), log_filter));
+
+	#[cfg(not(unix))]
+	drop(log_filter);
+
 	cs2kz_api::run(api_config)
 		.instrument(runtime_span)
 		.await
 		.context("run API")?;
 
-	Ok(())
+	Ok(ExitCode::SUCCESS)
+}
+
+/// Loads the configuration and reports whether it is valid.
+fn check_config(env_file: Option<PathBuf>) -> ExitCode {
+	let env_file_result = match &env_file {
+		Some(path) => dotenvy::from_path(path),
+		None => dotenvy::dotenv().map(drop),
+	};
+
+	if let Err(error) = env_file_result {
+		if env_file.is_some() {
+			eprintln!("ERROR: failed to read env file: {error}");
+			return ExitCode::FAILURE;
+		}
+
+		eprintln!("WARNING: no `.env` file found");
+	}
+
+	match cs2kz_api::Config::new() {
+		Ok(_) => {
+			eprintln!("config is valid");
+			ExitCode::SUCCESS
+		}
+		Err(error) => {
+			eprintln!("ERROR: invalid config: {error:#}");
+			ExitCode::FAILURE
+		}
+	}
+}
+
+/// Reloads settings whenever we receive a SIGHUP signal.
+///
+/// This re-reads the `.env` file and applies the new log filter. Everything else in the config
+/// is baked into the running API, so changes to it are only logged, and take effect after a
+/// restart. If the address or database URLs changed, the reload is rejected entirely.
+#[cfg(unix)]
+async fn reload_on_sighup(config: cs2kz_api::Config, log_filter: logging::FilterHandle) {
+	let mut sighup = match signal(SignalKind::hangup()) {
+		Ok(sighup) => sighup,
+		Err(error) => {
+			tracing::error!(%error, "failed to register SIGHUP handler");
+			return;
+		}
+	};
+
+	while sighup.recv().await.is_some() {
+		tracing::info_span!("runtime::reload").in_scope(|| reload(&config, &log_filter));
+	}
+}
+
+/// Reloads the config; see [`reload_on_sighup()`].
+#[cfg(unix)]
+fn reload(config: &cs2kz_api::Config, log_filter: &logging::FilterHandle) {
+	tracing::info!(target: "cs2kz_api::audit_log", "received SIGHUP; reloading config");
+
+	// We can't load the file into the environment, as other threads might be reading from it at
+	// the same time.
+	let env_file = match read_env_file() {
+		Ok(env_file) => env_file,
+		Err(error) => {
+			tracing::warn!(%error, "failed to re-read `.env` file");
+			HashMap::new()
+		}
+	};
+
+	let new_config = match cs2kz_api::Config::with_overrides(&env_file) {
+		Ok(new_config) => new_config,
+		Err(error) => {
+			tracing::error! {
+				target: "cs2kz_api::audit_log",
+				"rejecting config reload: {error:#}",
+			};
+
+			return;
+		}
+	};
+
+	let changed = config.changed_fields(&new_config);
+	let structural = changed
+		.iter()
+		.filter(|field| cs2kz_api::Config::STRUCTURAL_FIELDS.contains(field))
+		.collect::<Vec<_>>();
+
+	if !structural.is_empty() {
+		tracing::error! {
+			target: "cs2kz_api::audit_log",
+			?structural,
+			"rejecting config reload; these settings can only be changed by restarting the API",
+		};
+
+		return;
+	}
+
+	let directives = env_file.get("RUST_LOG").map(String::as_str);
+
+	if let Err(error) = log_filter.reload(logging::stderr_filter(directives)) {
+		tracing::error!(%error, "failed to reload log filter");
+	} else {
+		tracing::info!(target: "cs2kz_api::audit_log", "reloaded log filter");
+	}
+
+	if !changed.is_empty() {
+		tracing::warn! {
+			target: "cs2kz_api::audit_log",
+			?changed,
+			"some settings changed, but will only take effect after a restart",
+		};
+	}
+}
+
+/// Reads the variables in the `.env` file without adding them to the environment.
+#[cfg(unix)]
+fn read_env_file() -> dotenvy::Result<HashMap<String, String>> {
+	dotenvy::dotenv_iter()?.collect()
 }
@@ -4,11 +4,12 @@ use std::collections::HashSet;
 
 use axum::extract::Path;
 use axum::Json;
-use cs2kz::{GlobalStatus, MapIdentifier, SteamID};
+use cs2kz::{GlobalStatus, MapIdentifier, RankedStatus, SteamID, Tier};
 use futures::TryFutureExt;
 use sqlx::{MySql, QueryBuilder};
 
 use super::root::create_mappers;
+use crate::authentication::CsrfToken;
 use crate::authorization::{self, Permissions};
 use crate::maps::handlers::root::insert_course_mappers;
 use crate::maps::{
@@ -16,9 +17,9 @@ use crate::maps::{
 };
 use crate::openapi::responses;
 use crate::openapi::responses::NoContent;
-use crate::sqlx::UpdateQuery;
+use crate::sqlx::{FetchID, QueryBuilderExt, UpdateQuery};
 use crate::steam::workshop::{self, WorkshopID};
-use crate::{authentication, Error, Result, State};
+use crate::{authentication, steam, Error, Result, State};
 
 /// Fetch a specific map by its name or ID.
 #[tracing::instrument(skip(state))]
@@ -34,20 +35,10 @@ use crate::{authentication, Error, Result, State};
   ),
 )]
 pub async fn get(state: State, Path(map): Path<MapIdentifier>) -> Result<Json<FullMap>> {
+	let map_id = map.fetch_id(&state.database).await?;
 	let mut query = QueryBuilder::new(queries::SELECT);
 
-	query.push(" WHERE ");
-
-	match map {
-		MapIdentifier::ID(id) => {
-			query.push(" m.id = ").push_bind(id);
-		}
-		MapIdentifier::Name(name) => {
-			query.push(" m.name LIKE ").push_bind(format!("%{name}%"));
-		}
-	}
-
-	query.push(" ORDER BY m.id DESC ");
+	query.push(" WHERE m.id = ").push_bind(map_id);
 
 	let map = query
 		.build_query_as::<FullMap>()
@@ -61,6 +52,8 @@ pub async fn get(state: State, Path(map): Path<MapIdentifier>) -> Result<Json<Fu
 }
 
 /// Update an existing map.
+///
+/// Requires a valid CSRF token in the `X-CSRF-Token` header.
 #[tracing::instrument(skip(state))]
 #[utoipa::path(
   patch,
@@ -72,6 +65,7 @@ pub async fn get(state: State, Path(map): Path<MapIdentifier>) -> Result<Json<Fu
     responses::NoContent,
     responses::BadRequest,
     responses::Unauthorized,
+    responses::Forbidden,
     responses::Conflict,
     responses::UnprocessableEntity,
   ),
@@ -79,6 +73,7 @@ pub async fn get(state: State, Path(map): Path<MapIdentifier>) -> Result<Json<Fu
 pub async fn patch(
 	state: State,
 	session: authentication::Session<authorization::HasPermissions<{ Permissions::MAPS.value() }>>,
+	_csrf: CsrfToken,
 	Path(map_id): Path<MapID>,
 	Json(MapUpdate {
 		description,
@@ -106,7 +101,7 @@ pub async fn patch(
 			map_id,
 			workshop_id,
 			&state.config,
-			&state.http_client,
+			&state.steam_api,
 			&mut transaction,
 		)
 		.await?;
@@ -172,11 +167,15 @@ async fn update_details(
 }
 
 /// Updates a map's name and checksum by downloading it from the workshop.
+///
+/// This also clears the map's [review flag], as the new checksum is now the expected one.
+///
+/// [review flag]: crate::maps::workshop_updates
 async fn update_name_and_checksum(
 	map_id: MapID,
 	workshop_id: Option<WorkshopID>,
 	api_config: &crate::Config,
-	http_client: &reqwest::Client,
+	steam_api: &steam::api::Client,
 	transaction: &mut sqlx::Transaction<'_, MySql>,
 ) -> Result<()> {
 	let workshop_id = if let Some(workshop_id) = workshop_id {
@@ -197,8 +196,8 @@ async fn update_name_and_checksum(
 		.await?
 	};
 
-	let (name, checksum) = tokio::try_join! {
-		workshop::fetch_map_name(workshop_id, http_client),
+	let (metadata, checksum) = tokio::try_join! {
+		workshop::fetch_map_metadata(workshop_id, steam_api),
 		workshop::MapFile::download(workshop_id, api_config).and_then(|map| async move {
 			map.checksum().await.map_err(|err| {
 				Error::checksum(err).context(format!("map_id: {map_id}, workshop_id: {workshop_id}"))
@@ -216,8 +215,8 @@ async fn update_name_and_checksum(
 		WHERE
 		  id = ?
 		"#,
-		name,
-		checksum,
+		metadata.title,
+		checksum.get(),
 		map_id,
 	}
 	.execute(transaction.as_mut())
@@ -228,6 +227,23 @@ async fn update_name_and_checksum(
 		n => assert_eq!(n, 1, "updated more than 1 map"),
 	}
 
+	// The map has been re-checked, so any pending workshop update has been reviewed.
+	sqlx::query! {
+		r#"
+		UPDATE
+		  Maps
+		SET
+		  workshop_updated_on = ?,
+		  needs_review = FALSE
+		WHERE
+		  id = ?
+		"#,
+		metadata.updated_at,
+		map_id,
+	}
+	.execute(transaction.as_mut())
+	.await?;
+
 	tracing::debug!(target: "cs2kz_api::audit_log", %map_id, "updated workshop details");
 
 	Ok(())
@@ -241,15 +257,12 @@ async fn delete_mappers(
 ) -> Result<()> {
 	let mut query = QueryBuilder::new("DELETE FROM Mappers WHERE map_id = ");
 
-	query.push_bind(map_id).push(" AND player_id IN (");
+	query.push_bind(map_id).push(" AND player_id");
 
-	let mut separated = query.separated(", ");
-
-	for &steam_id in mappers {
-		separated.push_bind(steam_id);
+	if !query.push_in_list(mappers.iter().copied()) {
+		return Ok(());
 	}
 
-	query.push(")");
 	query.build().execute(transaction.as_mut()).await?;
 
 	let remaining_mappers = sqlx::query_scalar! {
@@ -302,18 +315,22 @@ where
 	.into_iter()
 	.collect::<HashSet<_>>();
 
-	let courses = courses.into_iter().map(|(id, update)| {
-		if valid_course_ids.remove(&id) {
-			(id, Ok(update))
-		} else {
-			(id, Err(Error::mismatching_map_course(id, map_id)))
-		}
-	});
+	// Make sure every update is valid before we apply any of them.
+	let courses = courses
+		.into_iter()
+		.map(|(id, update)| {
+			if valid_course_ids.remove(&id) {
+				Ok((id, update))
+			} else {
+				Err(Error::mismatching_map_course(id, map_id))
+			}
+		})
+		.collect::<Result<Vec<_>>>()?;
 
 	let mut updated_course_ids = Vec::new();
 
 	for (course_id, update) in courses {
-		if let Some(course_id) = update_course(map_id, course_id, update?, transaction).await? {
+		if let Some(course_id) = update_course(map_id, course_id, update, transaction).await? {
 			updated_course_ids.push(course_id);
 		}
 	}
@@ -392,15 +409,12 @@ async fn delete_course_mappers(
 ) -> Result<()> {
 	let mut query = QueryBuilder::new("DELETE FROM CourseMappers WHERE course_id = ");
 
-	query.push_bind(course_id).push(" AND player_id IN (");
-
-	let mut separated = query.separated(", ");
+	query.push_bind(course_id).push(" AND player_id");
 
-	for &steam_id in mappers {
-		separated.push_bind(steam_id);
+	if !query.push_in_list(mappers.iter().copied()) {
+		return Ok(());
 	}
 
-	query.push(")");
 	query.build().execute(transaction.as_mut()).await?;
 
 	let remaining_mappers = sqlx::query_scalar! {
@@ -459,18 +473,22 @@ where
 	.into_iter()
 	.collect::<HashSet<_>>();
 
-	let filters = filters.into_iter().map(|(id, update)| {
-		if valid_filter_ids.remove(&id) {
-			(id, Ok(update))
-		} else {
-			(id, Err(Error::mismatching_course_filter(id, course_id)))
-		}
-	});
+	// Make sure every update is valid before we apply any of them.
+	let filters = filters
+		.into_iter()
+		.map(|(id, update)| {
+			if valid_filter_ids.remove(&id) {
+				Ok((id, update))
+			} else {
+				Err(Error::mismatching_course_filter(id, course_id))
+			}
+		})
+		.collect::<Result<Vec<_>>>()?;
 
 	let mut updated_filter_ids = Vec::new();
 
 	for (filter_id, update) in filters {
-		if let Some(filter_id) = update_filter(filter_id, update?, transaction).await? {
+		if let Some(filter_id) = update_filter(filter_id, update, transaction).await? {
 			updated_filter_ids.push(filter_id);
 		}
 	}
@@ -521,6 +539,29 @@ async fn update_filter(
 	query.push(" WHERE id = ").push_bind(filter_id);
 	query.build().execute(transaction.as_mut()).await?;
 
+	// The tier and ranked status can be updated independently, so we can only check whether
+	// they still make sense together after applying the update.
+	if tier.is_some() || ranked_status.is_some() {
+		let filter = sqlx::query! {
+			r#"
+			SELECT
+			  tier `tier: Tier`,
+			  ranked_status `ranked_status: RankedStatus`
+			FROM
+			  CourseFilters
+			WHERE
+			  id = ?
+			"#,
+			filter_id,
+		}
+		.fetch_one(transaction.as_mut())
+		.await?;
+
+		if filter.tier > Tier::Death && filter.ranked_status.is_ranked() {
+			return Err(Error::invalid_ranked_filter(filter_id, filter.tier));
+		}
+	}
+
 	tracing::debug! {
 		target: "cs2kz_api::audit_log",
 		%filter_id,
@@ -529,3 +570,67 @@ async fn update_filter(
 
 	Ok(Some(filter_id))
 }
+
+#[cfg(test)]
+mod tests {
+	use axum_extra::extract::cookie::Cookie;
+	use cs2kz::{RankedStatus, SteamID, Tier};
+	use reqwest::header;
+	use serde_json::json;
+
+	use crate::authentication::{csrf, CsrfToken};
+
+	#[crate::integration_test(fixtures = ["grotto-maps"])]
+	async fn reject_impossible_ranked_filters(ctx: &Context) {
+		let alphakeks = SteamID::from_u64(76561198282622073_u64).unwrap();
+		let session = ctx.auth_session(alphakeks).await?;
+		let csrf_cookie = CsrfToken::create(session.id(), &ctx.database)
+			.await?
+			.to_cookie(&ctx.api_config);
+		let cookies = format!(
+			"{}; {}",
+			Cookie::from(session).encoded(),
+			csrf_cookie.encoded(),
+		);
+
+		sqlx::query!("UPDATE CourseFilters SET tier = 10 WHERE id = 1")
+			.execute(&ctx.database)
+			.await?;
+
+		let response = ctx
+			.http_client
+			.patch(ctx.url("/maps/1"))
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
+			.json(&json!({
+			  "course_updates": {
+				"1": {
+				  "filter_updates": {
+					"1": { "ranked_status": "ranked" }
+				  }
+				}
+			  }
+			}))
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 409);
+
+		let filter = sqlx::query! {
+			r#"
+			SELECT
+			  tier `tier: Tier`,
+			  ranked_status `ranked_status: RankedStatus`
+			FROM
+			  CourseFilters
+			WHERE
+			  id = 1
+			"#,
+		}
+		.fetch_one(&ctx.database)
+		.await?;
+
+		assert_eq!(filter.tier, Tier::Impossible);
+		assert!(!filter.ranked_status.is_ranked());
+	}
+}
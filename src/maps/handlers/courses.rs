@@ -0,0 +1,73 @@
+//! HTTP handlers for the `/maps/{map_id}/courses` routes.
+
+use axum::extract::Path;
+use axum::Json;
+use cs2kz::{Mode, RankedStatus, Tier};
+
+use crate::maps::{CourseID, CourseSummary, Filter, FilterID, MapID};
+use crate::openapi::responses;
+use crate::{Error, Result, State};
+
+/// Fetch the courses of a specific map.
+///
+/// This is a lightweight alternative to `/maps/{map}` if you only care about a map's courses
+/// and their filters.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  get,
+  path = "/maps/{map_id}/courses",
+  tag = "Maps",
+  params(("map_id" = u16, Path, description = "The map's ID")),
+  responses(
+    responses::OkList<CourseSummary>,
+    responses::BadRequest,
+    responses::NotFound,
+  ),
+)]
+pub async fn get(state: State, Path(map_id): Path<MapID>) -> Result<Json<Vec<CourseSummary>>> {
+	let courses = sqlx::query! {
+		r#"
+		SELECT
+		  c.id `course_id: CourseID`,
+		  c.name course_name,
+		  f.id `filter_id: FilterID`,
+		  f.mode_id `filter_mode: Mode`,
+		  f.teleports `filter_teleports: bool`,
+		  f.tier `filter_tier: Tier`,
+		  f.ranked_status `filter_ranked_status: RankedStatus`,
+		  f.notes filter_notes
+		FROM
+		  Courses c
+		  JOIN CourseFilters f ON f.course_id = c.id
+		WHERE
+		  c.map_id = ?
+		ORDER BY
+		  c.id ASC,
+		  f.id ASC
+		"#,
+		map_id,
+	}
+	.fetch_all(&state.database)
+	.await?
+	.into_iter()
+	.map(|row| CourseSummary {
+		id: row.course_id,
+		name: Some(row.course_name),
+		filters: vec![Filter {
+			id: row.filter_id,
+			mode: row.filter_mode,
+			teleports: row.filter_teleports,
+			tier: row.filter_tier,
+			ranked_status: row.filter_ranked_status,
+			notes: row.filter_notes,
+		}],
+	});
+
+	let courses = CourseSummary::flatten(courses);
+
+	if courses.is_empty() {
+		return Err(Error::not_found("map"));
+	}
+
+	Ok(Json(courses))
+}
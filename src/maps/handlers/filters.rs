@@ -0,0 +1,158 @@
+//! HTTP handlers for the `/maps/filters/{filter_id}` routes.
+
+use axum::extract::Path;
+use axum::Json;
+use cs2kz::{RankedStatus, Tier};
+
+use crate::authentication::CsrfToken;
+use crate::authorization::{self, Permissions};
+use crate::maps::{FilterID, FilterUpdate, FilterUpdatePreview};
+use crate::openapi::responses;
+use crate::{authentication, Error, Result, State};
+
+/// Preview how updating a filter would affect existing records.
+///
+/// Nothing is changed by this request. Only `tier` and `ranked_status` are taken into account;
+/// `notes` are ignored.
+///
+/// Requires a valid CSRF token in the `X-CSRF-Token` header.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  post,
+  path = "/maps/filters/{filter_id}/preview",
+  tag = "Maps",
+  security(("Browser Session" = ["maps"])),
+  params(("filter_id" = u16, Path, description = "The filter's ID")),
+  request_body = FilterUpdate,
+  responses(
+    responses::Ok<FilterUpdatePreview>,
+    responses::BadRequest,
+    responses::Unauthorized,
+    responses::Forbidden,
+    responses::Conflict,
+  ),
+)]
+pub async fn preview(
+	state: State,
+	_session: authentication::Session<authorization::HasPermissions<{ Permissions::MAPS.value() }>>,
+	_csrf: CsrfToken,
+	Path(filter_id): Path<FilterID>,
+	Json(FilterUpdate {
+		tier,
+		ranked_status,
+		..
+	}): Json<FilterUpdate>,
+) -> Result<Json<FilterUpdatePreview>> {
+	let (old_tier, old_ranked_status) = sqlx::query! {
+		r#"
+		SELECT
+		  tier `tier: Tier`,
+		  ranked_status `ranked_status: RankedStatus`
+		FROM
+		  CourseFilters
+		WHERE
+		  id = ?
+		"#,
+		filter_id,
+	}
+	.fetch_optional(&state.database)
+	.await?
+	.map(|row| (row.tier, row.ranked_status))
+	.ok_or_else(|| Error::not_found("filter"))?;
+
+	let new_tier = tier.unwrap_or(old_tier);
+	let new_ranked_status = ranked_status.unwrap_or(old_ranked_status);
+
+	if new_tier > Tier::Death && new_ranked_status.is_ranked() {
+		return Err(Error::invalid_ranked_filter(filter_id, new_tier));
+	}
+
+	let (affected_records, affected_players) =
+		if (new_tier, new_ranked_status) == (old_tier, old_ranked_status) {
+			(0, 0)
+		} else {
+			sqlx::query! {
+				r#"
+				SELECT
+				  CAST(COUNT(*) AS UNSIGNED) `records!: u64`,
+				  CAST(COUNT(DISTINCT player_id) AS UNSIGNED) `players!: u64`
+				FROM
+				  Records
+				WHERE
+				  filter_id = ?
+				"#,
+				filter_id,
+			}
+			.fetch_one(state.acquire_read().await?.as_mut())
+			.await
+			.map(|row| (row.records, row.players))?
+		};
+
+	Ok(Json(FilterUpdatePreview {
+		old_tier,
+		new_tier,
+		old_ranked_status,
+		new_ranked_status,
+		affected_records,
+		affected_players,
+	}))
+}
+
+#[cfg(test)]
+mod tests {
+	use axum_extra::extract::cookie::Cookie;
+	use cs2kz::{SteamID, Tier};
+	use reqwest::header;
+	use serde_json::json;
+
+	use crate::authentication::{csrf, CsrfToken};
+	use crate::maps::FilterUpdatePreview;
+
+	#[crate::integration_test(fixtures = ["grotto-maps"])]
+	async fn preview_does_not_update(ctx: &Context) {
+		let alphakeks = SteamID::from_u64(76561198282622073_u64).unwrap();
+		let session = ctx.auth_session(alphakeks).await?;
+		let csrf_cookie = CsrfToken::create(session.id(), &ctx.database)
+			.await?
+			.to_cookie(&ctx.api_config);
+		let cookies = format!(
+			"{}; {}",
+			Cookie::from(session).encoded(),
+			csrf_cookie.encoded(),
+		);
+
+		let response = ctx
+			.http_client
+			.post(ctx.url("/maps/filters/1/preview"))
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
+			.json(&json!({ "tier": "hard" }))
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 200);
+
+		let preview = response.json::<FilterUpdatePreview>().await?;
+
+		assert_eq!(preview.old_tier, Tier::VeryEasy);
+		assert_eq!(preview.new_tier, Tier::Hard);
+		assert_eq!(preview.affected_records, 0);
+
+		let tier = sqlx::query_scalar!("SELECT tier `tier: Tier` FROM CourseFilters WHERE id = 1")
+			.fetch_one(&ctx.database)
+			.await?;
+
+		assert_eq!(tier, Tier::VeryEasy);
+
+		let response = ctx
+			.http_client
+			.post(ctx.url("/maps/filters/1/preview"))
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
+			.json(&json!({ "tier": "impossible", "ranked_status": "ranked" }))
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 409);
+	}
+}
@@ -0,0 +1,102 @@
+//! HTTP handlers for the `/maps/{map_id}/image` routes.
+
+use std::collections::BTreeMap;
+
+use axum::extract::Path;
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Utc};
+use utoipa::openapi::response::{Response as ResponseSchema, ResponseBuilder};
+use utoipa::openapi::schema::{KnownFormat, SchemaFormat};
+use utoipa::openapi::{ContentBuilder, ObjectBuilder, RefOr, SchemaType};
+use utoipa::IntoResponses;
+
+use crate::maps::MapID;
+use crate::openapi::responses;
+use crate::steam::workshop::{PreviewImage, WorkshopID};
+use crate::{Error, Result, State};
+
+/// Image served for maps whose workshop item does not have a preview.
+const PLACEHOLDER: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" width="640" height="360" viewBox="0 0 640 360"><rect width="640" height="360" fill="#1e1e2e"/><text x="320" y="190" fill="#6c7086" font-family="sans-serif" font-size="32" text-anchor="middle">no preview</text></svg>"##;
+
+/// How long clients may cache the image, in seconds.
+const MAX_AGE: u32 = 60 * 60 * 24;
+
+/// The `200 OK` response of `/maps/{map_id}/image`.
+#[derive(Debug, Clone, Copy)]
+pub struct ImageResponse;
+
+impl IntoResponses for ImageResponse {
+	fn responses() -> BTreeMap<String, RefOr<ResponseSchema>> {
+		let image = ObjectBuilder::new()
+			.schema_type(SchemaType::String)
+			.format(Some(SchemaFormat::KnownFormat(KnownFormat::Binary)));
+
+		let response = ResponseBuilder::new()
+			.description("The image. Placeholders are SVGs.")
+			.content("image/*", ContentBuilder::new().schema(image).build())
+			.build();
+
+		BTreeMap::from_iter([(String::from("200"), RefOr::T(response))])
+	}
+}
+
+/// Fetch a map's preview image.
+///
+/// This is the workshop item's preview image, or a placeholder if it does not have one.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  get,
+  path = "/maps/{map_id}/image",
+  tag = "Maps",
+  params(("map_id" = u16, Path, description = "The map's ID")),
+  responses(
+    ImageResponse,
+    responses::BadRequest,
+    responses::NotFound,
+    responses::BadGateway,
+  ),
+)]
+pub async fn get(state: State, Path(map_id): Path<MapID>) -> Result<Response> {
+	let (workshop_id, updated_on) = sqlx::query! {
+		r#"
+		SELECT
+		  workshop_id `workshop_id: WorkshopID`,
+		  workshop_updated_on `workshop_updated_on: DateTime<Utc>`
+		FROM
+		  Maps
+		WHERE
+		  id = ?
+		"#,
+		map_id,
+	}
+	.fetch_optional(&state.database)
+	.await?
+	.map(|row| (row.workshop_id, row.workshop_updated_on))
+	.ok_or_else(|| Error::not_found("map"))?;
+
+	let image =
+		PreviewImage::fetch(workshop_id, updated_on, &state.config, &state.steam_api).await?;
+	let cache_control = format!("public, max-age={MAX_AGE}");
+
+	let response = match image {
+		Some(image) => {
+			let headers = [
+				(header::CONTENT_TYPE, image.content_type().to_owned()),
+				(header::CACHE_CONTROL, cache_control),
+			];
+
+			(headers, image.bytes).into_response()
+		}
+		None => {
+			let headers = [
+				(header::CONTENT_TYPE, String::from("image/svg+xml")),
+				(header::CACHE_CONTROL, cache_control),
+			];
+
+			(headers, PLACEHOLDER).into_response()
+		}
+	};
+
+	Ok(response)
+}
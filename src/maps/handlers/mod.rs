@@ -2,3 +2,7 @@
 
 pub mod root;
 pub mod by_identifier;
+pub mod transfer;
+pub mod filters;
+pub mod courses;
+pub mod image;
@@ -11,6 +11,7 @@ use serde::Deserialize;
 use sqlx::{MySql, QueryBuilder};
 use utoipa::IntoParams;
 
+use crate::authentication::CsrfToken;
 use crate::authorization::Permissions;
 use crate::make_id::IntoID;
 use crate::maps::{
@@ -19,16 +20,25 @@ use crate::maps::{
 use crate::openapi::parameters::{Limit, Offset};
 use crate::openapi::responses;
 use crate::openapi::responses::{Created, PaginationResponse};
-use crate::sqlx::{query, FilteredQuery, SqlErrorExt};
+use crate::sqlx::{query, FilteredQuery, SqlErrorExt, WithTotal};
 use crate::steam::workshop::{self, WorkshopID};
+use crate::time::TimeRange;
 use crate::{authentication, authorization, Error, Result, State};
 
 /// Query parameters for `/maps`.
 #[derive(Debug, Deserialize, IntoParams)]
 pub struct GetParams {
 	/// Filter by name.
+	///
+	/// Maps with this exact name are always returned first.
 	name: Option<String>,
 
+	/// Only include maps whose name starts with `name`.
+	///
+	/// This is useful for autocompletion.
+	#[serde(default)]
+	prefix: bool,
+
 	/// Filter by workshop ID.
 	workshop_id: Option<WorkshopID>,
 
@@ -41,6 +51,13 @@ pub struct GetParams {
 	/// Only include maps approved before this date.
 	created_before: Option<DateTime<Utc>>,
 
+	/// Only include maps approved within this range.
+	///
+	/// This is an alternative to `created_after` / `created_before`, e.g.
+	/// `2024-01-01..2024-02-01`.
+	#[param(value_type = Option<String>)]
+	range: Option<TimeRange>,
+
 	/// Maximum number of results to return.
 	#[serde(default)]
 	limit: Limit,
@@ -66,19 +83,28 @@ pub async fn get(
 	state: State,
 	Query(GetParams {
 		name,
+		prefix,
 		workshop_id,
 		global_status,
 		created_after,
 		created_before,
+		range,
 		limit,
 		offset,
 	}): Query<GetParams>,
 ) -> Result<Json<PaginationResponse<FullMap>>> {
-	let mut query = FilteredQuery::new(queries::SELECT);
+	let mut query = FilteredQuery::new(query::select_with_total(queries::SELECT));
 	let mut transaction = state.transaction().await?;
 
-	if let Some(name) = name {
-		query.filter(" m.name LIKE ", format!("%{name}%"));
+	if let Some(ref name) = name {
+		let name = query::escape_like(name);
+		let pattern = if prefix {
+			format!("{name}%")
+		} else {
+			format!("%{name}%")
+		};
+
+		query.filter(" m.name LIKE ", pattern);
 	}
 
 	if let Some(workshop_id) = workshop_id {
@@ -89,42 +115,48 @@ pub async fn get(
 		query.filter(" m.global_status = ", global_status);
 	}
 
-	if let Some(created_after) = created_after {
-		query.filter(" m.created_on > ", created_after);
-	}
-
-	if let Some(created_before) = created_before {
-		query.filter(" m.created_on < ", created_before);
-	}
+	query.filter_range(
+		"m.created_on",
+		TimeRange::between(created_after, created_before).intersect(range.unwrap_or_default()),
+	);
 
 	// not entirely sure if this is correct?
 	if let offset @ 1.. = *offset {
 		query.filter(" m.id > ", offset);
 	}
 
-	query.push(" ORDER BY m.id DESC ");
+	query.push(" ORDER BY ");
 
-	let maps = query
-		.build_query_as::<FullMap>()
+	if let Some(name) = name {
+		query.push(" m.name = ").push_bind(name).push(" DESC, ");
+	}
+
+	query.push(" m.id DESC ");
+
+	let (total, maps) = query
+		.build_query_as::<WithTotal<FullMap>>()
 		.fetch_all(transaction.as_mut())
 		.await
-		.map(|maps| FullMap::flatten(maps, limit.into()))?;
+		.map(WithTotal::split)?;
+
+	let maps = FullMap::flatten(maps, limit.into());
 
 	if maps.is_empty() {
 		return Err(Error::no_content());
 	}
 
-	let total = query::total_rows(&mut transaction).await?;
-
 	transaction.commit().await?;
 
 	Ok(Json(PaginationResponse {
 		total,
 		results: maps,
+		next_cursor: None,
 	}))
 }
 
 /// Create a new map.
+///
+/// Requires a valid CSRF token in the `X-CSRF-Token` header.
 #[tracing::instrument(skip(state))]
 #[utoipa::path(
   put,
@@ -136,12 +168,14 @@ pub async fn get(
     responses::Created<CreatedMap>,
     responses::BadRequest,
     responses::Unauthorized,
+    responses::Forbidden,
     responses::UnprocessableEntity,
   ),
 )]
 pub async fn put(
 	state: State,
 	session: authentication::Session<authorization::HasPermissions<{ Permissions::MAPS.value() }>>,
+	_csrf: CsrfToken,
 	Json(NewMap {
 		workshop_id,
 		description,
@@ -150,8 +184,8 @@ pub async fn put(
 		courses,
 	}): Json<NewMap>,
 ) -> Result<Created<Json<CreatedMap>>> {
-	let (name, checksum) = tokio::try_join! {
-		workshop::fetch_map_name(workshop_id, &state.http_client),
+	let (metadata, checksum) = tokio::try_join! {
+		workshop::fetch_map_metadata(workshop_id, &state.steam_api),
 		workshop::MapFile::download(workshop_id, &state.config).and_then(|map| async move {
 			map.checksum().await.map_err(|err| {
 				Error::checksum(err).context(format!("workshop_id: {workshop_id}"))
@@ -159,18 +193,27 @@ pub async fn put(
 		}),
 	}?;
 
+	let description = description.or(metadata.description);
 	let mut transaction = state.transaction().await?;
 
 	let map_id = create_map(
-		name,
+		metadata.title,
 		description,
 		global_status,
 		workshop_id,
-		checksum,
+		checksum.into(),
 		&mut transaction,
 	)
 	.await?;
 
+	sqlx::query!(
+		"UPDATE Maps SET workshop_updated_on = ? WHERE id = ?",
+		metadata.updated_at,
+		map_id
+	)
+	.execute(transaction.as_mut())
+	.await?;
+
 	create_mappers(map_id, &mappers, &mut transaction).await?;
 	create_courses(map_id, &courses, &mut transaction).await?;
 
@@ -404,3 +447,135 @@ async fn insert_course_filters(
 
 	Ok(filter_ids)
 }
+
+#[cfg(test)]
+mod tests {
+	use reqwest::header;
+
+	use crate::maps::FullMap;
+	use crate::openapi::responses::PaginationResponse;
+
+	#[crate::integration_test(fixtures = ["grotto-maps"])]
+	async fn search_maps_by_name(ctx: &Context) {
+		let exact = ctx
+			.http_client
+			.get(ctx.url("/maps"))
+			.query(&[("name", "kz_grotto")])
+			.send()
+			.await?;
+
+		assert_eq!(exact.status(), 200);
+
+		let exact = exact.json::<PaginationResponse<FullMap>>().await?;
+		let names = exact
+			.results
+			.iter()
+			.map(|map| map.name.as_str())
+			.collect::<Vec<_>>();
+
+		assert_eq!(names.len(), 4);
+		assert_eq!(names.first(), Some(&"kz_grotto"));
+
+		let prefix = ctx
+			.http_client
+			.get(ctx.url("/maps"))
+			.query(&[("name", "kz_grotto"), ("prefix", "true")])
+			.send()
+			.await?;
+
+		assert_eq!(prefix.status(), 200);
+
+		let prefix = prefix.json::<PaginationResponse<FullMap>>().await?;
+		let names = prefix
+			.results
+			.iter()
+			.map(|map| map.name.as_str())
+			.collect::<Vec<_>>();
+
+		assert_eq!(names.len(), 3);
+		assert_eq!(names.first(), Some(&"kz_grotto"));
+		assert!(!names.contains(&"bkz_grotto"));
+
+		// wildcards are matched literally
+		for name in ["%", "_", "kz\\_grotto"] {
+			let response = ctx
+				.http_client
+				.get(ctx.url("/maps"))
+				.query(&[("name", name)])
+				.send()
+				.await?;
+
+			assert_eq!(response.status(), 204, "{name}");
+		}
+	}
+
+	#[crate::integration_test(fixtures = ["grotto-maps"])]
+	async fn not_modified(ctx: &Context) {
+		let response = ctx.http_client.get(ctx.url("/maps")).send().await?;
+
+		assert_eq!(response.status(), 200);
+
+		let etag = response
+			.headers()
+			.get(header::ETAG)
+			.cloned()
+			.expect("response should have an etag");
+
+		let response = ctx
+			.http_client
+			.get(ctx.url("/maps"))
+			.header(header::IF_NONE_MATCH, etag)
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 304);
+	}
+
+	#[crate::integration_test(fixtures = ["grotto-maps"])]
+	async fn filter_by_range(ctx: &Context) {
+		let unbounded = ctx
+			.http_client
+			.get(ctx.url("/maps"))
+			.query(&[("range", "..")])
+			.send()
+			.await?;
+
+		assert_eq!(unbounded.status(), 200);
+
+		let past = ctx
+			.http_client
+			.get(ctx.url("/maps"))
+			.query(&[("range", "2000-01-01..2000-02-01")])
+			.send()
+			.await?;
+
+		assert_eq!(past.status(), 204);
+
+		let invalid = ctx
+			.http_client
+			.get(ctx.url("/maps"))
+			.query(&[("range", "yesterday")])
+			.send()
+			.await?;
+
+		assert_eq!(invalid.status(), 400);
+	}
+
+	#[crate::integration_test(fixtures = ["grotto-maps"])]
+	async fn maps_match_schema(ctx: &Context) {
+		let response = ctx.http_client.get(ctx.url("/maps")).send().await?;
+
+		assert_eq!(response.status(), 200);
+
+		let response = response.json::<serde_json::Value>().await?;
+		let maps = response["results"]
+			.as_array()
+			.context("`results` should be an array")?;
+
+		assert!(!maps.is_empty());
+
+		for map in maps {
+			crate::test::validate_schema::<FullMap>(map)?;
+		}
+	}
+}
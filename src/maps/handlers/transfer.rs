@@ -0,0 +1,249 @@
+//! HTTP handlers for the `/maps/{map_id}/transfer` routes.
+
+use axum::extract::Path;
+use axum::Json;
+
+use crate::authentication::CsrfToken;
+use crate::authorization::{self, Permissions};
+use crate::maps::{MapID, MapTransfer};
+use crate::openapi::responses;
+use crate::openapi::responses::NoContent;
+use crate::sqlx::SqlErrorExt;
+use crate::{authentication, Error, Result, State};
+
+/// Transfer a map from one mapper to another.
+///
+/// This is meant for when a mapper moves to a different account, or a map was submitted under
+/// the wrong one. `from` is replaced by `to` as a mapper of the map and, unless `skip_courses`
+/// is set, of all of its courses.
+///
+/// Requires a valid CSRF token in the `X-CSRF-Token` header.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  post,
+  path = "/maps/{map_id}/transfer",
+  tag = "Maps",
+  security(("Browser Session" = ["maps"])),
+  params(("map_id" = u16, Path, description = "The map's ID")),
+  request_body = MapTransfer,
+  responses(
+    responses::NoContent,
+    responses::BadRequest,
+    responses::Unauthorized,
+    responses::Forbidden,
+    responses::NotFound,
+  ),
+)]
+pub async fn post(
+	state: State,
+	session: authentication::Session<authorization::HasPermissions<{ Permissions::MAPS.value() }>>,
+	_csrf: CsrfToken,
+	Path(map_id): Path<MapID>,
+	Json(MapTransfer {
+		from,
+		to,
+		skip_courses,
+	}): Json<MapTransfer>,
+) -> Result<NoContent> {
+	if from == to {
+		return Err(Error::invalid("transfer").context("`from` and `to` are the same player"));
+	}
+
+	let mut transaction = state.transaction().await?;
+
+	let is_mapper = sqlx::query_scalar! {
+		r#"
+		SELECT
+		  CAST(COUNT(*) AS UNSIGNED) `mappers!: u64`
+		FROM
+		  Mappers
+		WHERE
+		  map_id = ?
+		  AND player_id = ?
+		"#,
+		map_id,
+		from,
+	}
+	.fetch_one(transaction.as_mut())
+	.await?;
+
+	if is_mapper == 0 {
+		return Err(Error::not_found("mapper"));
+	}
+
+	sqlx::query! {
+		r#"
+		INSERT INTO
+		  Mappers (map_id, player_id)
+		VALUES
+		  (?, ?)
+		ON DUPLICATE KEY UPDATE
+		  map_id = map_id
+		"#,
+		map_id,
+		to,
+	}
+	.execute(transaction.as_mut())
+	.await
+	.map_err(|err| {
+		if err.is_fk_violation_of("player_id") {
+			Error::not_found("player").context(err)
+		} else {
+			Error::from(err)
+		}
+	})?;
+
+	sqlx::query!(
+		"DELETE FROM Mappers WHERE map_id = ? AND player_id = ?",
+		map_id,
+		from
+	)
+	.execute(transaction.as_mut())
+	.await?;
+
+	if !skip_courses {
+		sqlx::query! {
+			r#"
+			INSERT INTO
+			  CourseMappers (course_id, player_id)
+			SELECT
+			  cm.course_id,
+			  ?
+			FROM
+			  CourseMappers cm
+			  JOIN Courses c ON c.id = cm.course_id
+			WHERE
+			  c.map_id = ?
+			  AND cm.player_id = ?
+			ON DUPLICATE KEY UPDATE
+			  course_id = CourseMappers.course_id
+			"#,
+			to,
+			map_id,
+			from,
+		}
+		.execute(transaction.as_mut())
+		.await?;
+
+		sqlx::query! {
+			r#"
+			DELETE
+			  cm
+			FROM
+			  CourseMappers cm
+			  JOIN Courses c ON c.id = cm.course_id
+			WHERE
+			  c.map_id = ?
+			  AND cm.player_id = ?
+			"#,
+			map_id,
+			from,
+		}
+		.execute(transaction.as_mut())
+		.await?;
+	}
+
+	transaction.commit().await?;
+
+	tracing::info! {
+		target: "cs2kz_api::audit_log",
+		%map_id,
+		%from,
+		%to,
+		skip_courses,
+		admin_id = %session.user().steam_id(),
+		"transferred map",
+	};
+
+	Ok(NoContent)
+}
+
+#[cfg(test)]
+mod tests {
+	use axum_extra::extract::cookie::Cookie;
+	use cs2kz::SteamID;
+	use reqwest::header;
+	use serde_json::json;
+
+	use crate::authentication::{csrf, CsrfToken};
+
+	#[crate::integration_test(fixtures = ["grotto-maps"])]
+	async fn transfer_map(ctx: &Context) {
+		let alphakeks = SteamID::from_u64(76561198282622073_u64).unwrap();
+		let schnose = SteamID::from_u64(76561198165203332_u64).unwrap();
+
+		sqlx::query!(
+			"INSERT INTO Players (id, name, ip_address) VALUES (?, 'Schnose', '::1')",
+			schnose
+		)
+		.execute(&ctx.database)
+		.await?;
+
+		let transfer = json!({ "from": alphakeks, "to": schnose });
+		let url = ctx.url("/maps/1/transfer");
+		let response = ctx
+			.http_client
+			.post(url.clone())
+			.json(&transfer)
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 401);
+
+		let session = ctx.auth_session(alphakeks).await?;
+		let csrf_cookie = CsrfToken::create(session.id(), &ctx.database)
+			.await?
+			.to_cookie(&ctx.api_config);
+		let session_cookie = Cookie::from(session).encoded().to_string();
+		let cookies = format!("{session_cookie}; {}", csrf_cookie.encoded());
+
+		let response = ctx
+			.http_client
+			.post(url.clone())
+			.header(header::COOKIE, &session_cookie)
+			.json(&transfer)
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 403);
+
+		let response = ctx
+			.http_client
+			.post(url.clone())
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
+			.json(&transfer)
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 204);
+
+		let map_mappers = sqlx::query_scalar! {
+			"SELECT player_id `player_id: SteamID` FROM Mappers WHERE map_id = 1",
+		}
+		.fetch_all(&ctx.database)
+		.await?;
+
+		assert_eq!(map_mappers, [schnose]);
+
+		let course_mappers = sqlx::query_scalar! {
+			"SELECT player_id `player_id: SteamID` FROM CourseMappers WHERE course_id = 1",
+		}
+		.fetch_all(&ctx.database)
+		.await?;
+
+		assert_eq!(course_mappers, [schnose]);
+
+		// alphakeks is no longer a mapper of this map
+		let response = ctx
+			.http_client
+			.post(url)
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
+			.json(&transfer)
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 404);
+	}
+}
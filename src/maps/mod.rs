@@ -5,17 +5,19 @@ use axum::{routing, Router};
 
 use crate::authorization::Permissions;
 use crate::middleware::auth::session_auth;
-use crate::middleware::cors;
+use crate::middleware::{body_limit, cors, etag};
 use crate::{authorization, State};
 
 mod models;
 pub use models::{
-	Course, CourseID, CourseInfo, CourseUpdate, CreatedMap, Filter, FilterID, FilterUpdate,
-	FullMap, MapID, MapInfo, MapUpdate, NewCourse, NewFilter, NewMap,
+	Course, CourseID, CourseInfo, CourseSummary, CourseUpdate, CreatedMap, Filter, FilterID,
+	FilterUpdate, FilterUpdatePreview, FullMap, MapID, MapInfo, MapTransfer, MapUpdate, NewCourse,
+	NewFilter, NewMap,
 };
 
 mod queries;
 pub mod handlers;
+pub mod workshop_updates;
 
 /// Returns an [`axum::Router`] for the `/maps` routes.
 pub fn router(state: State) -> Router {
@@ -26,6 +28,7 @@ pub fn router(state: State) -> Router {
 
 	let root = Router::new()
 		.route("/", routing::get(handlers::root::get))
+		.route_layer(etag::layer!())
 		.route_layer(cors::permissive())
 		.route("/", routing::put(handlers::root::put).route_layer(auth()))
 		.route_layer(cors::dashboard([Method::PUT]))
@@ -33,6 +36,7 @@ pub fn router(state: State) -> Router {
 
 	let by_identifier = Router::new()
 		.route("/:map", routing::get(handlers::by_identifier::get))
+		.route_layer(etag::layer!())
 		.route_layer(cors::permissive())
 		.route(
 			"/:map",
@@ -41,5 +45,31 @@ pub fn router(state: State) -> Router {
 		.route_layer(cors::dashboard([Method::PATCH]))
 		.with_state(state.clone());
 
+	let transfer = Router::new()
+		.route(
+			"/:map_id/transfer",
+			routing::post(handlers::transfer::post).route_layer(auth()),
+		)
+		.route_layer(cors::dashboard([Method::POST]))
+		.with_state(state.clone());
+
+	let filters = Router::new()
+		.route(
+			"/filters/:filter_id/preview",
+			routing::post(handlers::filters::preview).route_layer(auth()),
+		)
+		.route_layer(cors::dashboard([Method::POST]))
+		.with_state(state.clone());
+
+	let courses = Router::new()
+		.route("/:map/courses", routing::get(handlers::courses::get))
+		.route("/:map/image", routing::get(handlers::image::get))
+		.route_layer(cors::permissive())
+		.with_state(state.clone());
+
 	root.merge(by_identifier)
+		.merge(transfer)
+		.merge(filters)
+		.merge(courses)
+		.layer(body_limit::layer!(state.config.max_map_body_bytes))
 }
@@ -20,7 +20,7 @@ make_id!(CourseID as u16);
 make_id!(FilterID as u16);
 
 /// A KZ map.
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct FullMap {
 	/// The map's ID.
 	pub id: MapID,
@@ -41,6 +41,12 @@ pub struct FullMap {
 	/// CRC32 checksum of the map's `.vpk` file.
 	pub checksum: u32,
 
+	/// Whether the map's `.vpk` file changed since it was approved.
+	///
+	/// This is set when the mapper updates the map on the workshop, and cleared when the map
+	/// is re-checked by updating it.
+	pub needs_review: bool,
+
 	/// Players who contributed to the creation of this map.
 	pub mappers: Vec<Player>,
 
@@ -111,6 +117,7 @@ impl FromRow<'_, MySqlRow> for FullMap {
 			global_status: row.try_get("global_status")?,
 			workshop_id: row.try_get("workshop_id")?,
 			checksum: row.try_get("checksum")?,
+			needs_review: row.try_get("needs_review")?,
 			mappers: vec![Player {
 				name: row.try_get("mapper_name")?,
 				steam_id: row.try_get("mapper_id")?,
@@ -122,7 +129,7 @@ impl FromRow<'_, MySqlRow> for FullMap {
 }
 
 /// A KZ map course.
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct Course {
 	/// The course's ID.
 	pub id: CourseID,
@@ -152,20 +159,48 @@ impl FromRow<'_, MySqlRow> for Course {
 				name: row.try_get("course_mapper_name")?,
 				steam_id: row.try_get("course_mapper_id")?,
 			}],
-			filters: vec![Filter {
-				id: row.try_get("filter_id")?,
-				mode: row.try_get("filter_mode")?,
-				teleports: row.try_get("filter_teleports")?,
-				tier: row.try_get("filter_tier")?,
-				ranked_status: row.try_get("filter_ranked_status")?,
-				notes: row.try_get("filter_notes")?,
-			}],
+			filters: vec![Filter::from_row(row)?],
 		})
 	}
 }
 
-/// A course filter.
+/// A KZ map course, without any mapper information.
 #[derive(Debug, Serialize, ToSchema)]
+pub struct CourseSummary {
+	/// The course's ID.
+	pub id: CourseID,
+
+	/// The course's name.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub name: Option<String>,
+
+	/// The course's filters.
+	pub filters: Vec<Filter>,
+}
+
+impl CourseSummary {
+	/// Flatten database results by aggregating courses with equal IDs but different filters
+	/// into a list of courses with unique IDs.
+	pub fn flatten<I>(courses: I) -> Vec<Self>
+	where
+		I: IntoIterator<Item = Self>,
+	{
+		courses
+			.into_iter()
+			.chunk_by(|course| course.id)
+			.into_iter()
+			.filter_map(|(_, courses)| {
+				courses.reduce(|mut course, other| {
+					course.filters.extend(other.filters);
+					course
+				})
+			})
+			.collect()
+	}
+}
+
+/// A course filter.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct Filter {
 	/// The filter's ID.
 	pub id: FilterID,
@@ -187,6 +222,19 @@ pub struct Filter {
 	pub notes: Option<String>,
 }
 
+impl FromRow<'_, MySqlRow> for Filter {
+	fn from_row(row: &MySqlRow) -> sqlx::Result<Self> {
+		Ok(Self {
+			id: row.try_get("filter_id")?,
+			mode: row.try_get("filter_mode")?,
+			teleports: row.try_get("filter_teleports")?,
+			tier: row.try_get("filter_tier")?,
+			ranked_status: row.try_get("filter_ranked_status")?,
+			notes: row.try_get("filter_notes")?,
+		})
+	}
+}
+
 /// Request payload for creating a new map.
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct NewMap {
@@ -194,6 +242,8 @@ pub struct NewMap {
 	pub workshop_id: WorkshopID,
 
 	/// Description of the map.
+	///
+	/// If omitted, the description of the workshop item is used.
 	#[serde(
 		default,
 		deserialize_with = "crate::serde::string::deserialize_empty_as_none"
@@ -453,6 +503,44 @@ pub struct FilterUpdate {
 	pub notes: Option<String>,
 }
 
+/// Request payload for transferring a map from one mapper to another.
+#[derive(Debug, Clone, Copy, Deserialize, ToSchema)]
+pub struct MapTransfer {
+	/// SteamID of the current mapper.
+	pub from: SteamID,
+
+	/// SteamID of the player who should replace them.
+	pub to: SteamID,
+
+	/// Whether to only transfer the map itself and leave its courses' mappers alone.
+	#[serde(default)]
+	pub skip_courses: bool,
+}
+
+/// Response body for previewing a [`FilterUpdate`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct FilterUpdatePreview {
+	/// The filter's current tier.
+	pub old_tier: Tier,
+
+	/// The filter's tier after the update.
+	pub new_tier: Tier,
+
+	/// The filter's current ranked status.
+	pub old_ranked_status: RankedStatus,
+
+	/// The filter's ranked status after the update.
+	pub new_ranked_status: RankedStatus,
+
+	/// How many records would be affected by the update.
+	///
+	/// This is 0 if neither the tier nor the ranked status would change.
+	pub affected_records: u64,
+
+	/// How many players those records belong to.
+	pub affected_players: u64,
+}
+
 /// Information about a KZ map.
 #[derive(Debug, Serialize, FromRow, ToSchema)]
 pub struct MapInfo {
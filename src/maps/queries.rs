@@ -2,13 +2,14 @@
 
 /// SQL query for `SELECT`ing maps from the database.
 pub static SELECT: &str = r#"
-	SELECT SQL_CALC_FOUND_ROWS
+	SELECT
 	  m.id,
 	  m.name,
 	  m.description,
 	  m.global_status,
 	  m.workshop_id,
 	  m.checksum,
+	  m.needs_review,
 	  p1.id mapper_id,
 	  p1.name mapper_name,
 	  c.id course_id,
@@ -0,0 +1,160 @@
+//! Periodic checks for updated Workshop Maps.
+//!
+//! Mappers can update their maps on the workshop at any time, which changes the map's `.vpk`
+//! file. Records set on the old version might not be valid on the new one, so a background task
+//! spawned by [`spawn()`] regularly compares the last update time we know about against Steam's.
+//! If a map was updated, it is downloaded and hashed again, and if its checksum changed, it is
+//! flagged for review.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tokio::task;
+use tokio::time::{self, Instant};
+
+use crate::checksum::Checksum;
+use crate::maps::MapID;
+use crate::steam::workshop::{self, WorkshopID};
+use crate::{Error, Result, State};
+
+/// How often the check runs.
+const INTERVAL: Duration = Duration::from_secs(60 * 60 * 6);
+
+/// Spawns a background task that periodically checks all maps for workshop updates.
+pub fn spawn(state: State) -> task::JoinHandle<()> {
+	task::spawn(async move {
+		// Checking every map means a lot of requests to Steam, so we don't do it on startup.
+		let mut interval = time::interval_at(Instant::now() + INTERVAL, INTERVAL);
+
+		loop {
+			interval.tick().await;
+
+			if let Err(error) = check_all(&state).await {
+				tracing::error!(%error, "failed to check maps for workshop updates");
+			}
+		}
+	})
+}
+
+/// Checks every map for workshop updates.
+///
+/// Returns the number of maps whose checksum changed.
+#[tracing::instrument(level = "debug", name = "maps::workshop_updates", skip_all, err)]
+pub async fn check_all(state: &State) -> Result<u64> {
+	let maps = sqlx::query! {
+		r#"
+		SELECT
+		  id `id: MapID`,
+		  workshop_id `workshop_id: WorkshopID`,
+		  checksum,
+		  workshop_updated_on `workshop_updated_on: DateTime<Utc>`
+		FROM
+		  Maps
+		"#,
+	}
+	.fetch_all(&state.database)
+	.await?;
+
+	let mut changed = 0;
+
+	for map in maps {
+		let (map_id, workshop_id) = (map.id, map.workshop_id);
+
+		match check_map(
+			map_id,
+			workshop_id,
+			map.checksum.into(),
+			map.workshop_updated_on,
+			state,
+		)
+		.await
+		{
+			Ok(true) => changed += 1,
+			Ok(false) => {}
+			Err(error) => tracing::warn! {
+				%error,
+				%map_id,
+				%workshop_id,
+				"failed to check map for workshop updates",
+			},
+		}
+	}
+
+	tracing::debug!(changed, "checked maps for workshop updates");
+
+	Ok(changed)
+}
+
+/// Checks a single map for workshop updates.
+///
+/// Returns whether the map's checksum changed.
+async fn check_map(
+	map_id: MapID,
+	workshop_id: WorkshopID,
+	checksum: Checksum,
+	updated_on: Option<DateTime<Utc>>,
+	state: &State,
+) -> Result<bool> {
+	let Some(latest_update) = workshop::get_update_time(workshop_id, &state.steam_api).await?
+	else {
+		return Ok(false);
+	};
+
+	// If we have never seen an update time for this map, there's nothing to compare against, so
+	// we only remember it for next time.
+	let Some(updated_on) = updated_on else {
+		sqlx::query!(
+			"UPDATE Maps SET workshop_updated_on = ? WHERE id = ?",
+			latest_update,
+			map_id
+		)
+		.execute(&state.database)
+		.await?;
+
+		return Ok(false);
+	};
+
+	if latest_update <= updated_on {
+		return Ok(false);
+	}
+
+	let new_checksum = workshop::MapFile::download(workshop_id, &state.config)
+		.await?
+		.checksum()
+		.await
+		.map_err(|err| {
+			Error::checksum(err).context(format!("map_id: {map_id}, workshop_id: {workshop_id}"))
+		})?;
+
+	let checksum_changed = new_checksum != checksum;
+
+	sqlx::query! {
+		r#"
+		UPDATE
+		  Maps
+		SET
+		  workshop_updated_on = ?,
+		  needs_review = needs_review OR ?
+		WHERE
+		  id = ?
+		"#,
+		latest_update,
+		checksum_changed,
+		map_id,
+	}
+	.execute(&state.database)
+	.await?;
+
+	if checksum_changed {
+		tracing::warn! {
+			target: "cs2kz_api::audit_log",
+			%map_id,
+			%workshop_id,
+			old_checksum = %checksum,
+			%new_checksum,
+			"workshop map was updated and needs review",
+		};
+	}
+
+	Ok(checksum_changed)
+}
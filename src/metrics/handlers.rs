@@ -0,0 +1,46 @@
+//! HTTP handlers for the `/metrics` routes.
+
+use axum::http::header;
+use axum::response::IntoResponse;
+
+use crate::authentication::ApiKey;
+use crate::sqlx::pool;
+use crate::{Error, Result, State};
+
+/// Fetch metrics in the Prometheus text format.
+///
+/// This endpoint is intended to be scraped by Prometheus.
+#[tracing::instrument(skip(state))]
+pub async fn get(state: State, api_key: ApiKey) -> Result<impl IntoResponse> {
+	if api_key.name() != "metrics" {
+		return Err(Error::unauthorized().context(api_key.to_string()));
+	}
+
+	let metrics = state.metrics.render(pool::stats(&state.database));
+
+	Ok((
+		[(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+		metrics,
+	))
+}
+
+#[cfg(test)]
+mod tests {
+	#[crate::integration_test(fixtures = ["metrics-api-key"])]
+	async fn get(ctx: &Context) {
+		let response = ctx
+			.http_client
+			.get(ctx.url("/metrics"))
+			.bearer_auth("a5c6f8b2-3d1e-4f7a-9b0c-2e4d6f8a1b3c")
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 200);
+
+		let metrics = response.text().await?;
+
+		assert!(metrics.contains("# TYPE http_requests_total counter"));
+		assert!(metrics.contains("database_connections_max"));
+		assert!(metrics.contains("# TYPE database_slow_queries_total counter"));
+	}
+}
@@ -0,0 +1,36 @@
+//! Prometheus metrics.
+//!
+//! Every request is recorded by a middleware created with [`layer!()`], and the collected metrics
+//! are served in the [Prometheus text format] at `/metrics`. This endpoint requires an API key,
+//! as it is meant for our own monitoring.
+//!
+//! Slow database queries are counted by a tracing layer instead, see
+//! [`slow_query_layer()`][crate::sqlx::pool::slow_query_layer()].
+//!
+//! [Prometheus text format]: https://prometheus.io/docs/instrumenting/exposition_formats/
+
+use axum::{routing, Router};
+
+use crate::State;
+
+mod registry;
+pub use registry::Metrics;
+pub(crate) use registry::{record, record_slow_query};
+
+pub mod handlers;
+
+/// Creates a middleware that records metrics for every request.
+macro_rules! layer {
+	($metrics:expr) => {
+		::axum::middleware::from_fn_with_state($metrics, $crate::metrics::record)
+	};
+}
+
+pub(crate) use layer;
+
+/// Returns an [`axum::Router`] for the `/metrics` routes.
+pub fn router(state: State) -> Router {
+	Router::new()
+		.route("/", routing::get(handlers::get))
+		.with_state(state.clone())
+}
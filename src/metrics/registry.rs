@@ -0,0 +1,284 @@
+//! Collection and rendering of metrics.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::Method;
+use axum::middleware::Next;
+use axum::response::Response;
+
+use crate::sqlx::pool::PoolStats;
+
+/// Upper bounds of the request latency histogram buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 11] = [
+	0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// The number of slow database queries, keyed by the query's summary.
+///
+/// Slow queries are reported through `tracing` (see [`crate::sqlx::pool::slow_query_layer()`]),
+/// which is set up once per process, before any [`Metrics`] exist. They are therefore counted
+/// process-wide rather than per [`Metrics`] instance.
+static SLOW_QUERIES: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+
+/// Metrics collected by the API.
+#[derive(Debug, Default)]
+pub struct Metrics {
+	/// Metrics for every route, keyed by method and path.
+	routes: Mutex<BTreeMap<(Method, String), RouteMetrics>>,
+}
+
+/// Metrics for a single route.
+#[derive(Debug, Default)]
+struct RouteMetrics {
+	/// The number of requests currently being processed.
+	in_flight: u64,
+
+	/// The number of responses, keyed by status code.
+	responses: BTreeMap<u16, u64>,
+
+	/// The number of requests that took at most as long as the corresponding entry in
+	/// [`LATENCY_BUCKETS`].
+	latency_buckets: [u64; LATENCY_BUCKETS.len()],
+
+	/// The total time spent processing requests, in seconds.
+	latency_sum: f64,
+}
+
+impl Metrics {
+	/// Runs `f` on the metrics for the given route.
+	fn with_route<T>(
+		&self,
+		method: &Method,
+		path: &str,
+		f: impl FnOnce(&mut RouteMetrics) -> T,
+	) -> T {
+		let mut routes = self
+			.routes
+			.lock()
+			.expect("metrics mutex should not be poisoned");
+		let route = routes.entry((method.clone(), path.to_owned())).or_default();
+
+		f(route)
+	}
+
+	/// Renders all metrics in the Prometheus text format.
+	///
+	/// `pool` are the current statistics of the database connection pool.
+	pub fn render(&self, pool: PoolStats) -> String {
+		let routes = self
+			.routes
+			.lock()
+			.expect("metrics mutex should not be poisoned");
+		let mut requests = String::new();
+		let mut latency = String::new();
+		let mut in_flight = String::new();
+
+		for ((method, path), route) in routes.iter() {
+			let labels = format!("method=\"{method}\",path=\"{}\"", escape(path));
+
+			for (status, count) in &route.responses {
+				_ = writeln!(
+					requests,
+					"http_requests_total{{{labels},status=\"{status}\"}} {count}"
+				);
+			}
+
+			let mut cumulative = 0;
+
+			for (bound, count) in LATENCY_BUCKETS.iter().zip(route.latency_buckets) {
+				cumulative += count;
+				_ = writeln!(
+					latency,
+					"http_request_duration_seconds_bucket{{{labels},le=\"{bound}\"}} {cumulative}"
+				);
+			}
+
+			let total = route.responses.values().sum::<u64>();
+
+			_ = writeln!(
+				latency,
+				"http_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {total}"
+			);
+			_ = writeln!(
+				latency,
+				"http_request_duration_seconds_sum{{{labels}}} {}",
+				route.latency_sum
+			);
+			_ = writeln!(
+				latency,
+				"http_request_duration_seconds_count{{{labels}}} {total}"
+			);
+			_ = writeln!(
+				in_flight,
+				"http_requests_in_flight{{{labels}}} {}",
+				route.in_flight
+			);
+		}
+
+		let mut output = String::new();
+
+		_ = writeln!(
+			output,
+			"# HELP http_requests_total Total number of HTTP requests."
+		);
+		_ = writeln!(output, "# TYPE http_requests_total counter");
+		output.push_str(&requests);
+
+		_ = writeln!(
+			output,
+			"# HELP http_request_duration_seconds HTTP request latency."
+		);
+		_ = writeln!(output, "# TYPE http_request_duration_seconds histogram");
+		output.push_str(&latency);
+
+		_ = writeln!(
+			output,
+			"# HELP http_requests_in_flight Number of HTTP requests being processed."
+		);
+		_ = writeln!(output, "# TYPE http_requests_in_flight gauge");
+		output.push_str(&in_flight);
+
+		_ = writeln!(
+			output,
+			"# HELP database_connections Number of database connections."
+		);
+		_ = writeln!(output, "# TYPE database_connections gauge");
+		_ = writeln!(
+			output,
+			"database_connections{{state=\"idle\"}} {}",
+			pool.idle
+		);
+		_ = writeln!(
+			output,
+			"database_connections{{state=\"in_use\"}} {}",
+			pool.in_use
+		);
+
+		_ = writeln!(
+			output,
+			"# HELP database_connections_max Maximum number of database connections."
+		);
+		_ = writeln!(output, "# TYPE database_connections_max gauge");
+		_ = writeln!(output, "database_connections_max {}", pool.max_size);
+
+		_ = writeln!(
+			output,
+			"# HELP database_slow_queries_total Number of slow database queries."
+		);
+		_ = writeln!(output, "# TYPE database_slow_queries_total counter");
+
+		for (query, count) in SLOW_QUERIES
+			.lock()
+			.expect("metrics mutex should not be poisoned")
+			.iter()
+		{
+			_ = writeln!(
+				output,
+				"database_slow_queries_total{{query=\"{}\"}} {count}",
+				escape(query)
+			);
+		}
+
+		output
+	}
+}
+
+/// Counts a query that exceeded the slow query threshold.
+///
+/// `summary` is used as the query's label.
+pub(crate) fn record_slow_query(summary: &str) {
+	let mut slow_queries = SLOW_QUERIES
+		.lock()
+		.expect("metrics mutex should not be poisoned");
+
+	*slow_queries.entry(summary.to_owned()).or_default() += 1;
+}
+
+/// Escapes a label value for the Prometheus text format.
+fn escape(value: &str) -> String {
+	value
+		.replace('\\', r"\\")
+		.replace('"', r#"\""#)
+		.replace('\n', r"\n")
+}
+
+/// Counts a request as in flight for as long as it is alive.
+struct InFlight {
+	/// The metrics to update.
+	metrics: Arc<Metrics>,
+
+	/// The request's method.
+	method: Method,
+
+	/// The route the request matched.
+	path: String,
+}
+
+impl InFlight {
+	/// Marks a request to the given route as in flight.
+	fn new(metrics: Arc<Metrics>, method: Method, path: String) -> Self {
+		metrics.with_route(&method, &path, |route| route.in_flight += 1);
+
+		Self {
+			metrics,
+			method,
+			path,
+		}
+	}
+}
+
+impl Drop for InFlight {
+	fn drop(&mut self) {
+		self.metrics
+			.with_route(&self.method, &self.path, |route| route.in_flight -= 1);
+	}
+}
+
+/// Records metrics for a request.
+///
+/// Requests that don't match any route are not recorded, as their paths are arbitrary.
+#[doc(hidden)]
+pub(crate) async fn record(
+	State(metrics): State<Arc<Metrics>>,
+	request: Request,
+	next: Next,
+) -> Response {
+	let Some(path) = request
+		.extensions()
+		.get::<MatchedPath>()
+		.map(|path| path.as_str().to_owned())
+	else {
+		return next.run(request).await;
+	};
+
+	let method = request.method().clone();
+	let start = Instant::now();
+
+	// if the client disconnects, this future is dropped before `next` returns
+	let in_flight = InFlight::new(Arc::clone(&metrics), method, path);
+	let response = next.run(request).await;
+	let latency = start.elapsed().as_secs_f64();
+
+	metrics.with_route(&in_flight.method, &in_flight.path, |route| {
+		*route
+			.responses
+			.entry(response.status().as_u16())
+			.or_default() += 1;
+		route.latency_sum += latency;
+
+		let bucket = LATENCY_BUCKETS
+			.iter()
+			.zip(&mut route.latency_buckets)
+			.find(|(&bound, _)| latency <= bound);
+
+		if let Some((_, count)) = bucket {
+			*count += 1;
+		}
+	});
+
+	response
+}
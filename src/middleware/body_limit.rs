@@ -0,0 +1,52 @@
+//! Request body size limits.
+//!
+//! Request bodies are rejected with `413 Payload Too Large` if they exceed a configured size.
+//! If the client sends a `Content-Length` header, we can reject the request before reading any of
+//! the body; otherwise we stop reading as soon as the limit is exceeded.
+//!
+//! Different route groups can have different limits by applying separate layers.
+
+use axum::body::{self, Body};
+use axum::extract::{Request, State};
+use axum::http::header;
+use axum::middleware::Next;
+use axum::response::Response;
+
+use crate::{Error, Result};
+
+/// Creates a middleware that rejects request bodies larger than `max_bytes`.
+macro_rules! layer {
+	($max_bytes:expr) => {
+		::axum::middleware::from_fn_with_state(
+			$max_bytes,
+			$crate::middleware::body_limit::body_limit,
+		)
+	};
+}
+
+pub(crate) use layer;
+
+/// Rejects the request if its body is larger than `max_bytes`.
+#[tracing::instrument(level = "debug", name = "middleware::body_limit", skip(request, next))]
+pub async fn body_limit(
+	State(max_bytes): State<usize>,
+	request: Request,
+	next: Next,
+) -> Result<Response> {
+	let content_length = request
+		.headers()
+		.get(header::CONTENT_LENGTH)
+		.and_then(|value| value.to_str().ok())
+		.and_then(|value| value.parse::<usize>().ok());
+
+	if content_length.is_some_and(|content_length| content_length > max_bytes) {
+		return Err(Error::payload_too_large(max_bytes));
+	}
+
+	let (parts, body) = request.into_parts();
+	let body = body::to_bytes(body, max_bytes)
+		.await
+		.map_err(|err| Error::payload_too_large(max_bytes).context(err))?;
+
+	Ok(next.run(Request::from_parts(parts, Body::from(body))).await)
+}
@@ -2,10 +2,12 @@
 //!
 //! [CORS]: https://developer.mozilla.org/en-US/docs/Glossary/CORS
 
-use axum::http::{header, request, HeaderValue, Method};
+use axum::http::{header, request, HeaderName, HeaderValue, Method};
 use tower_http::cors::{AllowMethods, AllowOrigin, CorsLayer};
 use url::Url;
 
+use crate::authentication;
+
 /// Creates a permissive CORS layer, allowing any origins or headers, but only GET requests.
 pub fn permissive() -> CorsLayer {
 	CorsLayer::permissive().allow_methods([Method::GET])
@@ -21,7 +23,10 @@ where
 	CorsLayer::new()
 		.allow_methods(methods)
 		.allow_credentials(true)
-		.allow_headers([header::CONTENT_TYPE])
+		.allow_headers([
+			header::CONTENT_TYPE,
+			HeaderName::from_static(authentication::csrf::HEADER_NAME),
+		])
 		.allow_origin(if cfg!(feature = "production") {
 			AllowOrigin::exact(HeaderValue::from_static("https://dashboard.cs2kz.org"))
 		} else {
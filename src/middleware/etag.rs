@@ -0,0 +1,80 @@
+//! [ETag] middleware.
+//!
+//! Some endpoints are polled a lot, but their responses rarely change. For those, this middleware
+//! computes a weak ETag over the response body and responds with `304 Not Modified` if the client
+//! already has the same version cached, as indicated by the `If-None-Match` header.
+//!
+//! [ETag]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/ETag
+
+use axum::body::{self, Body};
+use axum::extract::Request;
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+/// Creates an ETag middleware.
+// NOTE: this is a macro because this type cannot be spelled out in code
+macro_rules! layer {
+	() => {
+		axum::middleware::from_fn($crate::middleware::etag::etag)
+	};
+}
+
+pub(crate) use layer;
+
+#[doc(hidden)]
+pub(crate) async fn etag(request: Request, next: Next) -> Response {
+	if request.method() != Method::GET {
+		return next.run(request).await;
+	}
+
+	let if_none_match = request.headers().get(header::IF_NONE_MATCH).cloned();
+	let response = next.run(request).await;
+
+	if response.status() != StatusCode::OK {
+		return response;
+	}
+
+	let (mut parts, body) = response.into_parts();
+
+	let body = match body::to_bytes(body, usize::MAX).await {
+		Ok(body) => body,
+		Err(error) => {
+			tracing::error!(%error, "failed to buffer response body");
+			return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+		}
+	};
+
+	let etag = format!("W/\"{:08x}\"", crc32fast::hash(&body));
+	let etag = HeaderValue::from_str(&etag).expect("this is a valid header value");
+
+	if if_none_match.is_some_and(|value| matches(&value, &etag)) {
+		let mut headers = HeaderMap::new();
+		headers.insert(header::ETAG, etag);
+
+		return (StatusCode::NOT_MODIFIED, headers).into_response();
+	}
+
+	parts.headers.insert(header::ETAG, etag);
+
+	Response::from_parts(parts, Body::from(body))
+}
+
+/// Checks if an `If-None-Match` header matches the given ETag.
+///
+/// The header may contain a list of ETags, or `*`. As per the spec, ETags are compared weakly.
+fn matches(if_none_match: &HeaderValue, etag: &HeaderValue) -> bool {
+	let Ok(if_none_match) = if_none_match.to_str() else {
+		return false;
+	};
+
+	let etag = strip_weak(etag.to_str().expect("we created this value"));
+
+	if_none_match.trim() == "*" || if_none_match.split(',').any(|tag| strip_weak(tag) == etag)
+}
+
+/// Strips the weak validator prefix (`W/`) from an ETag.
+fn strip_weak(etag: &str) -> &str {
+	let etag = etag.trim();
+	etag.strip_prefix("W/").unwrap_or(etag)
+}
@@ -3,3 +3,6 @@
 pub mod logging;
 pub mod cors;
 pub mod auth;
+pub mod body_limit;
+pub mod etag;
+pub mod rate_limit;
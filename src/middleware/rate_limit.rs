@@ -0,0 +1,306 @@
+//! Rate limiting middleware.
+//!
+//! Every client gets a token bucket, keyed by their IP address. Every request takes a token out
+//! of the bucket, and tokens are refilled at a constant rate. If the bucket is empty, the request
+//! is rejected with `429 Too Many Requests` and a `Retry-After` header.
+//!
+//! CS2 servers are keyed by their ID instead, if they send a valid JWT. Many servers can share a
+//! single address (e.g. if they are run by the same hosting provider), and they shouldn't use up
+//! each other's requests. Sessions and API tokens would require a database query to verify, so
+//! those requests are still keyed by address.
+//!
+//! IPv6 clients are keyed by their `/64` prefix, since a single host usually controls at least
+//! that many addresses. If the API runs behind a reverse proxy, its address has to be listed in
+//! [`Config::trusted_proxies`], so the client's address is taken from the `X-Forwarded-For`
+//! header instead of the proxy's.
+//!
+//! Different route groups can have different limits by using separate [`RateLimiter`]s.
+//!
+//! [`Config::trusted_proxies`]: crate::Config::trusted_proxies
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::num::NonZeroU32;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{header, HeaderMap};
+use axum::middleware::Next;
+use axum::response::Response;
+use tokio::time::MissedTickBehavior;
+
+use crate::servers::ServerID;
+use crate::{authentication, Error, Result};
+
+/// The maximum number of buckets a [`RateLimiter`] keeps track of.
+///
+/// Clients that show up while the limit is reached share a single bucket until some space is
+/// freed up again.
+const MAX_BUCKETS: usize = 100_000;
+
+/// How often full buckets are removed.
+///
+/// A bucket that is full is equivalent to one that doesn't exist, so they can be removed to
+/// keep memory usage in check.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The header reverse proxies put the client's address in.
+const FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// Creates a rate limiting middleware that allows `per_minute` requests per client per minute.
+///
+/// `state` is used to verify JWTs, and to look up the [trusted proxies].
+///
+/// [trusted proxies]: crate::Config::trusted_proxies
+macro_rules! layer {
+	($per_minute:expr, $state:expr $(,)?) => {
+		::axum::middleware::from_fn_with_state(
+			$crate::middleware::rate_limit::RateLimiter::new($per_minute, $state),
+			$crate::middleware::rate_limit::rate_limit,
+		)
+	};
+}
+
+pub(crate) use layer;
+
+/// Rate limiting state for a route group.
+#[derive(Debug)]
+pub struct RateLimiter {
+	/// The maximum number of requests per client per minute.
+	///
+	/// This is also the bucket size, which means clients can burst this many requests at once.
+	per_minute: NonZeroU32,
+
+	/// The application state, used to verify JWTs and to look up the [trusted proxies].
+	///
+	/// [trusted proxies]: crate::Config::trusted_proxies
+	state: crate::State,
+
+	/// The token buckets.
+	buckets: Mutex<Buckets>,
+}
+
+/// The token buckets of a [`RateLimiter`].
+#[derive(Debug)]
+struct Buckets {
+	/// A token bucket for every client.
+	clients: HashMap<ClientKey, Bucket>,
+
+	/// The bucket shared by all clients that don't fit into `clients` anymore.
+	overflow: Bucket,
+}
+
+/// What a client's bucket is keyed by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum ClientKey {
+	/// A CS2 server that sent a valid JWT.
+	Server(ServerID),
+
+	/// Everyone else, keyed by their address.
+	///
+	/// See [`bucket_key()`].
+	Addr(IpAddr),
+}
+
+/// A client's token bucket.
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+	/// How many tokens are currently in the bucket.
+	tokens: f64,
+
+	/// When the bucket was last refilled.
+	last_refill: Instant,
+}
+
+impl RateLimiter {
+	/// Creates a new [`RateLimiter`].
+	///
+	/// This spawns a background task that periodically removes full buckets, which runs until
+	/// the limiter is dropped.
+	///
+	/// # Panics
+	///
+	/// This function will panic if it is called outside of a tokio runtime.
+	pub fn new(per_minute: NonZeroU32, state: crate::State) -> Arc<Self> {
+		let limiter = Arc::new(Self {
+			per_minute,
+			state,
+			buckets: Mutex::new(Buckets {
+				clients: HashMap::new(),
+				overflow: Bucket {
+					tokens: f64::from(per_minute.get()),
+					last_refill: Instant::now(),
+				},
+			}),
+		});
+
+		tokio::spawn(prune_periodically(Arc::downgrade(&limiter)));
+
+		limiter
+	}
+
+	/// The bucket size.
+	fn capacity(&self) -> f64 {
+		f64::from(self.per_minute.get())
+	}
+
+	/// How many tokens are added to each bucket per second.
+	fn per_second(&self) -> f64 {
+		self.capacity() / 60.0
+	}
+
+	/// Determines which address a request was sent from.
+	///
+	/// If the request came from a trusted proxy, this is the last address in `X-Forwarded-For`
+	/// that doesn't belong to a trusted proxy.
+	fn client_addr(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+		if !self.state.config.trusted_proxies.contains(&peer) {
+			return peer;
+		}
+
+		headers
+			.get_all(FORWARDED_FOR)
+			.iter()
+			.filter_map(|value| value.to_str().ok())
+			.flat_map(|value| value.split(','))
+			.rev()
+			.map_while(|addr| addr.trim().parse::<IpAddr>().ok())
+			.find(|addr| !self.state.config.trusted_proxies.contains(addr))
+			.unwrap_or(peer)
+	}
+
+	/// Determines which bucket a request belongs to.
+	///
+	/// Requests with a valid server JWT use the server's bucket, everything else uses the
+	/// bucket of the address it was sent from.
+	fn client_key(&self, peer: IpAddr, headers: &HeaderMap) -> ClientKey {
+		let server = headers
+			.get(header::AUTHORIZATION)
+			.and_then(|value| value.to_str().ok())
+			.and_then(|value| value.strip_prefix("Bearer "))
+			.and_then(|token| self.state.decode_jwt::<authentication::Server>(token).ok())
+			.filter(|jwt| !jwt.has_expired());
+
+		match server {
+			Some(server) => ClientKey::Server(server.id()),
+			None => ClientKey::Addr(bucket_key(self.client_addr(peer, headers))),
+		}
+	}
+
+	/// Takes a token out of `client`'s bucket.
+	///
+	/// If the bucket is empty, returns how long the client has to wait for the next token.
+	fn acquire(&self, client: ClientKey) -> Result<(), Duration> {
+		let capacity = self.capacity();
+		let per_second = self.per_second();
+		let now = Instant::now();
+		let mut buckets = self
+			.buckets
+			.lock()
+			.expect("rate limiter mutex should not be poisoned");
+
+		let Buckets { clients, overflow } = &mut *buckets;
+		let has_space = clients.len() < MAX_BUCKETS;
+		let bucket = match clients.entry(client) {
+			Entry::Occupied(entry) => entry.into_mut(),
+			Entry::Vacant(entry) if has_space => entry.insert(Bucket {
+				tokens: capacity,
+				last_refill: now,
+			}),
+			Entry::Vacant(_) => {
+				tracing::debug!(?client, "rate limiter is full");
+				overflow
+			}
+		};
+
+		bucket.refill(now, capacity, per_second);
+
+		if bucket.tokens < 1.0 {
+			return Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_second));
+		}
+
+		bucket.tokens -= 1.0;
+
+		Ok(())
+	}
+
+	/// Removes all buckets that are full.
+	fn prune(&self) {
+		let capacity = self.capacity();
+		let per_second = self.per_second();
+		let now = Instant::now();
+		let mut buckets = self
+			.buckets
+			.lock()
+			.expect("rate limiter mutex should not be poisoned");
+
+		buckets.clients.retain(|_, bucket| {
+			bucket.refill(now, capacity, per_second);
+			bucket.tokens < capacity
+		});
+	}
+}
+
+impl Bucket {
+	/// Adds the tokens that accumulated since the last refill.
+	fn refill(&mut self, now: Instant, capacity: f64, per_second: f64) {
+		let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+
+		self.tokens = f64::min(self.tokens + elapsed * per_second, capacity);
+		self.last_refill = now;
+	}
+}
+
+/// Returns the key of the bucket `addr` belongs to.
+///
+/// IPv6 addresses are truncated to their `/64` prefix.
+fn bucket_key(addr: IpAddr) -> IpAddr {
+	match addr {
+		IpAddr::V4(_) => addr,
+		IpAddr::V6(addr) => match addr.to_ipv4_mapped() {
+			Some(addr) => IpAddr::V4(addr),
+			None => IpAddr::V6(Ipv6Addr::from(u128::from(addr) & (u128::MAX << 64))),
+		},
+	}
+}
+
+/// Removes full buckets from `limiter` every [`PRUNE_INTERVAL`], until it is dropped.
+async fn prune_periodically(limiter: Weak<RateLimiter>) {
+	let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+
+	interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+	loop {
+		interval.tick().await;
+
+		let Some(limiter) = limiter.upgrade() else {
+			break;
+		};
+
+		limiter.prune();
+	}
+}
+
+/// Rejects the request if the client has exceeded their rate limit.
+#[tracing::instrument(
+	level = "debug",
+	name = "middleware::rate_limit",
+	skip(limiter, request, next)
+)]
+pub async fn rate_limit(
+	State(limiter): State<Arc<RateLimiter>>,
+	ConnectInfo(addr): ConnectInfo<SocketAddr>,
+	request: Request,
+	next: Next,
+) -> Result<Response> {
+	let client = limiter.client_key(addr.ip(), request.headers());
+
+	if let Err(retry_after) = limiter.acquire(client) {
+		tracing::debug!(?retry_after, "client exceeded rate limit");
+		return Err(Error::rate_limited(retry_after));
+	}
+
+	Ok(next.run(request).await)
+}
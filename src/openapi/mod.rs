@@ -35,11 +35,17 @@ pub mod security;
     crate::players::handlers::by_identifier::patch,
     crate::players::handlers::steam::get,
     crate::players::handlers::preferences::get,
+    crate::players::handlers::completion::get,
+    crate::players::handlers::discord::delete,
 
     crate::maps::handlers::root::get,
     crate::maps::handlers::root::put,
     crate::maps::handlers::by_identifier::get,
     crate::maps::handlers::by_identifier::patch,
+    crate::maps::handlers::transfer::post,
+    crate::maps::handlers::filters::preview,
+    crate::maps::handlers::courses::get,
+    crate::maps::handlers::image::get,
 
     crate::servers::handlers::root::get,
     crate::servers::handlers::root::post,
@@ -58,26 +64,48 @@ pub mod security;
     crate::records::handlers::root::post,
     crate::records::handlers::top::get,
     crate::records::handlers::by_id::get,
+    crate::records::handlers::status::put,
     crate::records::handlers::replays::get,
+    crate::records::handlers::wr_history::get,
+    crate::records::handlers::export::get,
 
     crate::bans::handlers::root::get,
     crate::bans::handlers::root::post,
     crate::bans::handlers::by_id::get,
     crate::bans::handlers::by_id::patch,
     crate::bans::handlers::by_id::delete,
+    crate::bans::handlers::related::get,
+    crate::bans::handlers::appeals::get,
+    crate::bans::handlers::appeals::post,
+    crate::bans::handlers::appeals::put,
+    crate::bans::handlers::ip_ranges::get,
+    crate::bans::handlers::ip_ranges::post,
 
     crate::game_sessions::handlers::by_id::get,
 
     crate::authentication::handlers::login,
     crate::authentication::handlers::logout,
     crate::authentication::handlers::callback,
+    crate::authentication::handlers::me,
+    crate::authentication::handlers::tokens::get,
+    crate::authentication::handlers::tokens::post,
+    crate::authentication::handlers::tokens::delete,
+    crate::authentication::handlers::discord::login,
+    crate::authentication::handlers::discord::callback,
 
     crate::admins::handlers::root::get,
     crate::admins::handlers::by_id::get,
     crate::admins::handlers::by_id::put,
+    crate::admins::handlers::sessions::delete,
+    crate::admins::handlers::permissions::get,
+    crate::admins::handlers::permissions::post,
+    crate::admins::handlers::permissions::delete,
 
     crate::plugin::handlers::versions::get,
     crate::plugin::handlers::versions::post,
+    crate::plugin::handlers::versions::yank,
+
+    crate::health::handlers::get,
   ),
   components(
     schemas(
@@ -102,17 +130,26 @@ pub mod security;
 
       crate::steam::workshop::WorkshopID,
 
+      crate::authentication::handlers::CurrentUser,
+      crate::authentication::api_token::ApiToken,
+      crate::authentication::api_token::ApiTokenID,
+      crate::authentication::api_token::NewApiToken,
+      crate::authentication::api_token::CreatedApiToken,
       crate::players::Player,
       crate::players::NewPlayer,
       crate::players::PlayerUpdate,
       crate::players::Session,
       crate::players::CourseSession,
       crate::players::CourseSessions,
+      crate::players::Completion,
+      crate::players::TierCompletion,
+      crate::players::handlers::by_identifier::Include,
 
       crate::maps::FullMap,
       crate::maps::MapID,
       crate::maps::Course,
       crate::maps::CourseID,
+      crate::maps::CourseSummary,
       crate::maps::Filter,
       crate::maps::FilterID,
       crate::maps::NewMap,
@@ -120,6 +157,8 @@ pub mod security;
       crate::maps::NewFilter,
       crate::maps::CreatedMap,
       crate::maps::MapUpdate,
+      crate::maps::MapTransfer,
+      crate::maps::FilterUpdatePreview,
       crate::maps::CourseUpdate,
       crate::maps::FilterUpdate,
       crate::maps::MapInfo,
@@ -144,11 +183,16 @@ pub mod security;
       crate::records::BhopStats,
       crate::records::NewRecord,
       crate::records::CreatedRecord,
+      crate::records::RecordStatus,
+      crate::records::RecordStatusUpdate,
+      crate::records::WorldRecord,
       crate::records::handlers::root::SortRecordsBy,
+      crate::records::handlers::export::ExportFormat,
 
       crate::bans::Ban,
       crate::bans::BanID,
       crate::bans::BanReason,
+      crate::bans::BanSummary,
       crate::bans::Unban,
       crate::bans::UnbanID,
       crate::bans::NewBan,
@@ -156,6 +200,17 @@ pub mod security;
       crate::bans::BanUpdate,
       crate::bans::NewUnban,
       crate::bans::CreatedUnban,
+      crate::bans::RelatedAccount,
+      crate::bans::Appeal,
+      crate::bans::AppealID,
+      crate::bans::AppealStatus,
+      crate::bans::NewAppeal,
+      crate::bans::CreatedAppeal,
+      crate::bans::AppealResolution,
+      crate::bans::IpRangeBan,
+      crate::bans::IpRangeBanID,
+      crate::bans::NewIpRangeBan,
+      crate::bans::CreatedIpRangeBan,
 
       crate::game_sessions::GameSession,
       crate::game_sessions::GameSessionID,
@@ -163,11 +218,19 @@ pub mod security;
 
       crate::admins::Admin,
       crate::admins::AdminUpdate,
+      crate::admins::PermissionsDelta,
+      crate::admins::PermissionChange,
 
       crate::plugin::PluginVersion,
       crate::plugin::PluginVersionID,
       crate::plugin::NewPluginVersion,
       crate::plugin::CreatedPluginVersion,
+      crate::plugin::YankedPluginVersion,
+
+      crate::health::Health,
+      crate::health::DatabaseHealth,
+      crate::build_info::BuildInfo,
+      crate::sqlx::PoolStats,
     ),
   ),
 )]
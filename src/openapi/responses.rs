@@ -7,9 +7,9 @@ use std::collections::BTreeMap;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use serde::{Deserialize, Serialize};
-use utoipa::openapi::response::Response as ResponseSchema;
-use utoipa::openapi::schema::Schema;
-use utoipa::openapi::{ObjectBuilder, RefOr, SchemaType};
+use utoipa::openapi::response::{Response as ResponseSchema, ResponseBuilder};
+use utoipa::openapi::schema::{Array, Schema};
+use utoipa::openapi::{ContentBuilder, ObjectBuilder, RefOr, SchemaType};
 use utoipa::{IntoResponses, ToSchema};
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -21,6 +21,9 @@ where
 
 	#[schema(inline)]
 	pub results: Vec<T>,
+
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub next_cursor: Option<String>,
 }
 
 #[derive(Debug, Serialize, IntoResponses)]
@@ -29,6 +32,26 @@ pub struct Ok<T>(#[to_schema] pub T)
 where
 	T: ToSchema<'static>;
 
+#[derive(Debug, Serialize)]
+pub struct OkList<T>(pub Vec<T>)
+where
+	T: ToSchema<'static>;
+
+impl<T> IntoResponses for OkList<T>
+where
+	T: ToSchema<'static>,
+{
+	fn responses() -> BTreeMap<String, RefOr<ResponseSchema>> {
+		let (_, schema) = T::schema();
+		let content = ContentBuilder::new().schema(Array::new(schema)).build();
+		let response = ResponseBuilder::new()
+			.content("application/json", content)
+			.build();
+
+		BTreeMap::from_iter([(String::from("200"), RefOr::T(response))])
+	}
+}
+
 #[derive(Debug, Serialize)]
 pub struct Created<T = ()>(pub T);
 
@@ -79,6 +102,14 @@ pub struct BadRequest;
 #[response(status = 401)]
 pub struct Unauthorized;
 
+#[derive(Debug, Clone, Copy, Serialize, IntoResponses)]
+#[response(status = 403)]
+pub struct Forbidden;
+
+#[derive(Debug, Clone, Copy, Serialize, IntoResponses)]
+#[response(status = 404)]
+pub struct NotFound;
+
 #[derive(Debug, Clone, Copy, Serialize, IntoResponses)]
 #[response(status = 409)]
 pub struct Conflict;
@@ -87,10 +118,18 @@ pub struct Conflict;
 #[response(status = 422)]
 pub struct UnprocessableEntity;
 
+#[derive(Debug, Clone, Copy, Serialize, IntoResponses)]
+#[response(status = 429)]
+pub struct TooManyRequests;
+
 #[derive(Debug, Clone, Copy, Serialize, IntoResponses)]
 #[response(status = 502)]
 pub struct BadGateway;
 
+#[derive(Debug, Clone, Copy, Serialize, IntoResponses)]
+#[response(status = 503)]
+pub struct ServiceUnavailable;
+
 #[derive(Debug, Clone, Copy)]
 pub struct Object;
 
@@ -1,6 +1,6 @@
 #![allow(missing_docs, clippy::missing_docs_in_private_items)]
 
-use utoipa::openapi::security::{ApiKey, ApiKeyValue, Http, HttpAuthScheme, SecurityScheme};
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
 use utoipa::openapi::OpenApi;
 use utoipa::Modify;
 
@@ -16,10 +16,30 @@ impl Modify for Security {
 			.as_mut()
 			.expect("OpenAPI spec has components");
 
-		let cs_server_jwt = SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer));
-		let api_key = SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer));
-		let sessions = SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new(
+		let cs_server_jwt = SecurityScheme::Http(
+			HttpBuilder::new()
+				.scheme(HttpAuthScheme::Bearer)
+				.bearer_format("JWT")
+				.description(Some(
+					"An access key generated by a CS2 server using `POST /servers/key`.",
+				))
+				.build(),
+		);
+
+		let api_key = SecurityScheme::Http(
+			HttpBuilder::new()
+				.scheme(HttpAuthScheme::Bearer)
+				.description(Some(
+					"An opaque API key for special services, like GitHub actions.",
+				))
+				.build(),
+		);
+
+		let sessions = SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::with_description(
 			authentication::session::COOKIE_NAME,
+			"A session cookie obtained by logging in via `GET /auth/login`. Alternatively, an API \
+			 token created via `POST /auth/tokens` can be sent as a `Bearer` token in the \
+			 `Authorization` header.",
 		)));
 
 		components.add_security_schemes_from_iter([
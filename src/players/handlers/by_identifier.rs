@@ -2,25 +2,47 @@
 
 use std::iter;
 
-use axum::extract::Path;
+use axum::extract::{Path, Query};
 use axum::Json;
 use cs2kz::{Mode, PlayerIdentifier, SteamID};
 use futures::TryFutureExt;
+use serde::Deserialize;
 use sqlx::types::Json as SqlJson;
-use sqlx::{MySql, QueryBuilder};
+use sqlx::{MySql, Pool, QueryBuilder};
+use utoipa::{IntoParams, ToSchema};
 
+use super::root::insert_name_history;
 use crate::authentication::Jwt;
 use crate::authorization::Permissions;
+use crate::bans::{self, Ban, BanSummary};
 use crate::game_sessions::{CourseSessionID, GameSessionID};
 use crate::maps::CourseID;
 use crate::openapi::responses::{self, NoContent};
 use crate::players::{queries, CourseSession, FullPlayer, PlayerUpdate};
 use crate::servers::ServerID;
-use crate::sqlx::SqlErrorExt;
+use crate::sqlx::{FetchID, SqlErrorExt};
 use crate::{authentication, authorization, Error, Result, State};
 
+/// Query parameters for `/players/{player}`.
+#[derive(Debug, Clone, Copy, Deserialize, IntoParams)]
+pub struct GetParams {
+	/// Additional information to include in the response.
+	include: Option<Include>,
+}
+
+/// Additional information that can be included when fetching a player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Include {
+	/// The player's current ban and the amount of previous bans.
+	Bans,
+}
+
 /// Fetch a specific player by their name or SteamID.
 ///
+/// If no player currently uses the given name, names that players have used in the past are
+/// searched as well. In that case, the matched name is returned as `matched_alias`.
+///
 /// The object returned from this endpoint will include an `ip_address` field if and only if the
 /// requesting user is authorized to manage bans.
 #[tracing::instrument(skip(state))]
@@ -28,7 +50,8 @@ use crate::{authentication, authorization, Error, Result, State};
   get,
   path = "/players/{player}",
   tag = "Players",
-  params(PlayerIdentifier),
+  security((), ("Browser Session" = ["bans"])),
+  params(PlayerIdentifier, GetParams),
   responses(
     responses::Ok<FullPlayer>,
     responses::NoContent,
@@ -41,25 +64,23 @@ pub async fn get(
 		authentication::Session<authorization::HasPermissions<{ Permissions::BANS.value() }>>,
 	>,
 	Path(player): Path<PlayerIdentifier>,
+	Query(GetParams { include }): Query<GetParams>,
 ) -> Result<Json<FullPlayer>> {
-	let mut query = QueryBuilder::new(queries::SELECT);
-
-	query.push(" WHERE ");
-
-	match player {
-		PlayerIdentifier::SteamID(steam_id) => {
-			query.push(" p.id = ").push_bind(steam_id);
-		}
-		PlayerIdentifier::Name(name) => {
-			query.push(" p.name LIKE ").push_bind(format!("%{name}%"));
+	let mut player = match (player.resolve(&state.database).await?, player) {
+		(Some(steam_id), _) => {
+			let mut query = QueryBuilder::new(queries::SELECT);
+
+			query.push(" WHERE p.id = ").push_bind(steam_id);
+			query
+				.build_query_as::<FullPlayer>()
+				.fetch_one(&state.database)
+				.await?
 		}
-	}
-
-	let mut player = query
-		.build_query_as::<FullPlayer>()
-		.fetch_optional(&state.database)
-		.await?
-		.ok_or_else(|| Error::not_found("player"))?;
+		(None, PlayerIdentifier::Name(name)) => fetch_by_previous_name(&name, &state.database)
+			.await?
+			.ok_or_else(|| Error::not_found("player"))?,
+		(None, PlayerIdentifier::SteamID(_)) => return Err(Error::not_found("player")),
+	};
 
 	// Filter out IP address if we're not in a test and the user does not have permission to
 	// view IP addresses
@@ -67,13 +88,100 @@ pub async fn get(
 		player.ip_address = None;
 	}
 
+	if include == Some(Include::Bans) {
+		player.bans = Some(fetch_ban_summary(player.steam_id, &state.database).await?);
+	}
+
 	Ok(Json(player))
 }
 
+/// Fetches a player's active ban and counts their previous bans.
+async fn fetch_ban_summary(steam_id: SteamID, database: &Pool<MySql>) -> Result<BanSummary> {
+	let mut query = QueryBuilder::new(bans::queries::SELECT);
+
+	query
+		.push(" WHERE b.player_id = ")
+		.push_bind(steam_id)
+		.push(" AND b.expires_on > NOW() ")
+		.push(" ORDER BY b.created_on DESC ")
+		.push(" LIMIT 1 ");
+
+	// notes are only meant for admins
+	let active_ban = query
+		.build_query_as::<Ban>()
+		.fetch_optional(database)
+		.await?
+		.map(|ban| Ban { note: None, ..ban });
+
+	let previous_bans = sqlx::query_scalar! {
+		r#"
+		SELECT
+		  CAST(COUNT(id) AS UNSIGNED) `previous_bans!: u64`
+		FROM
+		  Bans
+		WHERE
+		  player_id = ?
+		  AND expires_on < NOW()
+		"#,
+		steam_id,
+	}
+	.fetch_one(database)
+	.await?;
+
+	Ok(BanSummary {
+		active_ban,
+		previous_bans,
+	})
+}
+
+/// Looks up a player by a name they used in the past.
+///
+/// If multiple names match, the most recently used one wins.
+async fn fetch_by_previous_name(name: &str, database: &Pool<MySql>) -> Result<Option<FullPlayer>> {
+	let Some(alias) = sqlx::query! {
+		r#"
+		SELECT
+		  player_id `player_id: SteamID`,
+		  name
+		FROM
+		  PlayerNameHistory
+		WHERE
+		  name LIKE ?
+		ORDER BY
+		  last_seen_on DESC
+		LIMIT
+		  1
+		"#,
+		format!("%{name}%"),
+	}
+	.fetch_optional(database)
+	.await?
+	else {
+		return Ok(None);
+	};
+
+	let mut query = QueryBuilder::new(queries::SELECT);
+
+	query.push(" WHERE p.id = ").push_bind(alias.player_id);
+
+	let player = query
+		.build_query_as::<FullPlayer>()
+		.fetch_optional(database)
+		.await?
+		.map(|player| FullPlayer {
+			matched_alias: Some(alias.name),
+			..player
+		});
+
+	Ok(player)
+}
+
 /// Update an existing player.
 ///
 /// This endpoint is for CS2 servers. Whenever a player disconnects, or when the map changes, they
 /// will update players using this endpoint.
+///
+/// Updates from an IP address in a banned range are rejected, like registrations are.
 #[tracing::instrument(skip(state))]
 #[utoipa::path(
   patch,
@@ -86,6 +194,7 @@ pub async fn get(
     responses::NoContent,
     responses::BadRequest,
     responses::Unauthorized,
+    responses::Forbidden,
     responses::UnprocessableEntity,
   ),
 )]
@@ -104,6 +213,19 @@ pub async fn patch(
 ) -> Result<NoContent> {
 	let mut transaction = state.transaction().await?;
 
+	if let Some(ban) = bans::find_ip_range_ban(ip_address, transaction.as_mut()).await? {
+		tracing::warn! {
+			target: "cs2kz_api::audit_log",
+			%steam_id,
+			%ip_address,
+			ban.id = %ban.id,
+			ban.range = %ban.range,
+			"rejected player update from banned ip range",
+		};
+
+		return Err(Error::banned_ip_address(ban.id));
+	}
+
 	let query_result = sqlx::query! {
 		r#"
 		UPDATE
@@ -130,6 +252,8 @@ pub async fn patch(
 
 	tracing::trace!(target: "cs2kz_api::audit_log", "updated player");
 
+	insert_name_history(steam_id, &name, &mut transaction).await?;
+
 	let session_id: GameSessionID = sqlx::query! {
 		r#"
 		INSERT INTO
@@ -287,6 +411,18 @@ mod tests {
 		assert_eq!(alphakeks.steam_id, 76561198282622073_u64);
 	}
 
+	#[crate::integration_test(fixtures = ["alphakeks-name-history"])]
+	async fn fetch_player_by_previous_name(ctx: &Context) {
+		let response = ctx.http_client.get(ctx.url("/players/kekw")).send().await?;
+
+		assert_eq!(response.status(), 200);
+
+		let alphakeks = response.json::<FullPlayer>().await?;
+
+		assert_eq!(alphakeks.name, "AlphaKeks");
+		assert_eq!(alphakeks.steam_id, 76561198282622073_u64);
+	}
+
 	#[crate::integration_test]
 	async fn update_player(ctx: &Context) {
 		let response = ctx
@@ -370,6 +506,19 @@ mod tests {
 
 		assert_eq!(player.name, new_name);
 
+		let response = ctx
+			.http_client
+			.get(ctx.url("/players/alphakeks"))
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 200);
+
+		let player = response.json::<FullPlayer>().await?;
+
+		assert_eq!(player.name, new_name);
+		assert_eq!(player.matched_alias.as_deref(), Some("AlphaKeks"));
+
 		let url = ctx.url(format_args!("/players/{}/preferences", player.steam_id));
 		let response = ctx.http_client.get(url).send().await?;
 
@@ -384,4 +533,51 @@ mod tests {
 
 		assert_eq!(funny_test, ctx.test_id);
 	}
+
+	#[crate::integration_test(fixtures = ["banned-ip-range"])]
+	async fn update_player_from_banned_ip_range(ctx: &Context) {
+		let update = PlayerUpdate {
+			name: String::from("AlphaKeks"),
+			ip_address: Ipv4Addr::new(69, 69, 4, 20).into(),
+			session: Session {
+				time_spent: TimeSpent {
+					active: Duration::from_secs(0).into(),
+					spectating: Duration::from_secs(0).into(),
+					afk: Duration::from_secs(0).into(),
+				},
+				bhop_stats: BhopStats { bhops: 0, perfs: 0 },
+				course_sessions: BTreeMap::new(),
+			},
+			preferences: json!({}),
+		};
+
+		let jwt = ctx.auth_server(Duration::from_secs(60 * 60))?;
+		let response = ctx
+			.http_client
+			.patch(ctx.url("/players/76561198282622073"))
+			.header("Authorization", format!("Bearer {jwt}"))
+			.json(&update)
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 403);
+
+		let ip_address = sqlx::query_scalar! {
+			r#"
+			SELECT
+			  ip_address `ip: IpAddr`
+			FROM
+			  Players
+			WHERE
+			  id = 76561198282622073
+			"#,
+		}
+		.fetch_one(&ctx.database)
+		.await?;
+
+		let banned_ip = Ipv4Addr::new(69, 69, 4, 20);
+
+		assert_ne!(ip_address, IpAddr::V4(banned_ip));
+		assert_ne!(ip_address, IpAddr::V6(banned_ip.to_ipv6_mapped()));
+	}
 }
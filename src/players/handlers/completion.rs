@@ -0,0 +1,219 @@
+//! HTTP handlers for the `/players/{player}/completion` routes.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use axum::extract::{Path, Query};
+use axum::Json;
+use cs2kz::{GlobalStatus, Mode, PlayerIdentifier, RankedStatus, Tier};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::maps::CourseID;
+use crate::openapi::responses;
+use crate::players::Completion;
+use crate::sqlx::FetchID;
+use crate::{Result, State};
+
+/// Query parameters for `/players/{player}/completion`.
+#[derive(Debug, Clone, Copy, Deserialize, IntoParams)]
+pub struct GetParams {
+	/// The mode to calculate completion for.
+	mode: Mode,
+}
+
+/// Fetch a player's completion of the global map pool.
+///
+/// Only ranked filters on global maps are taken into account, so maps that are still in
+/// testing don't count. A course counts as completed if the player has at least one record on
+/// any of its filters for the given mode.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  get,
+  path = "/players/{player}/completion",
+  tag = "Players",
+  params(PlayerIdentifier, GetParams),
+  responses(
+    responses::Ok<Completion>,
+    responses::BadRequest,
+  ),
+)]
+pub async fn get(
+	state: State,
+	Path(player): Path<PlayerIdentifier>,
+	Query(GetParams { mode }): Query<GetParams>,
+) -> Result<Json<Completion>> {
+	let player_id = player.fetch_id(&state.database).await?;
+
+	let courses = sqlx::query! {
+		r#"
+		SELECT
+		  c.id `course_id: CourseID`,
+		  f.tier `tier: Tier`,
+		  CAST(COUNT(pb.filter_id) AS UNSIGNED) `completed_filters!: u64`
+		FROM
+		  CourseFilters f
+		  JOIN Courses c ON c.id = f.course_id
+		  JOIN Maps m ON m.id = c.map_id
+		  LEFT JOIN (
+		    SELECT DISTINCT
+		      filter_id
+		    FROM
+		      Records
+		    WHERE
+		      player_id = ?
+		  ) pb ON pb.filter_id = f.id
+		WHERE
+		  f.mode_id = ?
+		  AND f.ranked_status = ?
+		  AND m.global_status = ?
+		GROUP BY
+		  c.id,
+		  f.tier
+		"#,
+		player_id,
+		mode,
+		RankedStatus::Ranked,
+		GlobalStatus::Global,
+	}
+	.fetch_all(state.acquire_read().await?.as_mut())
+	.await?;
+
+	let mut completion = Completion {
+		completed_courses: 0,
+		total_courses: 0,
+		by_tier: BTreeMap::new(),
+	};
+
+	// courses whose filters have different tiers show up once per tier
+	let mut seen_courses = BTreeSet::new();
+	let mut completed_courses = BTreeSet::new();
+
+	for course in courses {
+		let tier = completion.by_tier.entry(course.tier).or_default();
+
+		tier.total += 1;
+
+		if seen_courses.insert(course.course_id) {
+			completion.total_courses += 1;
+		}
+
+		if course.completed_filters > 0 {
+			tier.completed += 1;
+
+			if completed_courses.insert(course.course_id) {
+				completion.completed_courses += 1;
+			}
+		}
+	}
+
+	Ok(Json(completion))
+}
+
+#[cfg(test)]
+mod tests {
+	use cs2kz::Tier;
+
+	use crate::players::Completion;
+
+	#[crate::integration_test(fixtures = ["grotto-maps"])]
+	async fn only_counts_global_maps(ctx: &Context) {
+		sqlx::query!("UPDATE Maps SET global_status = 1 WHERE id IN (1, 2)")
+			.execute(&ctx.database)
+			.await?;
+
+		sqlx::query!("UPDATE CourseFilters SET ranked_status = 1")
+			.execute(&ctx.database)
+			.await?;
+
+		let response = ctx
+			.http_client
+			.get(ctx.url("/players/76561198282622073/completion"))
+			.query(&[("mode", "vanilla")])
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 200);
+
+		let completion = response.json::<Completion>().await?;
+
+		assert_eq!(completion.completed_courses, 0);
+		assert_eq!(completion.total_courses, 2);
+
+		let very_easy = completion
+			.by_tier
+			.get(&Tier::VeryEasy)
+			.expect("grotto is very easy");
+
+		assert_eq!(very_easy.completed, 0);
+		assert_eq!(very_easy.total, 2);
+	}
+
+	#[crate::integration_test(fixtures = ["grotto-maps"])]
+	async fn counts_completed_courses(ctx: &Context) {
+		sqlx::query!("UPDATE Maps SET global_status = 1")
+			.execute(&ctx.database)
+			.await?;
+
+		sqlx::query!("UPDATE CourseFilters SET ranked_status = 1")
+			.execute(&ctx.database)
+			.await?;
+
+		// a second filter on the same course, which should not count as a separate course
+		sqlx::query! {
+			r#"
+			INSERT INTO
+			  CourseFilters (course_id, mode_id, teleports, tier, ranked_status)
+			VALUES
+			  (1, 1, 0, 1, 1)
+			"#,
+		}
+		.execute(&ctx.database)
+		.await?;
+
+		for filter_id in [1, 5] {
+			sqlx::query! {
+				r#"
+				INSERT INTO
+				  Records (
+				    filter_id,
+				    style_flags,
+				    teleports,
+				    time,
+				    player_id,
+				    server_id,
+				    bhops,
+				    perfs,
+				    plugin_version_id
+				  )
+				VALUES
+				  (?, 0, 0, 60.0, 76561198282622073, 1, 0, 0, 1)
+				"#,
+				filter_id,
+			}
+			.execute(&ctx.database)
+			.await?;
+		}
+
+		let response = ctx
+			.http_client
+			.get(ctx.url("/players/76561198282622073/completion"))
+			.query(&[("mode", "vanilla")])
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 200);
+
+		let completion = response.json::<Completion>().await?;
+
+		assert_eq!(completion.completed_courses, 1);
+		assert_eq!(completion.total_courses, 4);
+
+		let very_easy = completion
+			.by_tier
+			.get(&Tier::VeryEasy)
+			.expect("grotto is very easy");
+
+		assert_eq!(very_easy.completed, 1);
+		assert_eq!(very_easy.total, 4);
+	}
+}
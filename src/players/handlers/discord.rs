@@ -0,0 +1,58 @@
+//! HTTP handlers for the `/players/{player}/discord` routes.
+
+use axum::extract::Path;
+use cs2kz::SteamID;
+
+use crate::authentication::CsrfToken;
+use crate::authorization::Permissions;
+use crate::openapi::responses;
+use crate::openapi::responses::NoContent;
+use crate::{authentication, Error, Result, State};
+
+/// Unlink a player's Discord account.
+///
+/// Players can unlink their own account; admins can unlink anyone's.
+///
+/// Requires a valid CSRF token in the `X-CSRF-Token` header.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  delete,
+  path = "/players/{steam_id}/discord",
+  tag = "Players",
+  security(("Browser Session" = [])),
+  params(SteamID),
+  responses(
+    responses::NoContent,
+    responses::BadRequest,
+    responses::Unauthorized,
+    responses::Forbidden,
+  ),
+)]
+pub async fn delete(
+	state: State,
+	session: authentication::Session,
+	_csrf: CsrfToken,
+	Path(steam_id): Path<SteamID>,
+) -> Result<NoContent> {
+	let user = session.user();
+
+	if user.steam_id() != steam_id && !user.permissions().contains(Permissions::ADMIN) {
+		return Err(Error::unauthorized());
+	}
+
+	sqlx::query!(
+		"UPDATE Players SET discord_id = NULL WHERE id = ?",
+		steam_id
+	)
+	.execute(&state.database)
+	.await?;
+
+	tracing::info! {
+		target: "cs2kz_api::audit_log",
+		%steam_id,
+		unlinked_by = %user.steam_id(),
+		"unlinked discord account",
+	};
+
+	Ok(NoContent)
+}
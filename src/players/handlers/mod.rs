@@ -4,3 +4,5 @@ pub mod root;
 pub mod by_identifier;
 pub mod steam;
 pub mod preferences;
+pub mod completion;
+pub mod discord;
@@ -5,10 +5,10 @@ use axum::Json;
 use cs2kz::PlayerIdentifier;
 use serde_json::Value as JsonValue;
 use sqlx::types::Json as SqlJson;
-use sqlx::QueryBuilder;
 
 use crate::openapi::responses;
-use crate::{Error, Result, State};
+use crate::sqlx::FetchID;
+use crate::{Result, State};
 
 /// Fetch a player's in-game preferences.
 #[tracing::instrument(skip(state))]
@@ -24,22 +24,20 @@ use crate::{Error, Result, State};
   ),
 )]
 pub async fn get(state: State, Path(player): Path<PlayerIdentifier>) -> Result<Json<JsonValue>> {
-	let mut query = QueryBuilder::new("SELECT preferences FROM Players WHERE");
-
-	match player {
-		PlayerIdentifier::SteamID(steam_id) => {
-			query.push(" id = ").push_bind(steam_id);
-		}
-		PlayerIdentifier::Name(name) => {
-			query.push(" name LIKE ").push_bind(format!("%{name}%"));
-		}
+	let player_id = player.fetch_id(&state.database).await?;
+	let SqlJson(preferences) = sqlx::query_scalar! {
+		r#"
+		SELECT
+		  preferences `preferences: SqlJson<JsonValue>`
+		FROM
+		  Players
+		WHERE
+		  id = ?
+		"#,
+		player_id,
 	}
-
-	let SqlJson(preferences) = query
-		.build_query_scalar::<SqlJson<JsonValue>>()
-		.fetch_optional(&state.database)
-		.await?
-		.ok_or_else(|| Error::not_found("player"))?;
+	.fetch_one(&state.database)
+	.await?;
 
 	Ok(Json(preferences))
 }
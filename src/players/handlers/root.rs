@@ -2,9 +2,10 @@
 
 use axum::extract::Query;
 use axum::Json;
+use cs2kz::SteamID;
 use futures::TryStreamExt;
 use serde::Deserialize;
-use sqlx::QueryBuilder;
+use sqlx::{MySql, QueryBuilder, Transaction};
 use utoipa::IntoParams;
 
 use crate::authentication::Jwt;
@@ -13,7 +14,7 @@ use crate::openapi::parameters::{Limit, Offset};
 use crate::openapi::responses::{self, Created, PaginationResponse};
 use crate::players::{queries, FullPlayer, NewPlayer};
 use crate::sqlx::{query, QueryBuilderExt, SqlErrorExt};
-use crate::{authentication, authorization, Error, Result, State};
+use crate::{authentication, authorization, bans, Error, Result, State};
 
 /// Query parameters for `/players`.
 #[derive(Debug, Clone, Copy, Deserialize, IntoParams)]
@@ -36,6 +37,7 @@ pub struct GetParams {
   get,
   path = "/players",
   tag = "Players",
+  security((), ("Browser Session" = ["bans"])),
   params(GetParams),
   responses(
     responses::Ok<PaginationResponse<FullPlayer>>,
@@ -84,6 +86,7 @@ pub async fn get(
 	Ok(Json(PaginationResponse {
 		total,
 		results: players,
+		next_cursor: None,
 	}))
 }
 
@@ -103,6 +106,7 @@ pub async fn get(
     responses::Created,
     responses::BadRequest,
     responses::Unauthorized,
+    responses::Forbidden,
     responses::UnprocessableEntity,
   ),
 )]
@@ -117,6 +121,21 @@ pub async fn post(
 		ip_address,
 	}): Json<NewPlayer>,
 ) -> Result<Created> {
+	let mut transaction = state.transaction().await?;
+
+	if let Some(ban) = bans::find_ip_range_ban(ip_address, transaction.as_mut()).await? {
+		tracing::warn! {
+			target: "cs2kz_api::audit_log",
+			%steam_id,
+			%ip_address,
+			ban.id = %ban.id,
+			ban.range = %ban.range,
+			"rejected player from banned ip range",
+		};
+
+		return Err(Error::banned_ip_address(ban.id));
+	}
+
 	sqlx::query! {
 		r#"
 		INSERT INTO
@@ -128,7 +147,7 @@ pub async fn post(
 		name,
 		ip_address,
 	}
-	.execute(&state.database)
+	.execute(transaction.as_mut())
 	.await
 	.map_err(|err| {
 		if err.is_duplicate_entry() {
@@ -138,11 +157,41 @@ pub async fn post(
 		}
 	})?;
 
+	insert_name_history(steam_id, &name, &mut transaction).await?;
+
+	transaction.commit().await?;
+
 	tracing::info!(target: "cs2kz_api::audit_log", "registered new player");
 
 	Ok(Created(()))
 }
 
+/// Records a name a player has used, so they can be found by it later.
+///
+/// If the player has used this name before, it is marked as seen again.
+pub(super) async fn insert_name_history(
+	steam_id: SteamID,
+	name: &str,
+	transaction: &mut Transaction<'_, MySql>,
+) -> Result<()> {
+	sqlx::query! {
+		r#"
+		INSERT INTO
+		  PlayerNameHistory (player_id, name)
+		VALUES
+		  (?, ?)
+		ON DUPLICATE KEY UPDATE
+		  last_seen_on = NOW()
+		"#,
+		steam_id,
+		name,
+	}
+	.execute(transaction.as_mut())
+	.await?;
+
+	Ok(())
+}
+
 #[cfg(test)]
 mod tests {
 	use std::net::{Ipv4Addr, Ipv6Addr};
@@ -241,4 +290,57 @@ mod tests {
 		assert_eq!(new_player.name, player.name);
 		assert!(player.ip_address.and_then(|ip| ip.to_ipv4_mapped()) == Some(new_ip));
 	}
+
+	#[crate::integration_test(fixtures = ["banned-ip-range"])]
+	async fn register_player_from_banned_ip_range(ctx: &Context) {
+		let jwt = ctx.auth_server(Duration::from_secs(60 * 60))?;
+		let player = NewPlayer {
+			name: String::from("definitely not AlphaKeks"),
+			steam_id: SteamID::MAX,
+			ip_address: Ipv4Addr::new(69, 69, 4, 20).into(),
+		};
+
+		let banned = ctx
+			.http_client
+			.post(ctx.url("/players"))
+			.header("Authorization", format!("Bearer {jwt}"))
+			.json(&player)
+			.send()
+			.await?;
+
+		assert_eq!(banned.status(), 403);
+
+		let player = NewPlayer {
+			ip_address: Ipv4Addr::new(69, 70, 4, 20).into(),
+			..player
+		};
+
+		let success = ctx
+			.http_client
+			.post(ctx.url("/players"))
+			.header("Authorization", format!("Bearer {jwt}"))
+			.json(&player)
+			.send()
+			.await?;
+
+		assert_eq!(success.status(), 201);
+	}
+
+	#[crate::integration_test]
+	async fn players_match_schema(ctx: &Context) {
+		let response = ctx.http_client.get(ctx.url("/players")).send().await?;
+
+		assert_eq!(response.status(), 200);
+
+		let response = response.json::<serde_json::Value>().await?;
+		let players = response["results"]
+			.as_array()
+			.context("`results` should be an array")?;
+
+		assert!(!players.is_empty());
+
+		for player in players {
+			crate::test::validate_schema::<FullPlayer>(player)?;
+		}
+	}
 }
@@ -22,8 +22,8 @@ use crate::{steam, Result, State};
   ),
 )]
 pub async fn get(state: State, Path(player): Path<PlayerIdentifier>) -> Result<Json<steam::User>> {
-	let steam_id = player.fetch_id(&state.database).await?;
-	let user = steam::User::fetch(steam_id, &state.http_client, &state.config).await?;
+	let steam_id = player.fetch_id_unchecked(&state.database).await?;
+	let user = steam::User::fetch(steam_id, &state.steam_api).await?;
 
 	Ok(Json(user))
 }
@@ -1,5 +1,6 @@
 //! Everything related to KZ players.
 
+use axum::http::Method;
 use axum::{routing, Router};
 
 use crate::middleware::cors;
@@ -7,7 +8,8 @@ use crate::State;
 
 mod models;
 pub use models::{
-	CourseSession, CourseSessions, FullPlayer, NewPlayer, Player, PlayerUpdate, Session,
+	Completion, CourseSession, CourseSessions, FullPlayer, NewPlayer, Player, PlayerUpdate,
+	Session, TierCompletion,
 };
 
 mod queries;
@@ -40,5 +42,25 @@ pub fn router(state: State) -> Router {
 		.route_layer(cors::permissive())
 		.with_state(state.clone());
 
-	root.merge(by_identifier).merge(steam).merge(preferences)
+	let completion = Router::new()
+		.route(
+			"/:player/completion",
+			routing::get(handlers::completion::get),
+		)
+		.route_layer(cors::permissive())
+		.with_state(state.clone());
+
+	let discord = Router::new()
+		.route(
+			"/:player/discord",
+			routing::delete(handlers::discord::delete),
+		)
+		.route_layer(cors::dashboard([Method::DELETE]))
+		.with_state(state.clone());
+
+	root.merge(by_identifier)
+		.merge(steam)
+		.merge(preferences)
+		.merge(completion)
+		.merge(discord)
 }
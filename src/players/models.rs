@@ -3,12 +3,13 @@
 use std::collections::BTreeMap;
 use std::net::{IpAddr, Ipv6Addr};
 
-use cs2kz::{Mode, SteamID};
+use cs2kz::{Mode, SteamID, Tier};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value as JsonValue;
 use sqlx::FromRow;
 use utoipa::ToSchema;
 
+use crate::bans::BanSummary;
 use crate::game_sessions::TimeSpent;
 use crate::maps::CourseID;
 use crate::records::BhopStats;
@@ -49,6 +50,20 @@ pub struct FullPlayer {
 
 	/// Whether this player is currently banned.
 	pub is_banned: bool,
+
+	/// A previous name of this player that matched the search query.
+	///
+	/// This field is only included if the player was found by a name they no longer use.
+	#[sqlx(default)]
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub matched_alias: Option<String>,
+
+	/// The player's current ban and the amount of previous bans.
+	///
+	/// This field is only included if requested with `?include=bans`.
+	#[sqlx(skip)]
+	#[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+	pub bans: Option<BanSummary>,
 }
 
 impl FullPlayer {
@@ -252,3 +267,34 @@ impl CourseSession {
 		self.finished_runs <= self.started_runs
 	}
 }
+
+/// A player's progress through the global map pool in a specific mode.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Completion {
+	/// How many courses the player has completed.
+	pub completed_courses: u64,
+
+	/// How many courses there are in total.
+	pub total_courses: u64,
+
+	/// Progress per tier.
+	///
+	/// A course whose filters have different tiers counts towards each of them.
+	#[schema(example = json!({
+	  "very_easy": {
+	    "completed": 12,
+	    "total": 15
+	  }
+	}))]
+	pub by_tier: BTreeMap<Tier, TierCompletion>,
+}
+
+/// A player's progress through the courses of a specific tier.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TierCompletion {
+	/// How many courses of this tier the player has completed.
+	pub completed: u64,
+
+	/// How many courses of this tier there are in total.
+	pub total: u64,
+}
@@ -1,6 +1,6 @@
 //! HTTP handlers for the `/plugin/versions` routes.
 
-use axum::extract::Query;
+use axum::extract::{Path, Query};
 use axum::Json;
 use serde::Deserialize;
 use sqlx::QueryBuilder;
@@ -10,8 +10,11 @@ use crate::authentication::ApiKey;
 use crate::make_id::IntoID;
 use crate::openapi::parameters::{Limit, Offset};
 use crate::openapi::responses;
-use crate::openapi::responses::{Created, PaginationResponse};
-use crate::plugin::{CreatedPluginVersion, NewPluginVersion, PluginVersion, PluginVersionID};
+use crate::openapi::responses::{Created, NoContent, PaginationResponse};
+use crate::plugin::{
+	self, CreatedPluginVersion, NewPluginVersion, PluginVersion, PluginVersionID,
+	YankedPluginVersion,
+};
 use crate::sqlx::{query, QueryBuilderExt, SqlErrorExt};
 use crate::{Error, Result, State};
 
@@ -66,6 +69,7 @@ pub async fn get(
 	Ok(Json(PaginationResponse {
 		total,
 		results: plugin_versions,
+		next_cursor: None,
 	}))
 }
 
@@ -164,3 +168,76 @@ pub async fn post(
 
 	Ok(Created(Json(CreatedPluginVersion { plugin_version_id })))
 }
+
+/// Yank a CS2KZ plugin version.
+///
+/// Servers running a yanked version will not be able to authenticate anymore, and are told to
+/// upgrade instead.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  put,
+  path = "/plugin/versions/{id}/yank",
+  tag = "CS2KZ Plugin",
+  security(("API Key" = ["plugin_versions"])),
+  params(("id" = u16, Path, description = "The plugin version's ID")),
+  request_body = YankedPluginVersion,
+  responses(
+    responses::NoContent,
+    responses::BadRequest,
+    responses::Unauthorized,
+    responses::Conflict,
+    responses::UnprocessableEntity,
+  ),
+)]
+pub async fn yank(
+	state: State,
+	api_key: ApiKey,
+	Path(plugin_version_id): Path<PluginVersionID>,
+	Json(YankedPluginVersion { reason }): Json<YankedPluginVersion>,
+) -> Result<NoContent> {
+	if api_key.name() != "plugin_versions" {
+		return Err(Error::unauthorized().context(api_key.to_string()));
+	}
+
+	let mut transaction = state.transaction().await?;
+
+	plugin::yank_version(plugin_version_id, &reason, &mut transaction).await?;
+
+	transaction.commit().await?;
+
+	tracing::info! {
+		target: "cs2kz_api::audit_log",
+		id = %plugin_version_id,
+		%reason,
+		"yanked plugin version",
+	};
+
+	Ok(NoContent)
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::plugin::PluginVersion;
+
+	#[crate::integration_test]
+	async fn plugin_versions_match_schema(ctx: &Context) {
+		let response = ctx
+			.http_client
+			.get(ctx.url("/plugin/versions"))
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 200);
+
+		let response = response.json::<serde_json::Value>().await?;
+		let versions = response["results"]
+			.as_array()
+			.context("`results` should be an array")?;
+
+		assert!(!versions.is_empty());
+
+		for version in versions {
+			crate::test::validate_schema::<PluginVersion>(version)?;
+		}
+	}
+}
@@ -8,7 +8,12 @@ use crate::middleware::cors;
 use crate::State;
 
 mod models;
-pub use models::{CreatedPluginVersion, NewPluginVersion, PluginVersion, PluginVersionID};
+pub use models::{
+	CreatedPluginVersion, NewPluginVersion, PluginVersion, PluginVersionID, YankedPluginVersion,
+};
+
+pub mod yank;
+pub use yank::{ensure_not_yanked, yank_version};
 
 pub mod handlers;
 
@@ -18,5 +23,6 @@ pub fn router(state: State) -> Router {
 		.route("/versions", routing::get(handlers::versions::get))
 		.route_layer(cors::permissive())
 		.route("/versions", routing::post(handlers::versions::post))
+		.route("/versions/:id/yank", routing::put(handlers::versions::yank))
 		.with_state(state.clone())
 }
@@ -26,6 +26,14 @@ pub struct PluginVersion {
 
 	/// When this version was submitted.
 	pub created_on: DateTime<Utc>,
+
+	/// When this version was yanked, if it was.
+	///
+	/// Servers running a yanked version cannot authenticate with the API anymore.
+	pub yanked_on: Option<DateTime<Utc>>,
+
+	/// Why this version was yanked.
+	pub yanked_reason: Option<String>,
 }
 
 impl FromRow<'_, MySqlRow> for PluginVersion {
@@ -41,6 +49,8 @@ impl FromRow<'_, MySqlRow> for PluginVersion {
 				})?,
 			git_revision: row.try_get("git_revision")?,
 			created_on: row.try_get("created_on")?,
+			yanked_on: row.try_get("yanked_on")?,
+			yanked_reason: row.try_get("yanked_reason")?,
 		})
 	}
 }
@@ -63,3 +73,12 @@ pub struct CreatedPluginVersion {
 	/// The version's ID.
 	pub plugin_version_id: PluginVersionID,
 }
+
+/// Request payload for yanking a plugin version.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct YankedPluginVersion {
+	/// Why the version is being yanked.
+	///
+	/// This is shown to servers that are still running it.
+	pub reason: String,
+}
@@ -0,0 +1,84 @@
+//! Yanking plugin versions.
+//!
+//! If a release turns out to be broken, it can be yanked. Servers still running a yanked version
+//! will be rejected when they try to authenticate, and are told why, so they know to upgrade.
+
+use chrono::{DateTime, Utc};
+use sqlx::{MySql, Transaction};
+
+use crate::plugin::PluginVersionID;
+use crate::{Error, Result};
+
+/// Marks the plugin version with the given ID as yanked.
+pub async fn yank_version(
+	plugin_version_id: PluginVersionID,
+	reason: &str,
+	transaction: &mut Transaction<'_, MySql>,
+) -> Result<()> {
+	let yanked_on = sqlx::query_scalar! {
+		r#"
+		SELECT
+		  yanked_on `yanked_on: DateTime<Utc>`
+		FROM
+		  PluginVersions
+		WHERE
+		  id = ?
+		FOR UPDATE
+		"#,
+		plugin_version_id,
+	}
+	.fetch_optional(transaction.as_mut())
+	.await?
+	.ok_or_else(|| Error::not_found("plugin version"))?;
+
+	if yanked_on.is_some() {
+		return Err(Error::already_exists("yank for this plugin version"));
+	}
+
+	sqlx::query! {
+		r#"
+		UPDATE
+		  PluginVersions
+		SET
+		  yanked_on = NOW(),
+		  yanked_reason = ?
+		WHERE
+		  id = ?
+		"#,
+		reason,
+		plugin_version_id,
+	}
+	.execute(transaction.as_mut())
+	.await?;
+
+	Ok(())
+}
+
+/// Makes sure the plugin version with the given ID has not been yanked.
+pub async fn ensure_not_yanked(
+	plugin_version_id: PluginVersionID,
+	transaction: &mut Transaction<'_, MySql>,
+) -> Result<()> {
+	let yanked = sqlx::query! {
+		r#"
+		SELECT
+		  yanked_on `yanked_on: DateTime<Utc>`,
+		  yanked_reason
+		FROM
+		  PluginVersions
+		WHERE
+		  id = ?
+		"#,
+		plugin_version_id,
+	}
+	.fetch_optional(transaction.as_mut())
+	.await?;
+
+	match yanked {
+		Some(row) if row.yanked_on.is_some() => Err(Error::yanked_plugin_version(
+			plugin_version_id,
+			row.yanked_reason.unwrap_or_default(),
+		)),
+		_ => Ok(()),
+	}
+}
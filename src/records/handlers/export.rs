@@ -0,0 +1,352 @@
+//! HTTP handlers for the `/records/export` routes.
+
+use std::borrow::Cow;
+use std::cmp;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::num::NonZeroU32;
+
+use axum::body::Body;
+use axum::extract::Query;
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Utc};
+use cs2kz::{CourseIdentifier, MapIdentifier, Mode, PlayerIdentifier, ServerIdentifier};
+use futures::stream;
+use itertools::Itertools;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tokio::task;
+use utoipa::openapi::response::{Response as ResponseSchema, ResponseBuilder};
+use utoipa::openapi::{ContentBuilder, ObjectBuilder, RefOr, SchemaType};
+use utoipa::{IntoParams, IntoResponses, ToSchema};
+
+use super::root::{Filters, ResolvedFilters};
+use crate::kz::StyleFlags;
+use crate::openapi::responses;
+use crate::records::{queries, Record, RecordID};
+use crate::sqlx::FilteredQuery;
+use crate::time::TimeRange;
+use crate::{Error, Result, State};
+
+/// How many encoded records are buffered before we stop reading from the database.
+///
+/// If the client reads the response slower than we can fetch records, the buffer fills up and
+/// we stop fetching pages until there is room again.
+const BUFFER_SIZE: usize = 256;
+
+/// How many records are fetched from the database at once.
+const PAGE_SIZE: usize = 1_000;
+
+/// The maximum number of records in a single export.
+const MAX_RECORDS: usize = 100_000;
+
+/// How many exports a single client can start per minute.
+pub const RATE_LIMIT_PER_MINUTE: NonZeroU32 = match NonZeroU32::new(5) {
+	Some(n) => n,
+	None => unreachable!(),
+};
+
+/// Query parameters for `/records/export`.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ExportParams {
+	/// The format to export records in.
+	#[serde(default)]
+	format: ExportFormat,
+
+	/// Filter by mode.
+	mode: Option<Mode>,
+
+	/// Filter by styles.
+	#[param(value_type = Vec<String>)]
+	#[serde(default)]
+	styles: StyleFlags,
+
+	/// Filter by whether teleports were used.
+	teleports: Option<bool>,
+
+	/// Filter by player.
+	player: Option<PlayerIdentifier>,
+
+	/// Filter by map.
+	map: Option<MapIdentifier>,
+
+	/// Filter by course.
+	course: Option<CourseIdentifier>,
+
+	/// Filter by server.
+	server: Option<ServerIdentifier>,
+
+	/// Only include records submitted after this date.
+	created_after: Option<DateTime<Utc>>,
+
+	/// Only include records submitted before this date.
+	created_before: Option<DateTime<Utc>>,
+
+	/// Only include records submitted within this range.
+	///
+	/// This is an alternative to `created_after` / `created_before`, e.g.
+	/// `2024-01-01..2024-02-01`.
+	#[param(value_type = Option<String>)]
+	range: Option<TimeRange>,
+
+	/// Only include records with an ID greater than this.
+	///
+	/// Use this to continue an export that hit the record limit.
+	#[param(value_type = Option<u64>)]
+	after: Option<RecordID>,
+}
+
+/// Formats records can be exported in.
+#[derive(Debug, Default, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+	/// One JSON object per line.
+	#[default]
+	NDJson,
+
+	/// Comma separated values, with a header row.
+	Csv,
+}
+
+impl ExportFormat {
+	/// The `Content-Type` of the response body.
+	const fn content_type(self) -> &'static str {
+		match self {
+			Self::NDJson => "application/x-ndjson",
+			Self::Csv => "text/csv",
+		}
+	}
+
+	/// The file name suggested to clients.
+	const fn file_name(self) -> &'static str {
+		match self {
+			Self::NDJson => "records.ndjson",
+			Self::Csv => "records.csv",
+		}
+	}
+
+	/// The first line of the response body, if any.
+	const fn header(self) -> Option<&'static str> {
+		match self {
+			Self::NDJson => None,
+			Self::Csv => Some(
+				"id,mode,styles,teleports,time,player_id,player_name,map_id,map_name,course_id,\
+				 course_name,course_tier,server_id,server_name,bhops,perfs,created_on\n",
+			),
+		}
+	}
+
+	/// Encodes a single record as a line of the response body.
+	fn encode(self, record: &Record) -> Result<String> {
+		match self {
+			Self::NDJson => {
+				let mut line = serde_json::to_string(record)
+					.map_err(|err| Error::logic("failed to serialize record").context(err))?;
+
+				line.push('\n');
+
+				Ok(line)
+			}
+			Self::Csv => {
+				let mut line = String::new();
+
+				writeln!(
+					&mut line,
+					"{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+					record.id,
+					record.mode,
+					csv_field(&record.styles.iter().join(";")),
+					record.teleports,
+					record.time,
+					record.player.steam_id,
+					csv_field(&record.player.name),
+					record.map.id,
+					csv_field(&record.map.name),
+					record.course.id,
+					csv_field(record.course.name.as_deref().unwrap_or_default()),
+					record.course.tier,
+					record.server.id,
+					csv_field(&record.server.name),
+					record.bhop_stats.bhops,
+					record.bhop_stats.perfs,
+					record.created_on.to_rfc3339(),
+				)
+				.expect("writing to a string never fails");
+
+				Ok(line)
+			}
+		}
+	}
+}
+
+/// Quotes a CSV field if it contains any special characters.
+fn csv_field(value: &str) -> Cow<'_, str> {
+	if value.contains([',', '"', '\n', '\r']) {
+		Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+	} else {
+		Cow::Borrowed(value)
+	}
+}
+
+/// The `200 OK` response of `/records/export`.
+///
+/// The body is either NDJSON or CSV, depending on the requested [format](ExportFormat).
+#[derive(Debug, Clone, Copy)]
+pub struct ExportResponse;
+
+impl IntoResponses for ExportResponse {
+	fn responses() -> BTreeMap<String, RefOr<ResponseSchema>> {
+		let (_, record) = Record::schema();
+		let ndjson = ContentBuilder::new().schema(record).build();
+		let csv = ContentBuilder::new()
+			.schema(ObjectBuilder::new().schema_type(SchemaType::String))
+			.build();
+
+		let response = ResponseBuilder::new()
+			.description("One record per line, in the requested format.")
+			.content(ExportFormat::NDJson.content_type(), ndjson)
+			.content(ExportFormat::Csv.content_type(), csv)
+			.build();
+
+		BTreeMap::from_iter([(String::from("200"), RefOr::T(response))])
+	}
+}
+
+/// Export records in bulk.
+///
+/// Unlike `/records`, this endpoint is not paginated. Matching records are streamed in the
+/// response body, ordered by their ID. A single export contains at most 100,000 records; to
+/// export more, pass the ID of the last record you received as `after`.
+///
+/// Every client can only start a few exports per minute.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+	get,
+	path = "/records/export",
+	tag = "Records",
+	params(ExportParams),
+	responses(ExportResponse, responses::BadRequest, responses::TooManyRequests,)
+)]
+pub async fn get(
+	state: State,
+	Query(ExportParams {
+		format,
+		mode,
+		styles,
+		teleports,
+		player,
+		map,
+		course,
+		server,
+		created_after,
+		created_before,
+		range,
+		after,
+	}): Query<ExportParams>,
+) -> Result<Response> {
+	let filters = Filters {
+		mode,
+		styles,
+		teleports,
+		player,
+		map,
+		course,
+		server,
+		created_on: TimeRange::between(created_after, created_before)
+			.intersect(range.unwrap_or_default()),
+	}
+	.resolve(&state)
+	.await?;
+
+	let (tx, rx) = mpsc::channel::<Result<String>>(BUFFER_SIZE);
+
+	task::spawn(async move {
+		if let Some(header) = format.header() {
+			if tx.send(Ok(String::from(header))).await.is_err() {
+				return;
+			}
+		}
+
+		let mut last_id = after;
+		let mut remaining = MAX_RECORDS;
+
+		while remaining > 0 {
+			let limit = cmp::min(remaining, PAGE_SIZE);
+			let page = match fetch_page(filters.clone(), last_id, limit, &state).await {
+				Ok(page) => page,
+				Err(error) => {
+					tracing::error!(%error, "failed to export records");
+
+					// we are stopping either way, so it doesn't matter if the client is gone
+					drop(tx.send(Err(error)).await);
+
+					return;
+				}
+			};
+
+			for record in &page {
+				let line = format.encode(record);
+
+				if let Err(ref error) = line {
+					tracing::error!(%error, "failed to export records");
+				}
+
+				let failed = line.is_err();
+
+				// either the client went away, or we can't continue
+				if tx.send(line).await.is_err() || failed {
+					return;
+				}
+			}
+
+			if page.len() < limit {
+				break;
+			}
+
+			last_id = page.last().map(|record| record.id);
+			remaining -= page.len();
+		}
+	});
+
+	let body = stream::unfold(rx, |mut rx| async move {
+		rx.recv().await.map(|line| (line, rx))
+	});
+
+	let headers = [
+		(header::CONTENT_TYPE, format.content_type().to_owned()),
+		(
+			header::CONTENT_DISPOSITION,
+			format!("attachment; filename=\"{}\"", format.file_name()),
+		),
+	];
+
+	Ok((headers, Body::from_stream(body)).into_response())
+}
+
+/// Fetches up to `limit` records matching `filters`, with an ID greater than `after`.
+///
+/// The connection is released again before the records are sent to the client, so slow
+/// clients don't hold on to it for the entire download.
+async fn fetch_page(
+	filters: ResolvedFilters,
+	after: Option<RecordID>,
+	limit: usize,
+	state: &State,
+) -> Result<Vec<Record>> {
+	let mut query = FilteredQuery::new(queries::SELECT);
+
+	filters.apply(&mut query);
+
+	if let Some(after) = after {
+		query.filter(" r.id > ", after);
+	}
+
+	query.push(" ORDER BY r.id ASC LIMIT ").push(limit);
+
+	query
+		.build_query_as::<Record>()
+		.fetch_all(state.acquire_read().await?.as_mut())
+		.await
+		.map_err(Error::from)
+}
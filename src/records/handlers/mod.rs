@@ -3,4 +3,7 @@
 pub mod root;
 pub mod top;
 pub mod by_id;
+pub mod status;
 pub mod replays;
+pub mod wr_history;
+pub mod export;
@@ -1,20 +1,27 @@
 //! HTTP handlers for the `/records` routes.
 
+use std::time::Duration;
+
 use axum::extract::Query;
+use axum::http::HeaderMap;
 use axum::Json;
 use chrono::{DateTime, Utc};
-use cs2kz::{CourseIdentifier, MapIdentifier, Mode, PlayerIdentifier, ServerIdentifier};
-use serde::Deserialize;
+use cs2kz::{CourseIdentifier, MapIdentifier, Mode, PlayerIdentifier, ServerIdentifier, SteamID};
+use serde::{Deserialize, Serialize};
+use sqlx::{MySql, Transaction};
 use utoipa::{IntoParams, ToSchema};
 
 use crate::authentication::{self, Jwt};
 use crate::kz::StyleFlags;
-use crate::maps::FilterID;
+use crate::maps::{CourseID, FilterID, MapID};
 use crate::openapi::parameters::{Limit, Offset, SortingOrder};
 use crate::openapi::responses;
 use crate::openapi::responses::{Created, PaginationResponse};
-use crate::records::{queries, CreatedRecord, NewRecord, Record};
-use crate::sqlx::{query, FetchID, FilteredQuery, QueryBuilderExt, SqlErrorExt};
+use crate::records::idempotency::{self, IdempotencyKey};
+use crate::records::{queries, CreatedRecord, NewRecord, Record, RecordID};
+use crate::servers::ServerID;
+use crate::sqlx::{query, Cursor, FetchID, FilteredQuery, QueryBuilderExt, SqlErrorExt, WithTotal};
+use crate::time::{Seconds, TimeRange};
 use crate::{Error, Result, State};
 
 /// Query parameters for `/records`.
@@ -49,6 +56,13 @@ pub struct GetParams {
 	/// Only include records submitted before this date.
 	created_before: Option<DateTime<Utc>>,
 
+	/// Only include records submitted within this range.
+	///
+	/// This is an alternative to `created_after` / `created_before`, e.g.
+	/// `2024-01-01..2024-02-01`.
+	#[param(value_type = Option<String>)]
+	range: Option<TimeRange>,
+
 	/// Which field to sort the results by.
 	#[serde(default)]
 	sort_by: SortRecordsBy,
@@ -64,6 +78,14 @@ pub struct GetParams {
 	/// Pagination offset.
 	#[serde(default)]
 	offset: Offset,
+
+	/// Pagination cursor.
+	///
+	/// This is returned as `next_cursor` from a previous request, and takes precedence over
+	/// `offset`. It is only valid for the same `sort_by` and `sort_order` values it was
+	/// created with.
+	#[param(value_type = Option<String>)]
+	cursor: Option<Cursor<RecordsCursorKey>>,
 }
 
 /// Fields to sort records by.
@@ -78,6 +100,172 @@ pub enum SortRecordsBy {
 	Date,
 }
 
+/// The sort key stored in a [`Cursor`] for `/records`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordsCursorKey {
+	/// The record's time.
+	Time(Seconds),
+
+	/// The record's submission date.
+	Date(DateTime<Utc>),
+}
+
+/// Filters shared by `/records` and `/records/export`.
+pub(super) struct Filters {
+	/// Filter by mode.
+	pub mode: Option<Mode>,
+
+	/// Filter by styles.
+	pub styles: StyleFlags,
+
+	/// Filter by whether teleports were used.
+	pub teleports: Option<bool>,
+
+	/// Filter by player.
+	pub player: Option<PlayerIdentifier>,
+
+	/// Filter by map.
+	pub map: Option<MapIdentifier>,
+
+	/// Filter by course.
+	pub course: Option<CourseIdentifier>,
+
+	/// Filter by server.
+	pub server: Option<ServerIdentifier>,
+
+	/// Only include records submitted within this range.
+	pub created_on: TimeRange,
+}
+
+/// [`Filters`] with all identifiers resolved to IDs.
+///
+/// See [`Filters::resolve()`].
+#[derive(Clone)]
+pub(super) struct ResolvedFilters {
+	/// Filter by mode.
+	mode: Option<Mode>,
+
+	/// Filter by styles.
+	styles: StyleFlags,
+
+	/// Filter by whether teleports were used.
+	teleports: Option<bool>,
+
+	/// Filter by player.
+	player_id: Option<SteamID>,
+
+	/// Filter by map.
+	map_id: Option<MapID>,
+
+	/// Filter by course.
+	course_id: Option<CourseID>,
+
+	/// Filter by server.
+	server_id: Option<ServerID>,
+
+	/// Only include records submitted within this range.
+	created_on: TimeRange,
+}
+
+impl Filters {
+	/// Replaces all names with the IDs they refer to, so that applying the filters doesn't
+	/// require any more lookups.
+	pub(super) async fn resolve(self, state: &State) -> Result<ResolvedFilters> {
+		let player_id = match self.player {
+			None => None,
+			Some(player) => Some(player.fetch_id_unchecked(&state.database).await?),
+		};
+
+		let map_id = match self.map {
+			None => None,
+			Some(map) => Some(map.fetch_id_unchecked(&state.database).await?),
+		};
+
+		let course_id = match self.course {
+			None => None,
+			Some(course) => Some(course.fetch_id_unchecked(&state.database).await?),
+		};
+
+		let server_id = match self.server {
+			None => None,
+			Some(server) => Some(server.fetch_id_unchecked(&state.database).await?),
+		};
+
+		Ok(ResolvedFilters {
+			mode: self.mode,
+			styles: self.styles,
+			teleports: self.teleports,
+			player_id,
+			map_id,
+			course_id,
+			server_id,
+			created_on: self.created_on,
+		})
+	}
+}
+
+impl ResolvedFilters {
+	/// Applies these filters to a query built from [`queries::SELECT`].
+	pub(super) fn apply(self, query: &mut FilteredQuery<'_>) {
+		let Self {
+			mode,
+			styles,
+			teleports,
+			player_id,
+			map_id,
+			course_id,
+			server_id,
+			created_on,
+		} = self;
+
+		if let Some(mode) = mode {
+			query.filter(" f.mode_id = ", mode);
+		}
+
+		if styles != StyleFlags::NONE {
+			query
+				.filter(" ((r.style_flags & ", styles)
+				.push(") = ")
+				.push_bind(styles)
+				.push(")");
+		}
+
+		match teleports {
+			None => {}
+			Some(true) => {
+				query.filter(" r.teleports > ", 0);
+			}
+			Some(false) => {
+				query.filter(" r.teleports = ", 0);
+			}
+		}
+
+		if let Some(steam_id) = player_id {
+			query.filter(" r.player_id = ", steam_id);
+		}
+
+		if let Some(map_id) = map_id {
+			query.filter(" m.id = ", map_id);
+		}
+
+		if let Some(course_id) = course_id {
+			query.filter(" c.id = ", course_id);
+		}
+
+		if let Some(server_id) = server_id {
+			query.filter(" r.server_id = ", server_id);
+		}
+
+		query.filter_range("r.created_on", created_on);
+	}
+}
+
+/// How many times a record submission is attempted before giving up.
+///
+/// Concurrent submissions can occasionally deadlock, in which case the transaction is retried.
+const MAX_SUBMIT_ATTEMPTS: u32 = 3;
+
 /// Fetch records.
 #[tracing::instrument(skip(state))]
 #[utoipa::path(
@@ -103,108 +291,110 @@ pub async fn get(
 		server,
 		created_after,
 		created_before,
+		range,
 		sort_by,
 		sort_order,
 		limit,
 		offset,
+		cursor,
 	}): Query<GetParams>,
 ) -> Result<Json<PaginationResponse<Record>>> {
-	let mut query = FilteredQuery::new(queries::SELECT);
-
-	if let Some(mode) = mode {
-		query.filter(" f.mode_id = ", mode);
-	}
-
-	if styles != StyleFlags::NONE {
-		query
-			.filter(" ((r.style_flags & ", styles)
-			.push(") = ")
-			.push_bind(styles)
-			.push(")");
-	}
+	let mut query = FilteredQuery::new(query::select_with_total(queries::SELECT));
 
-	match teleports {
-		None => {}
-		Some(true) => {
-			query.filter(" r.teleports > ", 0);
-		}
-		Some(false) => {
-			query.filter(" r.teleports = ", 0);
-		}
-	}
-
-	if let Some(player) = player {
-		let steam_id = player.fetch_id(&state.database).await?;
-
-		query.filter(" r.player_id = ", steam_id);
-	}
-
-	if let Some(map) = map {
-		let map_id = map.fetch_id(&state.database).await?;
-
-		query.filter(" m.id = ", map_id);
-	}
-
-	if let Some(course) = course {
-		let course_id = course.fetch_id(&state.database).await?;
-
-		query.filter(" c.id = ", course_id);
-	}
-
-	if let Some(server) = server {
-		let server_id = server.fetch_id(&state.database).await?;
-
-		query.filter(" r.server_id = ", server_id);
-	}
-
-	if let Some(created_after) = created_after {
-		query.filter(" r.created_on > ", created_after);
-	}
-
-	if let Some(created_before) = created_before {
-		query.filter(" r.created_on < ", created_before);
+	Filters {
+		mode,
+		styles,
+		teleports,
+		player,
+		map,
+		course,
+		server,
+		created_on: TimeRange::between(created_after, created_before)
+			.intersect(range.unwrap_or_default()),
 	}
+	.resolve(&state)
+	.await?
+	.apply(&mut query);
 
-	query.order_by(sort_order, match sort_by {
+	let sort_column = match sort_by {
 		SortRecordsBy::Time => "r.time",
 		SortRecordsBy::Date => "r.created_on",
-	});
+	};
+
+	let offset = match cursor {
+		None => offset,
+		Some(cursor) => {
+			match (sort_by, *cursor.key()) {
+				(SortRecordsBy::Time, RecordsCursorKey::Time(time)) => {
+					query.filter_cursor(sort_column, "r.id", sort_order, time, cursor.id());
+				}
+				(SortRecordsBy::Date, RecordsCursorKey::Date(date)) => {
+					query.filter_cursor(sort_column, "r.id", sort_order, date, cursor.id());
+				}
+				_ => {
+					return Err(Error::invalid("cursor").context("cursor does not match `sort_by`"));
+				}
+			}
+
+			Offset::default()
+		}
+	};
 
+	query.order_by(sort_order, sort_column);
+	query.push(", r.id").push(sort_order.sql());
 	query.push_limits(limit, offset);
 
-	let mut transaction = state.transaction().await?;
-
-	let records = query
-		.build_query_as::<Record>()
-		.fetch_all(transaction.as_mut())
-		.await?;
+	let (total, records) = query
+		.build_query_as::<WithTotal<Record>>()
+		.fetch_all(state.acquire_read().await?.as_mut())
+		.await
+		.map(WithTotal::split)?;
 
 	if records.is_empty() {
 		return Err(Error::no_content());
 	}
 
-	let total = query::total_rows(&mut transaction).await?;
+	let next_cursor = records
+		.last()
+		.filter(|_| records.len() == usize::from(limit))
+		.map(|record| {
+			let key = match sort_by {
+				SortRecordsBy::Time => RecordsCursorKey::Time(record.time),
+				SortRecordsBy::Date => RecordsCursorKey::Date(record.created_on),
+			};
 
-	transaction.commit().await?;
+			Cursor::new(key, record.id.0).encode()
+		});
 
 	Ok(Json(PaginationResponse {
 		total,
 		results: records,
+		next_cursor,
 	}))
 }
 
 /// Create a new record.
+///
+/// Submitting a record identical to one the same server submitted just before is rejected
+/// with `409 Conflict`. Only exact duplicates (same player, course, mode, styles, teleports and
+/// time) count, so consecutive improvements are never blocked.
+///
+/// Servers can send an `Idempotency-Key` header to make retries safe. If a record was already
+/// submitted with the same key in the last 24 hours, its ID is returned again and nothing is
+/// inserted.
 #[tracing::instrument(skip(state))]
 #[utoipa::path(
   post,
   path = "/records",
   tag = "Records",
   security(("CS2 Server" = [])),
+  params(("Idempotency-Key" = Option<String>, Header, description = "A unique key for this submission")),
   request_body = NewRecord,
   responses(
     responses::Created<CreatedRecord>,
     responses::NoContent,
     responses::BadRequest,
+    responses::Conflict,
   ),
 )]
 pub async fn post(
@@ -212,7 +402,50 @@ pub async fn post(
 	Jwt {
 		payload: server, ..
 	}: Jwt<authentication::Server>,
-	Json(NewRecord {
+	headers: HeaderMap,
+	Json(record): Json<NewRecord>,
+) -> Result<Created<Json<CreatedRecord>>> {
+	let idempotency_key = IdempotencyKey::from_headers(&headers)?;
+	let duplicate_window = state.config.duplicate_record_window;
+	let record_id = state
+		.in_transaction_retry(MAX_SUBMIT_ATTEMPTS, |transaction| {
+			let record = record.clone();
+			let idempotency_key = idempotency_key.clone();
+
+			Box::pin(async move {
+				if let Some(ref key) = idempotency_key {
+					if let Some(record_id) =
+						idempotency::find(server.id(), key, transaction).await?
+					{
+						tracing::debug!(%record_id, %key, "replaying idempotent submission");
+						return Ok(record_id);
+					}
+				}
+
+				let record_id =
+					insert_record(server, record, duplicate_window, transaction).await?;
+
+				if let Some(ref key) = idempotency_key {
+					idempotency::save(server.id(), key, record_id, transaction).await?;
+				}
+
+				Ok(record_id)
+			})
+		})
+		.await?;
+
+	tracing::trace!(%record_id, "created record");
+
+	Ok(Created(Json(CreatedRecord { record_id })))
+}
+
+/// Inserts a new record into the database and returns the generated [`RecordID`].
+///
+/// If an identical record was submitted by the same server less than `duplicate_window` ago,
+/// the new one is rejected.
+async fn insert_record(
+	server: authentication::Server,
+	NewRecord {
 		player_id,
 		mode,
 		styles,
@@ -220,10 +453,10 @@ pub async fn post(
 		teleports,
 		time,
 		bhop_stats,
-	}): Json<NewRecord>,
-) -> Result<Created<Json<CreatedRecord>>> {
-	let mut transaction = state.transaction().await?;
-
+	}: NewRecord,
+	duplicate_window: Duration,
+	transaction: &mut Transaction<'_, MySql>,
+) -> Result<RecordID> {
 	let filter_id = sqlx::query_scalar! {
 		r#"
 		SELECT
@@ -243,6 +476,45 @@ pub async fn post(
 	.await?
 	.ok_or_else(|| Error::not_found("course"))?;
 
+	let style_flags = styles.iter().copied().collect::<StyleFlags>();
+
+	if !duplicate_window.is_zero() {
+		let duplicate = sqlx::query_scalar! {
+			r#"
+			SELECT
+			  id `id: RecordID`
+			FROM
+			  Records
+			WHERE
+			  filter_id = ?
+			  AND player_id = ?
+			  AND server_id = ?
+			  AND style_flags = ?
+			  AND teleports = ?
+			  AND time = ?
+			  AND created_on > NOW() - INTERVAL ? MICROSECOND
+			ORDER BY
+			  id DESC
+			LIMIT
+			  1
+			"#,
+			filter_id,
+			player_id,
+			server.id(),
+			style_flags,
+			teleports,
+			time.as_secs_f64(),
+			u64::try_from(duplicate_window.as_micros()).unwrap_or(u64::MAX),
+		}
+		.fetch_optional(transaction.as_mut())
+		.await?;
+
+		if let Some(record_id) = duplicate {
+			tracing::warn!(%record_id, %player_id, "rejecting duplicate record");
+			return Err(Error::duplicate_record(record_id));
+		}
+	}
+
 	let record_id = sqlx::query! {
 		r#"
 		INSERT INTO
@@ -261,7 +533,7 @@ pub async fn post(
 		  (?, ?, ?, ?, ?, ?, ?, ?, ?)
 		"#,
 		filter_id,
-		styles.iter().copied().collect::<StyleFlags>(),
+		style_flags,
 		teleports,
 		time.as_secs_f64(),
 		player_id,
@@ -282,9 +554,171 @@ pub async fn post(
 	.last_insert_id()
 	.into();
 
-	transaction.commit().await?;
+	Ok(record_id)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use serde_json::json;
+
+	use super::insert_record;
+	use crate::authentication;
+	use crate::plugin::PluginVersionID;
+	use crate::records::{idempotency, NewRecord, Record};
+	use crate::servers::ServerID;
+
+	#[crate::integration_test(fixtures = ["grotto-maps"])]
+	async fn idempotent_submissions(ctx: &Context) {
+		let jwt = ctx.auth_server(Duration::from_secs(60 * 60))?;
+		let record = json!({
+		  "player_id": 76561198282622073_u64,
+		  "mode": "vanilla",
+		  "styles": [],
+		  "course_id": 1,
+		  "teleports": 3,
+		  "time": 69.420,
+		  "bhop_stats": { "bhops": 10, "perfs": 5 }
+		});
+
+		let mut record_ids = Vec::new();
+
+		// the second request pretends to be a retry after a network failure
+		for _ in 0..2 {
+			let response = ctx
+				.http_client
+				.post(ctx.url("/records"))
+				.header("Authorization", format!("Bearer {jwt}"))
+				.header(idempotency::HEADER_NAME, "first-attempt")
+				.json(&record)
+				.send()
+				.await?;
+
+			assert_eq!(response.status(), 201);
+
+			let response = response.json::<serde_json::Value>().await?;
+			record_ids.push(response["record_id"].as_u64());
+		}
 
-	tracing::trace!(%record_id, "created record");
+		record_ids.dedup();
 
-	Ok(Created(Json(CreatedRecord { record_id })))
+		assert_eq!(record_ids.len(), 1);
+		assert!(record_ids.iter().all(Option::is_some));
+
+		let records = sqlx::query_scalar!("SELECT CAST(COUNT(*) AS UNSIGNED) FROM Records")
+			.fetch_one(&ctx.database)
+			.await?;
+
+		assert_eq!(records, 1);
+	}
+
+	#[crate::integration_test(fixtures = ["grotto-maps"])]
+	async fn duplicate_submissions(ctx: &Context) {
+		assert!(
+			!ctx.api_config.duplicate_record_window.is_zero(),
+			"duplicate check should be enabled",
+		);
+
+		let jwt = ctx.auth_server(Duration::from_secs(60 * 60))?;
+		let mut record = json!({
+		  "player_id": 76561198282622073_u64,
+		  "mode": "vanilla",
+		  "styles": [],
+		  "course_id": 1,
+		  "teleports": 3,
+		  "time": 69.420,
+		  "bhop_stats": { "bhops": 10, "perfs": 5 }
+		});
+
+		for expected_status in [201, 409] {
+			let response = ctx
+				.http_client
+				.post(ctx.url("/records"))
+				.header("Authorization", format!("Bearer {jwt}"))
+				.json(&record)
+				.send()
+				.await?;
+
+			assert_eq!(response.status(), expected_status);
+		}
+
+		// improving right away is fine
+		record["time"] = json!(60.0);
+
+		let response = ctx
+			.http_client
+			.post(ctx.url("/records"))
+			.header("Authorization", format!("Bearer {jwt}"))
+			.json(&record)
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 201);
+
+		let records = sqlx::query_scalar!("SELECT CAST(COUNT(*) AS UNSIGNED) FROM Records")
+			.fetch_one(&ctx.database)
+			.await?;
+
+		assert_eq!(records, 2);
+	}
+
+	#[crate::integration_test(fixtures = ["grotto-maps"])]
+	async fn duplicate_check_disabled(ctx: &Context) {
+		let server = authentication::Server::new(ServerID(1), PluginVersionID(1));
+		let record = serde_json::from_value::<NewRecord>(json!({
+		  "player_id": 76561198282622073_u64,
+		  "mode": "vanilla",
+		  "styles": [],
+		  "course_id": 1,
+		  "teleports": 3,
+		  "time": 69.420,
+		  "bhop_stats": { "bhops": 10, "perfs": 5 }
+		}))?;
+
+		let mut transaction = ctx.database.begin().await?;
+		let first = insert_record(server, record.clone(), Duration::ZERO, &mut transaction).await?;
+		let second = insert_record(server, record, Duration::ZERO, &mut transaction).await?;
+
+		transaction.commit().await?;
+
+		assert_ne!(first, second);
+	}
+
+	#[crate::integration_test(fixtures = ["grotto-maps"])]
+	async fn records_match_schema(ctx: &Context) {
+		let jwt = ctx.auth_server(Duration::from_secs(60 * 60))?;
+		let response = ctx
+			.http_client
+			.post(ctx.url("/records"))
+			.header("Authorization", format!("Bearer {jwt}"))
+			.json(&json!({
+			  "player_id": 76561198282622073_u64,
+			  "mode": "vanilla",
+			  "styles": [],
+			  "course_id": 1,
+			  "teleports": 3,
+			  "time": 69.420,
+			  "bhop_stats": { "bhops": 10, "perfs": 5 }
+			}))
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 201);
+
+		let response = ctx.http_client.get(ctx.url("/records")).send().await?;
+
+		assert_eq!(response.status(), 200);
+
+		let response = response.json::<serde_json::Value>().await?;
+		let records = response["results"]
+			.as_array()
+			.context("`results` should be an array")?;
+
+		assert!(!records.is_empty());
+
+		for record in records {
+			crate::test::validate_schema::<Record>(record)?;
+		}
+	}
 }
@@ -0,0 +1,223 @@
+//! HTTP handlers for the `/records/{record_id}/status` routes.
+
+use axum::extract::Path;
+use axum::Json;
+
+use crate::authentication::CsrfToken;
+use crate::authorization::{self, Permissions};
+use crate::openapi::responses;
+use crate::openapi::responses::NoContent;
+use crate::records::{status, RecordID, RecordStatusUpdate};
+use crate::{authentication, Result, State};
+
+/// Change the status of a record.
+///
+/// Records that are marked as suspicious, cheated, or wiped are hidden, but not deleted.
+/// Setting the status back to `default` restores them.
+///
+/// Requires a valid CSRF token in the `X-CSRF-Token` header.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  put,
+  path = "/records/{record_id}/status",
+  tag = "Records",
+  security(("Browser Session" = ["bans"])),
+  params(("record_id" = u64, Path, description = "The record's ID")),
+  request_body = RecordStatusUpdate,
+  responses(
+    responses::NoContent,
+    responses::BadRequest,
+    responses::Unauthorized,
+    responses::Forbidden,
+    responses::NotFound,
+  ),
+)]
+pub async fn put(
+	state: State,
+	session: authentication::Session<authorization::HasPermissions<{ Permissions::BANS.value() }>>,
+	_csrf: CsrfToken,
+	Path(record_id): Path<RecordID>,
+	Json(RecordStatusUpdate {
+		status: new_status,
+		reason,
+	}): Json<RecordStatusUpdate>,
+) -> Result<NoContent> {
+	let mut transaction = state.transaction().await?;
+
+	status::set_status(
+		record_id,
+		new_status,
+		reason.as_deref(),
+		session.user().steam_id(),
+		&mut transaction,
+	)
+	.await?;
+
+	transaction.commit().await?;
+
+	Ok(NoContent)
+}
+
+#[cfg(test)]
+mod tests {
+	use axum_extra::extract::cookie::Cookie;
+	use cs2kz::SteamID;
+	use reqwest::header;
+	use serde_json::json;
+
+	use crate::authentication::{csrf, CsrfToken};
+
+	#[crate::integration_test(fixtures = ["grotto-maps"])]
+	async fn status_round_trip(ctx: &Context) {
+		let record_id = sqlx::query! {
+			r#"
+			INSERT INTO
+			  Records (
+			    filter_id,
+			    style_flags,
+			    teleports,
+			    time,
+			    player_id,
+			    server_id,
+			    bhops,
+			    perfs,
+			    plugin_version_id
+			  )
+			VALUES
+			  (1, 0, 0, 60.0, 76561198282622073, 1, 0, 0, 1)
+			"#,
+		}
+		.execute(&ctx.database)
+		.await?
+		.last_insert_id();
+
+		let alphakeks = SteamID::from_u64(76561198282622073_u64).unwrap();
+		let session = ctx.auth_session(alphakeks).await?;
+		let csrf_cookie = CsrfToken::create(session.id(), &ctx.database)
+			.await?
+			.to_cookie(&ctx.api_config);
+
+		let cookies = format!(
+			"{}; {}",
+			Cookie::from(session).encoded(),
+			csrf_cookie.encoded()
+		);
+		let url = ctx.url(format_args!("/records/{record_id}/status"));
+
+		let response = ctx
+			.http_client
+			.put(url.clone())
+			.header(header::COOKIE, &cookies)
+			.json(&json!({ "status": "cheated" }))
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 403);
+
+		let response = ctx
+			.http_client
+			.put(url.clone())
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
+			.json(&json!({ "status": "cheated", "reason": "teleported" }))
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 204);
+
+		let cheated = sqlx::query_scalar! {
+			"SELECT CAST(COUNT(*) AS UNSIGNED) FROM CheatedRecords WHERE id = ?",
+			record_id,
+		}
+		.fetch_one(&ctx.database)
+		.await?;
+
+		assert_eq!(cheated, 1);
+
+		let response = ctx.http_client.get(ctx.url("/records")).send().await?;
+
+		assert_eq!(response.status(), 204);
+
+		let response = ctx
+			.http_client
+			.put(url)
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
+			.json(&json!({ "status": "default" }))
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 204);
+
+		let response = ctx.http_client.get(ctx.url("/records")).send().await?;
+
+		assert_eq!(response.status(), 200);
+
+		let records = response.json::<serde_json::Value>().await?;
+		let results = records["results"]
+			.as_array()
+			.context("`results` should be an array")?;
+
+		assert_eq!(results.len(), 1);
+		assert_eq!(
+			results.first().map(|record| &record["id"]),
+			Some(&json!(record_id))
+		);
+
+		let changes = sqlx::query! {
+			r#"
+			SELECT
+			  old_status,
+			  new_status,
+			  reason
+			FROM
+			  RecordStatusChanges
+			WHERE
+			  record_id = ?
+			ORDER BY
+			  id ASC
+			"#,
+			record_id,
+		}
+		.fetch_all(&ctx.database)
+		.await?
+		.into_iter()
+		.map(|row| (row.old_status, row.new_status, row.reason))
+		.collect::<Vec<_>>();
+
+		assert_eq!(changes, [
+			(
+				String::from("default"),
+				String::from("cheated"),
+				Some(String::from("teleported"))
+			),
+			(String::from("cheated"), String::from("default"), None),
+		]);
+	}
+
+	#[crate::integration_test]
+	async fn unknown_record(ctx: &Context) {
+		let alphakeks = SteamID::from_u64(76561198282622073_u64).unwrap();
+		let session = ctx.auth_session(alphakeks).await?;
+		let csrf_cookie = CsrfToken::create(session.id(), &ctx.database)
+			.await?
+			.to_cookie(&ctx.api_config);
+
+		let cookies = format!(
+			"{}; {}",
+			Cookie::from(session).encoded(),
+			csrf_cookie.encoded()
+		);
+
+		let response = ctx
+			.http_client
+			.put(ctx.url("/records/1337/status"))
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
+			.json(&json!({ "status": "wiped" }))
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 404);
+	}
+}
@@ -0,0 +1,219 @@
+//! HTTP handlers for the `/records/wr-history` routes.
+
+use std::iter;
+
+use axum::extract::Query;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use cs2kz::{CourseIdentifier, Mode};
+use futures::TryStreamExt;
+use serde::Deserialize;
+use sqlx::QueryBuilder;
+use utoipa::IntoParams;
+
+use crate::kz::StyleFlags;
+use crate::maps::FilterID;
+use crate::openapi::responses;
+use crate::records::{queries, Record, RecordID, WorldRecord};
+use crate::sqlx::FetchID;
+use crate::{Error, Result, State};
+
+/// Query parameters for `/records/wr-history`.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct GetParams {
+	/// The course.
+	course: CourseIdentifier,
+
+	/// The mode.
+	mode: Mode,
+
+	/// Whether to look at the TP or the Pro leaderboard.
+	teleports: bool,
+
+	/// Only consider records with exactly these styles.
+	#[param(value_type = Vec<String>)]
+	#[serde(default)]
+	styles: StyleFlags,
+}
+
+/// Fetch the world record history of a course.
+///
+/// Returns every record that was the fastest time on the leaderboard when it was submitted,
+/// from oldest to newest. The last one is the current world record.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+  get,
+  path = "/records/wr-history",
+  tag = "Records",
+  params(GetParams),
+  responses(
+    responses::OkList<WorldRecord>,
+    responses::NoContent,
+    responses::BadRequest,
+    responses::NotFound,
+  ),
+)]
+pub async fn get(
+	state: State,
+	Query(GetParams {
+		course,
+		mode,
+		teleports,
+		styles,
+	}): Query<GetParams>,
+) -> Result<Json<Vec<WorldRecord>>> {
+	let course_id = course.fetch_id(&state.database).await?;
+	let filter_id = sqlx::query_scalar! {
+		r#"
+		SELECT
+		  id `id: FilterID`
+		FROM
+		  CourseFilters
+		WHERE
+		  course_id = ?
+		  AND mode_id = ?
+		  AND teleports = ?
+		"#,
+		course_id,
+		mode,
+		teleports,
+	}
+	.fetch_optional(&state.database)
+	.await?
+	.ok_or_else(|| Error::not_found("filter"))?;
+
+	let mut connection = state.acquire_read().await?;
+	let mut records = sqlx::query! {
+		r#"
+		SELECT
+		  id `id: RecordID`,
+		  time
+		FROM
+		  Records
+		WHERE
+		  filter_id = ?
+		  AND style_flags = ?
+		ORDER BY
+		  created_on ASC,
+		  id ASC
+		"#,
+		filter_id,
+		styles,
+	}
+	.fetch(connection.as_mut());
+
+	let mut fastest = f64::INFINITY;
+	let mut record_ids = Vec::new();
+
+	while let Some(record) = records.try_next().await? {
+		if record.time < fastest {
+			fastest = record.time;
+			record_ids.push(record.id);
+		}
+	}
+
+	drop(records);
+
+	if record_ids.is_empty() {
+		return Err(Error::no_content());
+	}
+
+	let mut query = QueryBuilder::new(queries::SELECT);
+
+	query.push(" WHERE r.id IN (");
+
+	let mut separated = query.separated(", ");
+
+	for record_id in record_ids {
+		separated.push_bind(record_id);
+	}
+
+	query.push(") ORDER BY r.created_on ASC, r.id ASC");
+
+	let records = query
+		.build_query_as::<Record>()
+		.fetch_all(connection.as_mut())
+		.await?;
+
+	// every world record was beaten by the next one
+	let beaten_on = records
+		.iter()
+		.skip(1)
+		.map(|record| Some(record.created_on))
+		.chain(iter::once(None))
+		.collect::<Vec<Option<DateTime<Utc>>>>();
+
+	let history = iter::zip(records, beaten_on)
+		.map(|(record, beaten_on)| WorldRecord { record, beaten_on })
+		.collect();
+
+	Ok(Json(history))
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::records::RecordID;
+
+	#[crate::integration_test(fixtures = ["grotto-maps"])]
+	async fn only_improvements(ctx: &Context) {
+		for (time, created_on) in [
+			(60.0, "2024-01-01"),
+			(65.0, "2024-01-02"),
+			(55.0, "2024-01-03"),
+			(55.0, "2024-01-04"),
+			(50.0, "2024-01-05"),
+		] {
+			sqlx::query! {
+				r#"
+				INSERT INTO
+				  Records (
+				    filter_id,
+				    style_flags,
+				    teleports,
+				    time,
+				    player_id,
+				    server_id,
+				    bhops,
+				    perfs,
+				    plugin_version_id,
+				    created_on
+				  )
+				VALUES
+				  (1, 0, 0, ?, 76561198282622073, 1, 0, 0, 1, ?)
+				"#,
+				time,
+				created_on,
+			}
+			.execute(&ctx.database)
+			.await?;
+		}
+
+		let response = ctx
+			.http_client
+			.get(ctx.url("/records/wr-history"))
+			.query(&[("course", "1"), ("mode", "vanilla"), ("teleports", "true")])
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 200);
+
+		let history = response.json::<serde_json::Value>().await?;
+		let history = history
+			.as_array()
+			.context("response should be an array")?
+			.iter()
+			.map(|wr| {
+				let record_id = serde_json::from_value::<RecordID>(wr["record"]["id"].clone())?;
+				let beaten = !wr["beaten_on"].is_null();
+
+				anyhow::Ok((record_id, beaten))
+			})
+			.collect::<anyhow::Result<Vec<_>>>()?;
+
+		assert_eq!(history, [
+			(RecordID(1), true),
+			(RecordID(3), true),
+			(RecordID(5), false),
+		]);
+	}
+}
@@ -0,0 +1,121 @@
+//! Idempotent record submissions.
+//!
+//! Servers can send an [`Idempotency-Key`][header] header when submitting a record. If a
+//! submission with the same key was already processed within the last [`TTL`], the original
+//! record ID is returned again instead of inserting a new record. This makes it safe for
+//! servers to retry submissions after network failures.
+//!
+//! Keys are scoped to the server that sent them.
+//!
+//! [header]: HEADER_NAME
+
+use std::time::Duration;
+
+use axum::http::HeaderMap;
+use derive_more::Display;
+use sqlx::{MySql, Transaction};
+
+use crate::records::RecordID;
+use crate::servers::ServerID;
+use crate::{Error, Result};
+
+/// The HTTP header that contains the [`IdempotencyKey`].
+pub const HEADER_NAME: &str = "idempotency-key";
+
+/// How long keys are remembered for.
+pub const TTL: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// The maximum length of a key, in bytes.
+const MAX_LEN: usize = 255;
+
+/// A client-generated key that identifies a single record submission.
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+pub struct IdempotencyKey(String);
+
+impl IdempotencyKey {
+	/// Extracts an [`IdempotencyKey`] from the [header], if there is one.
+	///
+	/// [header]: HEADER_NAME
+	pub fn from_headers(headers: &HeaderMap) -> Result<Option<Self>> {
+		let Some(value) = headers.get(HEADER_NAME) else {
+			return Ok(None);
+		};
+
+		let key = value
+			.to_str()
+			.map_err(|err| Error::invalid("idempotency key").context(err))?;
+
+		if key.is_empty() || key.len() > MAX_LEN {
+			return Err(Error::invalid("idempotency key")
+				.context(format!("key must be between 1 and {MAX_LEN} bytes long")));
+		}
+
+		Ok(Some(Self(key.to_owned())))
+	}
+}
+
+/// Returns the record that was created with `key` by `server_id`, if it hasn't expired yet.
+///
+/// Expired keys of the same server are removed along the way.
+pub async fn find(
+	server_id: ServerID,
+	key: &IdempotencyKey,
+	transaction: &mut Transaction<'_, MySql>,
+) -> Result<Option<RecordID>> {
+	sqlx::query! {
+		r#"
+		DELETE FROM
+		  RecordIdempotencyKeys
+		WHERE
+		  server_id = ?
+		  AND created_on < NOW() - INTERVAL ? SECOND
+		"#,
+		server_id,
+		TTL.as_secs(),
+	}
+	.execute(transaction.as_mut())
+	.await?;
+
+	let record_id = sqlx::query_scalar! {
+		r#"
+		SELECT
+		  record_id `record_id: RecordID`
+		FROM
+		  RecordIdempotencyKeys
+		WHERE
+		  server_id = ?
+		  AND idempotency_key = ?
+		FOR UPDATE
+		"#,
+		server_id,
+		key.0,
+	}
+	.fetch_optional(transaction.as_mut())
+	.await?;
+
+	Ok(record_id)
+}
+
+/// Remembers that `key` was used by `server_id` to create `record_id`.
+pub async fn save(
+	server_id: ServerID,
+	key: &IdempotencyKey,
+	record_id: RecordID,
+	transaction: &mut Transaction<'_, MySql>,
+) -> Result<()> {
+	sqlx::query! {
+		r#"
+		INSERT INTO
+		  RecordIdempotencyKeys (server_id, idempotency_key, record_id)
+		VALUES
+		  (?, ?, ?)
+		"#,
+		server_id,
+		key.0,
+		record_id,
+	}
+	.execute(transaction.as_mut())
+	.await?;
+
+	Ok(())
+}
@@ -1,18 +1,31 @@
 //! Everything related to KZ records.
 
+use axum::http::Method;
 use axum::{routing, Router};
 
-use crate::middleware::cors;
-use crate::State;
+use crate::authorization::Permissions;
+use crate::middleware::auth::session_auth;
+use crate::middleware::{cors, rate_limit};
+use crate::{authorization, State};
 
 mod models;
-pub use models::{BhopStats, CreatedRecord, NewRecord, Record, RecordID};
+pub use models::{
+	BhopStats, CreatedRecord, NewRecord, Record, RecordID, RecordStatus, RecordStatusUpdate,
+	WorldRecord,
+};
 
 mod queries;
 pub mod handlers;
+pub mod idempotency;
+pub mod status;
 
 /// Returns an [`axum::Router`] for the `/records` routes.
 pub fn router(state: State) -> Router {
+	let auth = session_auth!(
+		authorization::HasPermissions<{ Permissions::BANS.value() }>,
+		state.clone(),
+	);
+
 	let root = Router::new()
 		.route("/", routing::get(handlers::root::get))
 		.route_layer(cors::permissive())
@@ -29,10 +42,37 @@ pub fn router(state: State) -> Router {
 		.route_layer(cors::permissive())
 		.with_state(state.clone());
 
+	let status = Router::new()
+		.route(
+			"/:id/status",
+			routing::put(handlers::status::put).route_layer(auth()),
+		)
+		.route_layer(cors::dashboard([Method::PUT]))
+		.with_state(state.clone());
+
 	let replay = Router::new()
 		.route("/:id/replay", routing::get(handlers::replays::get))
 		.route_layer(cors::permissive())
 		.with_state(state.clone());
 
-	root.merge(top).merge(by_id).merge(replay)
+	let wr_history = Router::new()
+		.route("/wr-history", routing::get(handlers::wr_history::get))
+		.route_layer(cors::permissive())
+		.with_state(state.clone());
+
+	let export = Router::new()
+		.route("/export", routing::get(handlers::export::get))
+		.route_layer(rate_limit::layer!(
+			handlers::export::RATE_LIMIT_PER_MINUTE,
+			state.clone(),
+		))
+		.route_layer(cors::permissive())
+		.with_state(state.clone());
+
+	root.merge(top)
+		.merge(by_id)
+		.merge(status)
+		.merge(replay)
+		.merge(wr_history)
+		.merge(export)
 }
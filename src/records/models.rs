@@ -1,11 +1,14 @@
 //! Types for modeling KZ records.
 
+use std::str::FromStr;
+
 use chrono::{DateTime, Utc};
 use cs2kz::{Mode, SteamID, Style};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use sqlx::mysql::MySqlRow;
-use sqlx::{FromRow, Row};
+use sqlx::{database, FromRow, MySql, Row};
+use thiserror::Error;
 use utoipa::ToSchema;
 
 use crate::kz::StyleFlags;
@@ -83,6 +86,16 @@ impl FromRow<'_, MySqlRow> for Record {
 	}
 }
 
+/// A record that was the world record at some point.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WorldRecord {
+	/// The record.
+	pub record: Record,
+
+	/// When the record was beaten, if it has been beaten yet.
+	pub beaten_on: Option<DateTime<Utc>>,
+}
+
 /// Bhop statistics.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct BhopStats {
@@ -94,7 +107,7 @@ pub struct BhopStats {
 }
 
 /// Request payload for creating a new record.
-#[derive(Debug, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 pub struct NewRecord {
 	/// The SteamID of the player who performed the record.
 	pub player_id: SteamID,
@@ -124,3 +137,107 @@ pub struct CreatedRecord {
 	/// The record's ID.
 	pub record_id: RecordID,
 }
+
+/// The review status of a record.
+///
+/// Every status has its own table, and records are moved between them when their status
+/// changes. Only records in the `Records` table show up anywhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordStatus {
+	/// The record is legitimate, or has not been looked at.
+	Default,
+
+	/// The record looks suspicious and is hidden until it has been reviewed.
+	Suspicious,
+
+	/// The record was cheated.
+	Cheated,
+
+	/// The record was removed for reasons other than cheating, e.g. because of a bug in the
+	/// plugin or on the map.
+	Wiped,
+}
+
+impl RecordStatus {
+	/// All the possible statuses.
+	pub const ALL: [Self; 4] = [Self::Default, Self::Suspicious, Self::Cheated, Self::Wiped];
+
+	/// Stringified version that is also expected when parsing a string into a
+	/// [`RecordStatus`].
+	pub const fn as_str(&self) -> &'static str {
+		match self {
+			RecordStatus::Default => "default",
+			RecordStatus::Suspicious => "suspicious",
+			RecordStatus::Cheated => "cheated",
+			RecordStatus::Wiped => "wiped",
+		}
+	}
+
+	/// The table records with this status are stored in.
+	pub const fn table(&self) -> &'static str {
+		match self {
+			RecordStatus::Default => "Records",
+			RecordStatus::Suspicious => "SuspiciousRecords",
+			RecordStatus::Cheated => "CheatedRecords",
+			RecordStatus::Wiped => "WipedRecords",
+		}
+	}
+}
+
+/// An error for parsing record statuses.
+#[derive(Debug, Error)]
+#[error("`{0}` is not a valid record status")]
+pub struct InvalidRecordStatus(String);
+
+impl FromStr for RecordStatus {
+	type Err = InvalidRecordStatus;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		match value {
+			"default" => Ok(Self::Default),
+			"suspicious" => Ok(Self::Suspicious),
+			"cheated" => Ok(Self::Cheated),
+			"wiped" => Ok(Self::Wiped),
+			invalid => Err(InvalidRecordStatus(invalid.to_owned())),
+		}
+	}
+}
+
+impl sqlx::Type<MySql> for RecordStatus {
+	fn type_info() -> <MySql as sqlx::Database>::TypeInfo {
+		<str as sqlx::Type<MySql>>::type_info()
+	}
+}
+
+impl<'q> sqlx::Encode<'q, MySql> for RecordStatus {
+	fn encode_by_ref(
+		&self,
+		buf: &mut <MySql as database::HasArguments<'q>>::ArgumentBuffer,
+	) -> sqlx::encode::IsNull {
+		<&'q str as sqlx::Encode<'q, MySql>>::encode_by_ref(&self.as_str(), buf)
+	}
+}
+
+impl<'q> sqlx::Decode<'q, MySql> for RecordStatus {
+	fn decode(
+		value: <MySql as database::HasValueRef<'q>>::ValueRef,
+	) -> Result<Self, sqlx::error::BoxDynError> {
+		Ok(<&'q str as sqlx::Decode<'q, MySql>>::decode(value)
+			.map(|value| value.parse::<Self>())??)
+	}
+}
+
+/// Request payload for changing the status of a record.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RecordStatusUpdate {
+	/// The new status.
+	pub status: RecordStatus,
+
+	/// Why the status is being changed.
+	#[serde(
+		default,
+		deserialize_with = "crate::serde::string::deserialize_empty_as_none"
+	)]
+	pub reason: Option<String>,
+}
@@ -2,7 +2,7 @@
 
 /// SQL query for `SELECT`ing records from the database.
 pub static SELECT: &str = r#"
-	SELECT SQL_CALC_FOUND_ROWS
+	SELECT
 	  r.id,
 	  f.mode_id mode,
 	  r.style_flags,
@@ -0,0 +1,96 @@
+//! Changing the review status of records.
+//!
+//! Records are never deleted; instead they are moved into a separate table for their
+//! [status](RecordStatus). This hides them from everything that reads the `Records` table, and
+//! makes it possible to restore them later. Every change is recorded in the
+//! `RecordStatusChanges` table, together with the admin who made it.
+
+use cs2kz::SteamID;
+use sqlx::{MySql, Transaction};
+
+use crate::records::{RecordID, RecordStatus};
+use crate::{Error, Result};
+
+/// Moves `record_id` into the table for `status`, on behalf of `admin_id`.
+///
+/// Returns the record's previous status. Nothing happens if the record already has the given
+/// status.
+pub async fn set_status(
+	record_id: RecordID,
+	status: RecordStatus,
+	reason: Option<&str>,
+	admin_id: SteamID,
+	transaction: &mut Transaction<'_, MySql>,
+) -> Result<RecordStatus> {
+	let old_status = fetch_status(record_id, transaction).await?;
+
+	if old_status == status {
+		return Ok(old_status);
+	}
+
+	sqlx::query(&format!(
+		"INSERT INTO {} SELECT * FROM {} WHERE id = ?",
+		status.table(),
+		old_status.table(),
+	))
+	.bind(record_id)
+	.execute(transaction.as_mut())
+	.await?;
+
+	sqlx::query(&format!("DELETE FROM {} WHERE id = ?", old_status.table()))
+		.bind(record_id)
+		.execute(transaction.as_mut())
+		.await?;
+
+	sqlx::query! {
+		r#"
+		INSERT INTO
+		  RecordStatusChanges (record_id, admin_id, old_status, new_status, reason)
+		VALUES
+		  (?, ?, ?, ?, ?)
+		"#,
+		record_id,
+		admin_id,
+		old_status,
+		status,
+		reason,
+	}
+	.execute(transaction.as_mut())
+	.await?;
+
+	tracing::info! {
+		target: "cs2kz_api::audit_log",
+		%record_id,
+		%admin_id,
+		old_status = old_status.as_str(),
+		new_status = status.as_str(),
+		?reason,
+		"updated record status",
+	};
+
+	Ok(old_status)
+}
+
+/// Finds out which table `record_id` is currently stored in, and locks its row until the
+/// transaction ends.
+async fn fetch_status(
+	record_id: RecordID,
+	transaction: &mut Transaction<'_, MySql>,
+) -> Result<RecordStatus> {
+	for status in RecordStatus::ALL {
+		let exists = sqlx::query(&format!(
+			"SELECT id FROM {} WHERE id = ? FOR UPDATE",
+			status.table(),
+		))
+		.bind(record_id)
+		.fetch_optional(transaction.as_mut())
+		.await?
+		.is_some();
+
+		if exists {
+			return Ok(status);
+		}
+	}
+
+	Err(Error::not_found("record"))
+}
@@ -5,10 +5,11 @@ use axum::Json;
 use cs2kz::ServerIdentifier;
 use sqlx::QueryBuilder;
 
+use crate::authentication::CsrfToken;
 use crate::openapi::responses;
 use crate::openapi::responses::NoContent;
 use crate::servers::{queries, Server, ServerID, ServerUpdate};
-use crate::sqlx::UpdateQuery;
+use crate::sqlx::{FetchID, UpdateQuery};
 use crate::{authentication, authorization, Error, Result, State};
 
 /// Fetch a server by its name or ID.
@@ -24,29 +25,22 @@ use crate::{authentication, authorization, Error, Result, State};
   ),
 )]
 pub async fn get(state: State, Path(server): Path<ServerIdentifier>) -> Result<Json<Server>> {
+	let server_id = server.fetch_id(&state.database).await?;
 	let mut query = QueryBuilder::new(queries::SELECT);
 
-	query.push(" WHERE ");
-
-	match server {
-		ServerIdentifier::ID(id) => {
-			query.push(" s.id = ").push_bind(id);
-		}
-		ServerIdentifier::Name(name) => {
-			query.push(" s.name LIKE ").push_bind(format!("%{name}%"));
-		}
-	}
+	query.push(" WHERE s.id = ").push_bind(server_id);
 
 	let server = query
 		.build_query_as::<Server>()
-		.fetch_optional(&state.database)
-		.await?
-		.ok_or_else(|| Error::not_found("server"))?;
+		.fetch_one(&state.database)
+		.await?;
 
 	Ok(Json(server))
 }
 
 /// Update an existing server.
+///
+/// Requires a valid CSRF token in the `X-CSRF-Token` header.
 #[tracing::instrument(skip(state))]
 #[utoipa::path(
   patch,
@@ -57,12 +51,14 @@ pub async fn get(state: State, Path(server): Path<ServerIdentifier>) -> Result<J
     responses::NoContent,
     responses::BadRequest,
     responses::Unauthorized,
+    responses::Forbidden,
     responses::UnprocessableEntity,
   ),
 )]
 pub async fn patch(
 	state: State,
 	session: authentication::Session<authorization::IsServerAdminOrOwner>,
+	_csrf: CsrfToken,
 	Path(server_id): Path<ServerID>,
 	Json(ServerUpdate {
 		name,
@@ -120,6 +116,7 @@ mod tests {
 	use cs2kz::SteamID;
 	use reqwest::header;
 
+	use crate::authentication::{csrf, CsrfToken};
 	use crate::servers::{Server, ServerUpdate};
 
 	#[crate::integration_test]
@@ -169,12 +166,20 @@ mod tests {
 
 		let alphakeks = SteamID::from_u64(76561198282622073_u64).unwrap();
 		let session = ctx.auth_session(alphakeks).await?;
-		let session_cookie = Cookie::from(session).encoded().to_string();
+		let csrf_cookie = CsrfToken::create(session.id(), &ctx.database)
+			.await?
+			.to_cookie(&ctx.api_config);
+		let cookies = format!(
+			"{}; {}",
+			Cookie::from(session).encoded(),
+			csrf_cookie.encoded(),
+		);
 
 		let response = ctx
 			.http_client
 			.patch(url)
-			.header(header::COOKIE, session_cookie)
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
 			.json(&update)
 			.send()
 			.await?;
@@ -2,20 +2,25 @@
 
 use std::time::Duration;
 
-use axum::extract::Path;
+use axum::extract::{Path, Query};
 use axum::Json;
+use serde::Deserialize;
+use sqlx::{MySql, Transaction};
+use utoipa::IntoParams;
 use uuid::Uuid;
 
-use crate::authentication::{self, Jwt};
+use crate::authentication::{self, CsrfToken, Jwt};
 use crate::authorization::Permissions;
 use crate::openapi::responses::{self, Created, NoContent};
-use crate::plugin::PluginVersionID;
+use crate::plugin::{self, PluginVersionID};
 use crate::servers::{AccessKeyRequest, AccessKeyResponse, RefreshKey, ServerID};
+use crate::time::Seconds;
 use crate::{authorization, Error, Result, State};
 
 /// Generate a temporary access token using a CS2 server's API key.
 ///
 /// This endpoint is for CS2 servers. They will generate a new access token every ~30min.
+/// Servers running a yanked plugin version are rejected.
 #[tracing::instrument(skip(state))]
 #[utoipa::path(
   post,
@@ -25,6 +30,7 @@ use crate::{authorization, Error, Result, State};
     responses::Created<Jwt<authentication::Server>>,
     responses::BadRequest,
     responses::Unauthorized,
+    responses::Forbidden,
     responses::UnprocessableEntity,
   ),
 )]
@@ -52,8 +58,31 @@ pub async fn generate_temp(
 	}
 	.fetch_optional(transaction.as_mut())
 	.await?
-	.map(|row| authentication::Server::new(row.server_id, row.plugin_version_id))
-	.ok_or_else(|| Error::unauthorized())?;
+	.map(|row| authentication::Server::new(row.server_id, row.plugin_version_id));
+
+	let server = match server {
+		Some(server) => {
+			// The server has picked up its new key, so the old one is not needed anymore.
+			revoke_previous_key(server.id(), &mut transaction).await?;
+			server
+		}
+		None => fetch_by_previous_key(refresh_key, &plugin_version, &mut transaction)
+			.await?
+			.ok_or_else(|| Error::unauthorized())?,
+	};
+
+	if let Err(error) =
+		plugin::ensure_not_yanked(server.plugin_version_id(), &mut transaction).await
+	{
+		tracing::warn! {
+			target: "cs2kz_api::audit_log",
+			server_id = %server.id(),
+			plugin_version_id = %server.plugin_version_id(),
+			"server tried to authenticate with yanked plugin version",
+		};
+
+		return Err(error);
+	}
 
 	let jwt = Jwt::new(&server, Duration::from_secs(60 * 15));
 	let access_key = state.encode_jwt(jwt)?;
@@ -62,33 +91,150 @@ pub async fn generate_temp(
 
 	tracing::debug! {
 		server_id = %server.id(),
-		%access_key,
 		"generated access key for server",
 	};
 
 	Ok(Created(Json(AccessKeyResponse { access_key })))
 }
 
+/// Looks up a server by the API key it used before its key was rotated.
+///
+/// Only keys whose grace period has not expired yet are considered.
+async fn fetch_by_previous_key(
+	refresh_key: Uuid,
+	plugin_version: &semver::Version,
+	transaction: &mut Transaction<'_, MySql>,
+) -> Result<Option<authentication::Server>> {
+	let server = sqlx::query! {
+		r#"
+		SELECT
+		  s.id `server_id: ServerID`,
+		  v.id `plugin_version_id: PluginVersionID`
+		FROM
+		  Servers s
+		  JOIN PluginVersions v ON v.semver = ?
+		  AND s.previous_refresh_key = ?
+		WHERE
+		  s.previous_refresh_key_expires_on > NOW()
+		"#,
+		plugin_version.to_string(),
+		refresh_key,
+	}
+	.fetch_optional(transaction.as_mut())
+	.await?
+	.map(|row| authentication::Server::new(row.server_id, row.plugin_version_id));
+
+	Ok(server)
+}
+
+/// Revokes a server's previous API key, if it still has one.
+async fn revoke_previous_key(
+	server_id: ServerID,
+	transaction: &mut Transaction<'_, MySql>,
+) -> Result<()> {
+	let query_result = sqlx::query! {
+		r#"
+		UPDATE
+		  Servers
+		SET
+		  previous_refresh_key = NULL,
+		  previous_refresh_key_expires_on = NULL
+		WHERE
+		  id = ?
+		  AND previous_refresh_key IS NOT NULL
+		"#,
+		server_id,
+	}
+	.execute(transaction.as_mut())
+	.await?;
+
+	if query_result.rows_affected() > 0 {
+		tracing::info! {
+			target: "cs2kz_api::audit_log",
+			%server_id,
+			"revoked previous API key for server",
+		};
+	}
+
+	Ok(())
+}
+
+/// The longest [grace period] an old API key can be kept valid for.
+///
+/// [grace period]: PutPermaParams::grace_period
+const MAX_GRACE_PERIOD: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// Query parameters for `PUT /servers/{server_id}/key`.
+#[derive(Debug, Clone, Copy, Deserialize, IntoParams)]
+pub struct PutPermaParams {
+	/// How long the old key should remain valid, in seconds.
+	///
+	/// The old key is revoked early as soon as the server uses the new one. If this is omitted,
+	/// the old key is revoked immediately. The maximum is 24 hours.
+	#[param(value_type = Option<f64>)]
+	grace_period: Option<Seconds>,
+}
+
 /// Generate a new API key for a server, invalidating the old one.
+///
+/// If a `grace_period` is specified, the old key will remain valid until either it expires, or
+/// the server starts using the new key. This allows rotating keys without any downtime.
+///
+/// Requires a valid CSRF token in the `X-CSRF-Token` header.
 #[tracing::instrument(skip(state))]
 #[utoipa::path(
   put,
   path = "/servers/{server_id}/key",
   tag = "Servers",
   security(("Browser Session" = ["servers"])),
-  params(("server_id" = u16, Path, description = "The server's ID")),
+  params(
+    ("server_id" = u16, Path, description = "The server's ID"),
+    PutPermaParams,
+  ),
   responses(
     responses::NoContent,
     responses::BadRequest,
     responses::Unauthorized,
+    responses::Forbidden,
   ),
 )]
 pub async fn put_perma(
 	state: State,
 	session: authentication::Session<authorization::IsServerAdminOrOwner>,
+	_csrf: CsrfToken,
 	Path(server_id): Path<ServerID>,
+	Query(PutPermaParams { grace_period }): Query<PutPermaParams>,
 ) -> Result<Created<Json<RefreshKey>>> {
+	if grace_period.is_some_and(|grace_period| *grace_period > MAX_GRACE_PERIOD) {
+		return Err(Error::invalid("grace_period").context(format!(
+			"must not be longer than {} seconds",
+			MAX_GRACE_PERIOD.as_secs(),
+		)));
+	}
+
 	let mut transaction = state.transaction().await?;
+
+	match grace_period {
+		Some(grace_period) => {
+			sqlx::query! {
+				r#"
+				UPDATE
+				  Servers
+				SET
+				  previous_refresh_key = refresh_key,
+				  previous_refresh_key_expires_on = NOW() + INTERVAL ? SECOND
+				WHERE
+				  id = ?
+				"#,
+				grace_period.as_secs(),
+				server_id,
+			}
+			.execute(transaction.as_mut())
+			.await?;
+		}
+		None => revoke_previous_key(server_id, &mut transaction).await?,
+	}
+
 	let refresh_key = Uuid::new_v4();
 	let query_result = sqlx::query! {
 		r#"
@@ -115,7 +261,7 @@ pub async fn put_perma(
 	tracing::info! {
 		target: "cs2kz_api::audit_log",
 		%server_id,
-		%refresh_key,
+		?grace_period,
 		"generated new API key for server",
 	};
 
@@ -123,6 +269,8 @@ pub async fn put_perma(
 }
 
 /// Delete a server's API key, preventing them from generating new JWTs.
+///
+/// Requires a valid CSRF token in the `X-CSRF-Token` header.
 #[tracing::instrument(skip(state))]
 #[utoipa::path(
   delete,
@@ -134,6 +282,7 @@ pub async fn put_perma(
     responses::NoContent,
     responses::BadRequest,
     responses::Unauthorized,
+    responses::Forbidden,
   ),
 )]
 pub async fn delete_perma(
@@ -141,6 +290,7 @@ pub async fn delete_perma(
 	session: authentication::Session<
 		authorization::HasPermissions<{ Permissions::SERVERS.value() }>,
 	>,
+	_csrf: CsrfToken,
 	Path(server_id): Path<ServerID>,
 ) -> Result<NoContent> {
 	let mut transaction = state.transaction().await?;
@@ -164,6 +314,8 @@ pub async fn delete_perma(
 		n => assert_eq!(n, 1, "updated more than 1 server"),
 	}
 
+	revoke_previous_key(server_id, &mut transaction).await?;
+
 	transaction.commit().await?;
 
 	tracing::info!(target: "cs2kz_api::audit_log", %server_id, "deleted API key for server");
@@ -178,7 +330,7 @@ mod tests {
 	use reqwest::header;
 	use uuid::Uuid;
 
-	use crate::authentication;
+	use crate::authentication::{self, csrf, CsrfToken};
 	use crate::plugin::PluginVersionID;
 	use crate::servers::{AccessKeyRequest, AccessKeyResponse, RefreshKey, ServerID};
 
@@ -224,6 +376,41 @@ mod tests {
 		assert_eq!(server_info.plugin_version_id(), server.plugin_version_id);
 	}
 
+	#[crate::integration_test(fixtures = ["yanked-plugin-version"])]
+	async fn generate_temp_with_yanked_version(ctx: &Context) {
+		let server = sqlx::query! {
+			r#"
+			SELECT
+			  s.refresh_key `refresh_key!: uuid::fmt::Hyphenated`,
+			  v.semver
+			FROM
+			  Servers s
+			  JOIN PluginVersions v
+			WHERE
+			  s.id = 1
+			  AND v.yanked_on IS NOT NULL
+			LIMIT
+			  1
+			"#,
+		}
+		.fetch_one(&ctx.database)
+		.await?;
+
+		let refresh_key = AccessKeyRequest {
+			refresh_key: server.refresh_key.into(),
+			plugin_version: server.semver.parse()?,
+		};
+
+		let response = ctx
+			.http_client
+			.post(ctx.url("/servers/key"))
+			.json(&refresh_key)
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 403);
+	}
+
 	#[crate::integration_test(fixtures = ["alphakeks-server-role"])]
 	async fn put_perma(ctx: &Context) {
 		let server = sqlx::query! {
@@ -249,12 +436,20 @@ mod tests {
 
 		let alphakeks = SteamID::from_u64(76561198282622073_u64).unwrap();
 		let session = ctx.auth_session(alphakeks).await?;
-		let session_cookie = Cookie::from(session).encoded().to_string();
+		let csrf_cookie = CsrfToken::create(session.id(), &ctx.database)
+			.await?
+			.to_cookie(&ctx.api_config);
+		let cookies = format!(
+			"{}; {}",
+			Cookie::from(session).encoded(),
+			csrf_cookie.encoded(),
+		);
 
 		let response = ctx
 			.http_client
 			.put(ctx.url("/servers/1/key"))
-			.header(header::COOKIE, session_cookie)
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
 			.send()
 			.await?;
 
@@ -280,6 +475,107 @@ mod tests {
 		assert_eq!(server.refresh_key, refresh_key.hyphenated());
 	}
 
+	#[crate::integration_test(fixtures = ["alphakeks-server-role"])]
+	async fn put_perma_with_grace_period(ctx: &Context) {
+		let server = sqlx::query! {
+			r#"
+			SELECT
+			  s.refresh_key `refresh_key!: uuid::fmt::Hyphenated`,
+			  v.semver
+			FROM
+			  Servers s
+			  JOIN PluginVersions v
+			WHERE
+			  s.id = 1
+			LIMIT
+			  1
+			"#,
+		}
+		.fetch_one(&ctx.database)
+		.await?;
+
+		let alphakeks = SteamID::from_u64(76561198282622073_u64).unwrap();
+		let session = ctx.auth_session(alphakeks).await?;
+		let csrf_cookie = CsrfToken::create(session.id(), &ctx.database)
+			.await?
+			.to_cookie(&ctx.api_config);
+		let cookies = format!(
+			"{}; {}",
+			Cookie::from(session).encoded(),
+			csrf_cookie.encoded(),
+		);
+
+		let response = ctx
+			.http_client
+			.put(ctx.url("/servers/1/key"))
+			.query(&[("grace_period", "60")])
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 201);
+
+		let RefreshKey {
+			refresh_key: new_key,
+		} = response.json().await?;
+
+		let old_key = AccessKeyRequest {
+			refresh_key: server.refresh_key.into(),
+			plugin_version: server.semver.parse()?,
+		};
+
+		let new_key = AccessKeyRequest {
+			refresh_key: new_key,
+			plugin_version: server.semver.parse()?,
+		};
+
+		for (key, expected_status) in [(&old_key, 201), (&new_key, 201), (&old_key, 401)] {
+			let response = ctx
+				.http_client
+				.post(ctx.url("/servers/key"))
+				.json(key)
+				.send()
+				.await?;
+
+			assert_eq!(response.status(), expected_status);
+		}
+	}
+
+	#[crate::integration_test(fixtures = ["alphakeks-server-role"])]
+	async fn put_perma_with_long_grace_period(ctx: &Context) {
+		let get_key = || {
+			sqlx::query_scalar! {
+				"SELECT refresh_key `refresh_key!: uuid::fmt::Hyphenated` FROM Servers WHERE id = 1",
+			}
+			.fetch_one(&ctx.database)
+		};
+
+		let old_key = get_key().await?;
+		let alphakeks = SteamID::from_u64(76561198282622073_u64).unwrap();
+		let session = ctx.auth_session(alphakeks).await?;
+		let csrf_cookie = CsrfToken::create(session.id(), &ctx.database)
+			.await?
+			.to_cookie(&ctx.api_config);
+		let cookies = format!(
+			"{}; {}",
+			Cookie::from(session).encoded(),
+			csrf_cookie.encoded(),
+		);
+
+		let response = ctx
+			.http_client
+			.put(ctx.url("/servers/1/key"))
+			.query(&[("grace_period", "86401")])
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 400);
+		assert_eq!(get_key().await?, old_key);
+	}
+
 	#[crate::integration_test(fixtures = ["alphakeks-server-role"])]
 	async fn delete_perma(ctx: &Context) {
 		let server = sqlx::query! {
@@ -307,12 +603,20 @@ mod tests {
 
 		let alphakeks = SteamID::from_u64(76561198282622073_u64).unwrap();
 		let session = ctx.auth_session(alphakeks).await?;
-		let session_cookie = Cookie::from(session).encoded().to_string();
+		let csrf_cookie = CsrfToken::create(session.id(), &ctx.database)
+			.await?
+			.to_cookie(&ctx.api_config);
+		let cookies = format!(
+			"{}; {}",
+			Cookie::from(session).encoded(),
+			csrf_cookie.encoded(),
+		);
 
 		let response = ctx
 			.http_client
 			.delete(ctx.url("/servers/1/key"))
-			.header(header::COOKIE, session_cookie)
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
 			.send()
 			.await?;
 
@@ -3,18 +3,21 @@
 use axum::extract::Query;
 use axum::Json;
 use chrono::{DateTime, Utc};
-use cs2kz::PlayerIdentifier;
+use cs2kz::{PlayerIdentifier, SteamID};
 use serde::Deserialize;
+use sqlx::{MySql, Transaction};
 use utoipa::IntoParams;
 use uuid::Uuid;
 
+use crate::authentication::CsrfToken;
 use crate::authorization::{self, Permissions};
 use crate::make_id::IntoID;
 use crate::openapi::parameters::{Limit, Offset};
 use crate::openapi::responses;
 use crate::openapi::responses::{Created, PaginationResponse};
 use crate::servers::{queries, CreatedServer, NewServer, Server, ServerID};
-use crate::sqlx::{query, FetchID, FilteredQuery, QueryBuilderExt, SqlErrorExt};
+use crate::sqlx::{query, FetchID, FilteredQuery, QueryBuilderExt, SqlErrorExt, WithTotal};
+use crate::time::TimeRange;
 use crate::{authentication, Error, Result, State};
 
 /// Query parameters for `/servers`.
@@ -38,6 +41,13 @@ pub struct GetParams {
 	/// Only include servers approved before this date.
 	created_before: Option<DateTime<Utc>>,
 
+	/// Only include servers approved within this range.
+	///
+	/// This is an alternative to `created_after` / `created_before`, e.g.
+	/// `2024-01-01..2024-02-01`.
+	#[param(value_type = Option<String>)]
+	range: Option<TimeRange>,
+
 	/// Maximum number of results to return.
 	#[serde(default)]
 	limit: Limit,
@@ -68,11 +78,12 @@ pub async fn get(
 		owned_by,
 		created_after,
 		created_before,
+		range,
 		limit,
 		offset,
 	}): Query<GetParams>,
 ) -> Result<Json<PaginationResponse<Server>>> {
-	let mut query = FilteredQuery::new(queries::SELECT);
+	let mut query = FilteredQuery::new(query::select_with_total(queries::SELECT));
 	let mut transaction = state.transaction().await?;
 
 	if let Some(name) = name {
@@ -84,41 +95,40 @@ pub async fn get(
 	}
 
 	if let Some(player) = owned_by {
-		let steam_id = player.fetch_id(transaction.as_mut()).await?;
+		let steam_id = player.fetch_id_unchecked(transaction.as_mut()).await?;
 
 		query.filter(" s.owner_id = ", steam_id);
 	}
 
-	if let Some(created_after) = created_after {
-		query.filter(" s.created_on > ", created_after);
-	}
-
-	if let Some(created_before) = created_before {
-		query.filter(" s.created_on < ", created_before);
-	}
+	query.filter_range(
+		"s.created_on",
+		TimeRange::between(created_after, created_before).intersect(range.unwrap_or_default()),
+	);
 
 	query.push_limits(limit, offset);
 
-	let servers = query
-		.build_query_as::<Server>()
+	let (total, servers) = query
+		.build_query_as::<WithTotal<Server>>()
 		.fetch_all(transaction.as_mut())
-		.await?;
+		.await
+		.map(WithTotal::split)?;
 
 	if servers.is_empty() {
 		return Err(Error::no_content());
 	}
 
-	let total = query::total_rows(&mut transaction).await?;
-
 	transaction.commit().await?;
 
 	Ok(Json(PaginationResponse {
 		total,
 		results: servers,
+		next_cursor: None,
 	}))
 }
 
 /// Create a new server.
+///
+/// Requires a valid CSRF token in the `X-CSRF-Token` header.
 #[tracing::instrument(skip(state))]
 #[utoipa::path(
   post,
@@ -130,6 +140,8 @@ pub async fn get(
     responses::NoContent,
     responses::BadRequest,
     responses::Unauthorized,
+    responses::Forbidden,
+    responses::Conflict,
     responses::UnprocessableEntity,
   ),
 )]
@@ -138,6 +150,7 @@ pub async fn post(
 	session: authentication::Session<
 		authorization::HasPermissions<{ Permissions::SERVERS.value() }>,
 	>,
+	_csrf: CsrfToken,
 	Json(NewServer {
 		name,
 		host,
@@ -146,6 +159,14 @@ pub async fn post(
 	}): Json<NewServer>,
 ) -> Result<Created<Json<CreatedServer>>> {
 	let mut transaction = state.transaction().await?;
+
+	check_server_budget(
+		owned_by,
+		state.config.default_server_budget,
+		&mut transaction,
+	)
+	.await?;
+
 	let refresh_key = Uuid::new_v4();
 	let server_id = sqlx::query! {
 		r#"
@@ -177,7 +198,6 @@ pub async fn post(
 	tracing::debug! {
 		target: "cs2kz_api::audit_log",
 		id = %server_id,
-		%refresh_key,
 		"created new server",
 	};
 
@@ -187,6 +207,45 @@ pub async fn post(
 	})))
 }
 
+/// Makes sure the given server owner has not exhausted their server budget yet.
+///
+/// Players without an explicit budget get `default_budget`.
+async fn check_server_budget(
+	owner_id: SteamID,
+	default_budget: u16,
+	transaction: &mut Transaction<'_, MySql>,
+) -> Result<()> {
+	// lock the owner's row so concurrent requests can't both squeeze in the last server
+	let budget = sqlx::query_scalar!(
+		"SELECT server_budget FROM Players WHERE id = ? FOR UPDATE",
+		owner_id
+	)
+	.fetch_optional(transaction.as_mut())
+	.await?
+	.ok_or_else(|| Error::not_found("server owner"))?
+	.unwrap_or(default_budget);
+
+	let current = sqlx::query_scalar! {
+		r#"
+		SELECT
+		  CAST(COUNT(id) AS UNSIGNED) `servers!: u64`
+		FROM
+		  Servers
+		WHERE
+		  owner_id = ?
+		"#,
+		owner_id,
+	}
+	.fetch_one(transaction.as_mut())
+	.await?;
+
+	if current >= u64::from(budget) {
+		return Err(Error::server_budget_exceeded(budget, current));
+	}
+
+	Ok(())
+}
+
 #[cfg(test)]
 mod tests {
 	use std::net::Ipv6Addr;
@@ -195,6 +254,7 @@ mod tests {
 	use cs2kz::SteamID;
 	use reqwest::header;
 
+	use crate::authentication::{csrf, CsrfToken};
 	use crate::openapi::responses::PaginationResponse;
 	use crate::servers::{CreatedServer, NewServer, Server};
 
@@ -214,6 +274,24 @@ mod tests {
 		assert!(response.results.len() <= 7);
 	}
 
+	#[crate::integration_test]
+	async fn servers_match_schema(ctx: &Context) {
+		let response = ctx.http_client.get(ctx.url("/servers")).send().await?;
+
+		assert_eq!(response.status(), 200);
+
+		let response = response.json::<serde_json::Value>().await?;
+		let servers = response["results"]
+			.as_array()
+			.context("`results` should be an array")?;
+
+		assert!(!servers.is_empty());
+
+		for server in servers {
+			crate::test::validate_schema::<Server>(server)?;
+		}
+	}
+
 	#[crate::integration_test(fixtures = ["alphakeks-server-role"])]
 	async fn approve_server(ctx: &Context) {
 		let alphakeks = SteamID::from_u64(76561198282622073_u64).unwrap();
@@ -234,12 +312,20 @@ mod tests {
 		assert_eq!(response.status(), 401);
 
 		let session = ctx.auth_session(alphakeks).await?;
-		let session_cookie = Cookie::from(session).encoded().to_string();
+		let csrf_cookie = CsrfToken::create(session.id(), &ctx.database)
+			.await?
+			.to_cookie(&ctx.api_config);
+		let cookies = format!(
+			"{}; {}",
+			Cookie::from(session).encoded(),
+			csrf_cookie.encoded(),
+		);
 
 		let response = ctx
 			.http_client
 			.post(ctx.url("/servers"))
-			.header(header::COOKIE, session_cookie)
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
 			.json(&server)
 			.send()
 			.await?;
@@ -261,4 +347,36 @@ mod tests {
 		assert_eq!(server.name, "very cool server");
 		assert_eq!(server.owner.steam_id, alphakeks);
 	}
+
+	#[crate::integration_test(fixtures = ["alphakeks-server-role", "alphakeks-server-budget"])]
+	async fn server_budget(ctx: &Context) {
+		let alphakeks = SteamID::from_u64(76561198282622073_u64).unwrap();
+		let server = NewServer {
+			name: String::from("one server too many"),
+			host: url::Host::Ipv6(Ipv6Addr::UNSPECIFIED),
+			port: 420,
+			owned_by: alphakeks,
+		};
+
+		let session = ctx.auth_session(alphakeks).await?;
+		let csrf_cookie = CsrfToken::create(session.id(), &ctx.database)
+			.await?
+			.to_cookie(&ctx.api_config);
+		let cookies = format!(
+			"{}; {}",
+			Cookie::from(session).encoded(),
+			csrf_cookie.encoded(),
+		);
+
+		let response = ctx
+			.http_client
+			.post(ctx.url("/servers"))
+			.header(header::COOKIE, &cookies)
+			.header(csrf::HEADER_NAME, csrf_cookie.value())
+			.json(&server)
+			.send()
+			.await?;
+
+		assert_eq!(response.status(), 409);
+	}
 }
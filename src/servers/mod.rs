@@ -5,7 +5,7 @@ use axum::{routing, Router};
 
 use crate::authorization::Permissions;
 use crate::middleware::auth::session_auth;
-use crate::middleware::cors;
+use crate::middleware::{cors, etag};
 use crate::{authorization, State};
 
 mod models;
@@ -28,6 +28,7 @@ pub fn router(state: State) -> Router {
 
 	let root = Router::new()
 		.route("/", routing::get(handlers::root::get))
+		.route_layer(etag::layer!())
 		.route_layer(cors::permissive())
 		.route(
 			"/",
@@ -92,6 +92,7 @@ pub struct CreatedServer {
 	pub server_id: ServerID,
 
 	/// The server's API key.
+	#[debug("*****")]
 	pub refresh_key: Uuid,
 }
 
@@ -116,6 +117,7 @@ pub struct ServerUpdate {
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct AccessKeyRequest {
 	/// The server's API key.
+	#[debug("*****")]
 	pub refresh_key: Uuid,
 
 	/// The server's CS2KZ plugin version.
@@ -127,6 +129,7 @@ pub struct AccessKeyRequest {
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct AccessKeyResponse {
 	/// The JWT.
+	#[debug("*****")]
 	pub access_key: String,
 }
 
@@ -134,6 +137,7 @@ pub struct AccessKeyResponse {
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
 pub struct RefreshKey {
 	/// The key.
+	#[debug("*****")]
 	pub refresh_key: Uuid,
 }
 
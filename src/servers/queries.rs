@@ -2,7 +2,7 @@
 
 /// SQL query for `SELECT`ing servers from the database.
 pub static SELECT: &str = r#"
-	SELECT SQL_CALC_FOUND_ROWS
+	SELECT
 	  s.id,
 	  s.name,
 	  s.host,
@@ -0,0 +1,101 @@
+//! Cursor-based ("keyset") pagination.
+//!
+//! `LIMIT` / `OFFSET` pagination forces the database to scan every skipped row, which gets slow
+//! on deep pages. It can also skip or duplicate rows if new rows are inserted between requests.
+//!
+//! A [`Cursor`] instead remembers the sort key and ID of the last row that was returned, and the
+//! next page starts right after that row (see [`FilteredQuery::filter_cursor()`]).
+//! To clients, cursors are opaque base64 strings.
+//!
+//! [`FilteredQuery::filter_cursor()`]: crate::sqlx::FilteredQuery::filter_cursor
+
+use std::fmt;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::de::{self, DeserializeOwned};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A pagination cursor.
+///
+/// `K` is the type of the column the results are sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor<K> {
+	/// The sort key of the last returned row.
+	key: K,
+
+	/// The ID of the last returned row.
+	///
+	/// This acts as a tie breaker for rows with the same sort key.
+	id: u64,
+}
+
+impl<K> Cursor<K> {
+	/// Creates a new [`Cursor`] pointing at the row with the given sort `key` and `id`.
+	pub const fn new(key: K, id: u64) -> Self {
+		Self { key, id }
+	}
+
+	/// Returns the sort key of the row this cursor is pointing at.
+	pub const fn key(&self) -> &K {
+		&self.key
+	}
+
+	/// Returns the ID of the row this cursor is pointing at.
+	pub const fn id(&self) -> u64 {
+		self.id
+	}
+}
+
+impl<K> Cursor<K>
+where
+	K: Serialize,
+{
+	/// Encodes this cursor into an opaque string that can be returned to clients.
+	pub fn encode(&self) -> String {
+		let json = serde_json::to_vec(&(&self.key, self.id)).expect("cursor is valid json");
+
+		URL_SAFE_NO_PAD.encode(json)
+	}
+}
+
+impl<K> fmt::Display for Cursor<K>
+where
+	K: Serialize,
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.encode())
+	}
+}
+
+impl<K> Serialize for Cursor<K>
+where
+	K: Serialize,
+{
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		self.encode().serialize(serializer)
+	}
+}
+
+impl<'de, K> Deserialize<'de> for Cursor<K>
+where
+	K: DeserializeOwned,
+{
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		let encoded = String::deserialize(deserializer)?;
+		let json = URL_SAFE_NO_PAD
+			.decode(encoded)
+			.map_err(|_| de::Error::custom("invalid cursor"))?;
+
+		let (key, id) = serde_json::from_slice::<(K, u64)>(&json)
+			.map_err(|_| de::Error::custom("invalid cursor"))?;
+
+		Ok(Self { key, id })
+	}
+}
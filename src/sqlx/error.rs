@@ -1,5 +1,17 @@
 //! Helpers for dealing with SQL errors.
 
+use sqlx::mysql::MySqlDatabaseError;
+
+/// MySQL error code for deadlocks.
+///
+/// See <https://mariadb.com/kb/en/mariadb-error-code-reference/>.
+const ER_LOCK_DEADLOCK: u16 = 1213;
+
+/// MySQL error code for lock wait timeouts.
+///
+/// See <https://mariadb.com/kb/en/mariadb-error-code-reference/>.
+const ER_LOCK_WAIT_TIMEOUT: u16 = 1205;
+
 /// Extension trait for [`sqlx::Error`].
 pub trait SqlErrorExt {
 	/// Checks if the error is a "duplicate entry" error.
@@ -7,6 +19,14 @@ pub trait SqlErrorExt {
 
 	/// Checks if the error is a foreign key violation of the given `fk`.
 	fn is_fk_violation_of(&self, fk: &str) -> bool;
+
+	/// Checks if the error is a deadlock.
+	///
+	/// The transaction that caused this error has been rolled back, and can be retried.
+	fn is_deadlock(&self) -> bool;
+
+	/// Checks if the error is a lock wait timeout.
+	fn is_lock_wait_timeout(&self) -> bool;
 }
 
 impl SqlErrorExt for sqlx::Error {
@@ -19,4 +39,20 @@ impl SqlErrorExt for sqlx::Error {
 		self.as_database_error()
 			.is_some_and(|err| err.is_foreign_key_violation() && err.message().contains(fk))
 	}
+
+	fn is_deadlock(&self) -> bool {
+		mysql_error_number(self) == Some(ER_LOCK_DEADLOCK)
+	}
+
+	fn is_lock_wait_timeout(&self) -> bool {
+		mysql_error_number(self) == Some(ER_LOCK_WAIT_TIMEOUT)
+	}
+}
+
+/// Extracts the MySQL error number from an error, if it is a database error.
+fn mysql_error_number(error: &sqlx::Error) -> Option<u16> {
+	error
+		.as_database_error()?
+		.try_downcast_ref::<MySqlDatabaseError>()
+		.map(MySqlDatabaseError::number)
 }
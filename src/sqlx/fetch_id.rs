@@ -1,48 +1,109 @@
 //! Extension trait to fetch IDs from the database.
+//!
+//! All the "ID or name" types resolve the same way:
+//!
+//!    - IDs are looked up by primary key. If there is no row with that ID, but one whose name is
+//!      exactly the ID (e.g. a server called "1337"), that row is used instead, so
+//!      numeric-looking names stay reachable.
+//!    - Names are matched partially. If several rows match, an exact match wins, then the
+//!      shortest name, then the lowest ID, so ambiguous names always resolve to the same row.
+//!
+//! Filters on listing endpoints (e.g. `GET /records?player=...`) use
+//! [`FetchID::fetch_id_unchecked()`] instead, which takes IDs as-is without a lookup. Filtering
+//! by an ID that doesn't exist simply doesn't match anything.
 
 use std::future::Future;
 
 use cs2kz::{CourseIdentifier, MapIdentifier, PlayerIdentifier, ServerIdentifier, SteamID};
-use sqlx::MySqlExecutor;
+use sqlx::{Acquire, Decode, MySql, QueryBuilder, Type};
 
 use crate::maps::{CourseID, MapID};
 use crate::servers::ServerID;
+use crate::sqlx::query;
 use crate::{Error, Result};
 
 /// An extension trait for "ID or name" types, that will fetch an ID from the database by looking
 /// up a name, if necessary.
-pub trait FetchID {
+///
+/// See the [module level docs] for how identifiers are resolved.
+///
+/// [module level docs]: crate::sqlx::fetch_id
+pub trait FetchID: Sync {
 	/// The ID type.
-	type ID;
+	type ID: Send;
 
-	/// Fetches an ID from the database if necessary.
+	/// What the identifier refers to, used in error messages.
+	const WHAT: &'static str;
+
+	/// Returns the ID, if the identifier is one.
+	fn as_id(&self) -> Option<Self::ID>;
+
+	/// Resolves the identifier to an ID.
+	///
+	/// Returns `None` if there is no matching row.
+	#[allow(single_use_lifetimes)]
+	fn resolve<'c>(
+		&self,
+		connection: impl Acquire<'c, Database = MySql> + Send,
+	) -> impl Future<Output = Result<Option<Self::ID>>> + Send;
+
+	/// Resolves the identifier to an ID, returning a "not found" error if there is no matching
+	/// row.
 	#[allow(single_use_lifetimes)]
 	fn fetch_id<'c>(
 		&self,
-		executor: impl MySqlExecutor<'c>,
-	) -> impl Future<Output = Result<Self::ID>> + Send;
+		connection: impl Acquire<'c, Database = MySql> + Send,
+	) -> impl Future<Output = Result<Self::ID>> + Send {
+		async move {
+			self.resolve(connection)
+				.await?
+				.ok_or_else(|| Error::not_found(Self::WHAT))
+		}
+	}
+
+	/// Like [`FetchID::fetch_id()`], but IDs are returned as-is without checking whether they
+	/// exist.
+	///
+	/// Only names cause a database lookup.
+	#[allow(single_use_lifetimes)]
+	fn fetch_id_unchecked<'c>(
+		&self,
+		connection: impl Acquire<'c, Database = MySql> + Send,
+	) -> impl Future<Output = Result<Self::ID>> + Send {
+		async move {
+			match self.as_id() {
+				Some(id) => Ok(id),
+				None => self.fetch_id(connection).await,
+			}
+		}
+	}
 }
 
 impl FetchID for PlayerIdentifier {
 	type ID = SteamID;
 
-	async fn fetch_id(&self, executor: impl MySqlExecutor<'_>) -> Result<SteamID> {
+	const WHAT: &'static str = "player";
+
+	fn as_id(&self) -> Option<SteamID> {
+		match *self {
+			Self::SteamID(steam_id) => Some(steam_id),
+			Self::Name(_) => None,
+		}
+	}
+
+	async fn resolve(
+		&self,
+		connection: impl Acquire<'_, Database = MySql> + Send,
+	) -> Result<Option<SteamID>> {
 		match *self {
-			Self::SteamID(steam_id) => Ok(steam_id),
-			Self::Name(ref name) => sqlx::query_scalar! {
-				r#"
-				SELECT
-				  id `steam_id: SteamID`
-				FROM
-				  Players
-				WHERE
-				  name LIKE ?
-				"#,
-				format!("%{name}%"),
+			// SteamIDs can be parsed from plain 32 and 64 bit integers, so we don't know which one
+			// the user actually typed.
+			Self::SteamID(steam_id) => {
+				let names = [steam_id.as_u64().to_string(), steam_id.as_u32().to_string()];
+
+				resolve_by_id("Players", steam_id.as_u64(), &names, connection).await
 			}
-			.fetch_optional(executor)
-			.await?
-			.ok_or_else(|| Error::not_found("player")),
+			Self::Name(ref name) => resolve_by_name("Players", name, connection).await,
 		}
 	}
 }
@@ -50,23 +111,22 @@ impl FetchID for PlayerIdentifier {
 impl FetchID for MapIdentifier {
 	type ID = MapID;
 
-	async fn fetch_id(&self, executor: impl MySqlExecutor<'_>) -> Result<MapID> {
+	const WHAT: &'static str = "map";
+
+	fn as_id(&self) -> Option<MapID> {
 		match *self {
-			Self::ID(id) => Ok(MapID(id)),
-			Self::Name(ref name) => sqlx::query_scalar! {
-				r#"
-				SELECT
-				  id `id: MapID`
-				FROM
-				  Maps
-				WHERE
-				  name LIKE ?
-				"#,
-				format!("%{name}%"),
-			}
-			.fetch_optional(executor)
-			.await?
-			.ok_or_else(|| Error::not_found("map")),
+			Self::ID(id) => Some(MapID(id)),
+			Self::Name(_) => None,
+		}
+	}
+
+	async fn resolve(
+		&self,
+		connection: impl Acquire<'_, Database = MySql> + Send,
+	) -> Result<Option<MapID>> {
+		match *self {
+			Self::ID(id) => resolve_by_id("Maps", id.into(), &[id.to_string()], connection).await,
+			Self::Name(ref name) => resolve_by_name("Maps", name, connection).await,
 		}
 	}
 }
@@ -74,23 +134,24 @@ impl FetchID for MapIdentifier {
 impl FetchID for CourseIdentifier {
 	type ID = CourseID;
 
-	async fn fetch_id(&self, executor: impl MySqlExecutor<'_>) -> Result<CourseID> {
+	const WHAT: &'static str = "course";
+
+	fn as_id(&self) -> Option<CourseID> {
 		match *self {
-			CourseIdentifier::ID(course_id) => Ok(CourseID(course_id)),
-			CourseIdentifier::Name(ref name) => sqlx::query_scalar! {
-				r#"
-				SELECT
-				  id `id: CourseID`
-				FROM
-				  Courses
-				WHERE
-				  name LIKE ?
-				"#,
-				format!("%{name}%"),
+			Self::ID(id) => Some(CourseID(id)),
+			Self::Name(_) => None,
+		}
+	}
+
+	async fn resolve(
+		&self,
+		connection: impl Acquire<'_, Database = MySql> + Send,
+	) -> Result<Option<CourseID>> {
+		match *self {
+			Self::ID(id) => {
+				resolve_by_id("Courses", id.into(), &[id.to_string()], connection).await
 			}
-			.fetch_optional(executor)
-			.await?
-			.ok_or_else(|| Error::not_found("course")),
+			Self::Name(ref name) => resolve_by_name("Courses", name, connection).await,
 		}
 	}
 }
@@ -98,23 +159,164 @@ impl FetchID for CourseIdentifier {
 impl FetchID for ServerIdentifier {
 	type ID = ServerID;
 
-	async fn fetch_id(&self, executor: impl MySqlExecutor<'_>) -> Result<ServerID> {
+	const WHAT: &'static str = "server";
+
+	fn as_id(&self) -> Option<ServerID> {
 		match *self {
-			Self::ID(id) => Ok(ServerID(id)),
-			Self::Name(ref name) => sqlx::query_scalar! {
-				r#"
-				SELECT
-				  id `id: ServerID`
-				FROM
-				  Servers
-				WHERE
-				  name LIKE ?
-				"#,
-				format!("%{name}%"),
+			Self::ID(id) => Some(ServerID(id)),
+			Self::Name(_) => None,
+		}
+	}
+
+	async fn resolve(
+		&self,
+		connection: impl Acquire<'_, Database = MySql> + Send,
+	) -> Result<Option<ServerID>> {
+		match *self {
+			Self::ID(id) => {
+				resolve_by_id("Servers", id.into(), &[id.to_string()], connection).await
 			}
-			.fetch_optional(executor)
-			.await?
-			.ok_or_else(|| Error::not_found("server")),
+			Self::Name(ref name) => resolve_by_name("Servers", name, connection).await,
+		}
+	}
+}
+
+/// Looks up `id` in `table`, falling back to a row whose name is exactly one of `names`.
+///
+/// The name lookup can't use an index, so it only happens if there is no row with that ID.
+async fn resolve_by_id<ID>(
+	table: &str,
+	id: u64,
+	names: &[String],
+	connection: impl Acquire<'_, Database = MySql> + Send,
+) -> Result<Option<ID>>
+where
+	ID: for<'r> Decode<'r, MySql> + Type<MySql> + Send + Unpin,
+{
+	let mut connection = connection.acquire().await?;
+	let by_id = sqlx::query_scalar::<_, ID>(&format!("SELECT id FROM {table} WHERE id = ?"))
+		.bind(id)
+		.fetch_optional(&mut *connection)
+		.await?;
+
+	if by_id.is_some() {
+		return Ok(by_id);
+	}
+
+	let mut query = QueryBuilder::new(format!("SELECT id FROM {table} WHERE name IN ("));
+	let mut separated = query.separated(", ");
+
+	for name in names {
+		separated.push_bind(name);
+	}
+
+	query.push(") ORDER BY id ASC LIMIT 1");
+
+	query
+		.build_query_scalar::<ID>()
+		.fetch_optional(&mut *connection)
+		.await
+		.map_err(Error::from)
+}
+
+/// Looks up the row in `table` whose name best matches `name`.
+///
+/// `name` is matched literally, i.e. `%` and `_` are not treated as wildcards.
+async fn resolve_by_name<ID>(
+	table: &str,
+	name: &str,
+	connection: impl Acquire<'_, Database = MySql> + Send,
+) -> Result<Option<ID>>
+where
+	ID: for<'r> Decode<'r, MySql> + Type<MySql> + Send + Unpin,
+{
+	let mut connection = connection.acquire().await?;
+
+	sqlx::query_scalar::<_, ID>(&format!(
+		r#"
+		SELECT
+		  id
+		FROM
+		  {table}
+		WHERE
+		  name LIKE ? ESCAPE '\\'
+		ORDER BY
+		  name = ? DESC,
+		  CHAR_LENGTH(name) ASC,
+		  id ASC
+		LIMIT
+		  1
+		"#
+	))
+	.bind(format!("%{}%", query::escape_like(name)))
+	.bind(name)
+	.fetch_optional(&mut *connection)
+	.await
+	.map_err(Error::from)
+}
+
+#[cfg(test)]
+mod tests {
+	use cs2kz::{MapIdentifier, ServerIdentifier};
+
+	use crate::maps::MapID;
+	use crate::servers::ServerID;
+	use crate::sqlx::FetchID;
+
+	#[crate::integration_test(fixtures = ["grotto-maps"])]
+	async fn ambiguous_names(ctx: &Context) {
+		// all 4 maps contain "grotto"
+		let grotto = "grotto".parse::<MapIdentifier>()?;
+
+		assert_eq!(grotto.fetch_id(&ctx.database).await?, MapID(1));
+
+		// "kz_grotto" is a substring of 3 maps, but only one of them matches exactly
+		let kz_grotto = "kz_grotto".parse::<MapIdentifier>()?;
+
+		assert_eq!(kz_grotto.fetch_id(&ctx.database).await?, MapID(1));
+
+		let remake = "remake".parse::<MapIdentifier>()?;
+
+		assert_eq!(remake.fetch_id(&ctx.database).await?, MapID(3));
+
+		let unknown = "kz_does_not_exist".parse::<MapIdentifier>()?;
+
+		assert!(unknown.resolve(&ctx.database).await?.is_none());
+	}
+
+	#[crate::integration_test]
+	async fn numeric_names(ctx: &Context) {
+		let server_id = sqlx::query! {
+			r#"
+			INSERT INTO
+			  Servers (name, host, port, owner_id)
+			VALUES
+			  ("1337", "127.0.0.1", 1337, 76561198282622073)
+			"#,
 		}
+		.execute(&ctx.database)
+		.await?
+		.last_insert_id();
+
+		let server_id = ServerID(u16::try_from(server_id)?);
+		let by_name = "1337".parse::<ServerIdentifier>()?;
+
+		assert_eq!(by_name, ServerIdentifier::ID(1337));
+		assert_eq!(by_name.fetch_id(&ctx.database).await?, server_id);
+
+		// real IDs still take precedence
+		let by_id = ServerIdentifier::ID(server_id.0);
+
+		assert_eq!(by_id.fetch_id(&ctx.database).await?, server_id);
+
+		let unknown = ServerIdentifier::ID(42);
+
+		assert!(unknown.resolve(&ctx.database).await?.is_none());
+
+		// listing filters don't care whether the ID exists
+		assert_eq!(
+			unknown.fetch_id_unchecked(&ctx.database).await?,
+			ServerID(42)
+		);
 	}
 }
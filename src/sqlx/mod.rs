@@ -4,7 +4,13 @@ mod error;
 pub use error::SqlErrorExt;
 
 pub mod query;
-pub use query::{FilteredQuery, QueryBuilderExt, UpdateQuery};
+pub use query::{FilteredQuery, QueryBuilderExt, UpdateQuery, WithTotal};
+
+pub mod cursor;
+pub use cursor::Cursor;
+
+pub mod pool;
+pub use pool::PoolStats;
 
 mod fetch_id;
 pub use fetch_id::FetchID;
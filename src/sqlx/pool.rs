@@ -0,0 +1,130 @@
+//! Helpers for inspecting the database connection pool.
+
+use std::fmt;
+use std::time::Duration;
+
+use serde::Serialize;
+use sqlx::{Connection, MySql, Pool};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::filter::FilterFn;
+use tracing_subscriber::layer::{self, Layer};
+use tracing_subscriber::registry::LookupSpan;
+use utoipa::ToSchema;
+
+use crate::metrics;
+
+/// The `tracing` target sqlx uses when logging queries.
+const QUERY_LOG_TARGET: &str = "sqlx::query";
+
+/// Statistics about a database connection pool.
+///
+/// There is no `waiters` count, as sqlx doesn't expose how many tasks are waiting for a
+/// connection; `max_size` is reported instead. If `in_use` equals `max_size`, the pool is
+/// saturated and new queries have to wait.
+#[derive(Debug, Clone, Copy, Serialize, ToSchema)]
+pub struct PoolStats {
+	/// The amount of open connections.
+	pub size: u32,
+
+	/// The amount of idle connections.
+	pub idle: usize,
+
+	/// The amount of connections currently in use.
+	pub in_use: usize,
+
+	/// The maximum amount of connections the pool will open.
+	pub max_size: u32,
+}
+
+/// Returns statistics about the given `pool`.
+pub fn stats(pool: &Pool<MySql>) -> PoolStats {
+	let size = pool.size();
+	let idle = pool.num_idle();
+
+	PoolStats {
+		size,
+		idle,
+		in_use: usize::try_from(size).map_or(0, |size| size.saturating_sub(idle)),
+		max_size: pool.options().get_max_connections(),
+	}
+}
+
+/// Checks whether the database is reachable by pinging one of the pool's connections.
+///
+/// Returns `false` if the pool cannot serve a connection and ping it within `timeout`.
+#[tracing::instrument(level = "debug", name = "database::ping", skip(pool))]
+pub async fn ping(pool: &Pool<MySql>, timeout: Duration) -> bool {
+	let ping = async { pool.acquire().await?.ping().await };
+
+	match tokio::time::timeout(timeout, ping).await {
+		Ok(Ok(())) => true,
+		Ok(Err(error)) => {
+			tracing::warn!(%error, "failed to ping database");
+			false
+		}
+		Err(_) => {
+			tracing::warn!("database ping timed out");
+			false
+		}
+	}
+}
+
+/// Creates a tracing layer that counts slow queries in the [metrics registry].
+///
+/// sqlx logs every query that exceeds the slow query threshold as a warning (see
+/// [`MySqlConnectOptions::log_slow_statements()`]). This layer counts those warnings, labeled by
+/// sqlx's summary of the query (its first few words), so we can tell which queries need
+/// attention.
+///
+/// [metrics registry]: crate::metrics::Metrics
+/// [`MySqlConnectOptions::log_slow_statements()`]: sqlx::ConnectOptions::log_slow_statements
+pub fn slow_query_layer<S>() -> impl Layer<S>
+where
+	S: Subscriber + for<'a> LookupSpan<'a>,
+{
+	SlowQueryCounter.with_filter(FilterFn::new(|metadata| {
+		metadata.target() == QUERY_LOG_TARGET && *metadata.level() <= Level::WARN
+	}))
+}
+
+/// Tracing layer returned by [`slow_query_layer()`].
+struct SlowQueryCounter;
+
+impl<S: Subscriber> Layer<S> for SlowQueryCounter {
+	fn on_event(&self, event: &Event<'_>, _ctx: layer::Context<'_, S>) {
+		let mut visitor = SlowQueryVisitor::default();
+
+		event.record(&mut visitor);
+
+		if let (true, Some(summary)) = (visitor.is_slow, visitor.summary) {
+			metrics::record_slow_query(&summary);
+		}
+	}
+}
+
+/// Extracts the relevant fields from sqlx's query events.
+#[derive(Default)]
+struct SlowQueryVisitor {
+	/// sqlx's summary of the query.
+	summary: Option<String>,
+
+	/// Whether the query exceeded the slow query threshold.
+	///
+	/// Only events for slow queries have a `slow_threshold` field.
+	is_slow: bool,
+}
+
+impl Visit for SlowQueryVisitor {
+	fn record_str(&mut self, field: &Field, value: &str) {
+		if field.name() == "summary" {
+			self.summary = Some(value.to_owned());
+		}
+	}
+
+	fn record_debug(&mut self, field: &Field, _value: &dyn fmt::Debug) {
+		if field.name() == "slow_threshold" {
+			self.is_slow = true;
+		}
+	}
+}
@@ -3,9 +3,11 @@
 use std::fmt::Display;
 
 use derive_more::{Debug, Deref, DerefMut};
-use sqlx::{MySql, QueryBuilder, Transaction};
+use sqlx::mysql::MySqlRow;
+use sqlx::{FromRow, MySql, QueryBuilder, Row, Transaction};
 
 use crate::openapi::parameters::{Limit, Offset, SortingOrder};
+use crate::time::TimeRange;
 use crate::Result;
 
 /// Returns the amount of **total** rows a query _could have_ returned, ignoring `LIMIT`.
@@ -21,16 +23,115 @@ pub async fn total_rows(transaction: &mut Transaction<'_, MySql>) -> Result<u64>
 	Ok(total)
 }
 
+/// Escapes `LIKE` wildcards in `value`, so it only matches literally.
+///
+/// MySQL uses `\` as the default escape character, so backslashes are escaped as well.
+pub fn escape_like(value: &str) -> String {
+	let mut escaped = String::with_capacity(value.len());
+
+	for char in value.chars() {
+		if matches!(char, '\\' | '%' | '_') {
+			escaped.push('\\');
+		}
+
+		escaped.push(char);
+	}
+
+	escaped
+}
+
+/// Adds a `total_rows` column to a `SELECT` query.
+///
+/// The column contains the amount of **total** rows the query _could have_ returned, ignoring
+/// `LIMIT`, which means we don't need a separate query for [`total_rows()`]. The resulting rows
+/// can be decoded using [`WithTotal`].
+///
+/// NOTE: the window function makes the database compute the full result set before returning
+///       any rows, so this should not be used for queries that stream large results.
+///
+/// # Panics
+///
+/// This function will panic if `query` does not start with `SELECT`.
+pub fn select_with_total(query: &str) -> String {
+	let columns = query
+		.trim_start()
+		.strip_prefix("SELECT")
+		.expect("query should start with `SELECT`");
+
+	format!("SELECT COUNT(*) OVER () total_rows, {columns}")
+}
+
+/// A row returned by a query built with [`select_with_total()`].
+#[derive(Debug)]
+pub struct WithTotal<T> {
+	/// The amount of total rows the query could have returned.
+	pub total: u64,
+
+	/// The row itself.
+	pub row: T,
+}
+
+impl<T> WithTotal<T> {
+	/// Splits the total off a list of rows.
+	///
+	/// Every row contains the same total, so it is taken from the first one. If there are no
+	/// rows, the total is 0.
+	pub fn split(rows: Vec<Self>) -> (u64, Vec<T>) {
+		let total = rows.first().map_or(0, |row| row.total);
+		let rows = rows.into_iter().map(|row| row.row).collect();
+
+		(total, rows)
+	}
+}
+
+impl<'r, T> FromRow<'r, MySqlRow> for WithTotal<T>
+where
+	T: FromRow<'r, MySqlRow>,
+{
+	fn from_row(row: &'r MySqlRow) -> sqlx::Result<Self> {
+		let total = row
+			.try_get::<i64, _>("total_rows")?
+			.try_into()
+			.expect("how can a count be negative");
+
+		Ok(Self {
+			total,
+			row: T::from_row(row)?,
+		})
+	}
+}
+
 /// Extension trait for [`sqlx::QueryBuilder`].
-pub trait QueryBuilderExt {
+pub trait QueryBuilderExt<'args> {
 	/// Pushes `LIMIT` and `OFFSET` clauses into the query.
 	fn push_limits(&mut self, limit: Limit, offset: Offset) -> &mut Self;
 
 	/// Pushes an `ORDER BY` query into the query.
 	fn order_by(&mut self, order: SortingOrder, columns: impl Display) -> &mut Self;
+
+	/// Pushes an `IN (?, ?, ...)` clause into the query, binding every value in `values`.
+	///
+	/// An empty `IN ()` list is invalid SQL, so if `values` is empty, nothing will be pushed
+	/// and `false` is returned.
+	///
+	/// # Example
+	///
+	/// ```rust,ignore
+	/// let mut query = QueryBuilder::new("DELETE FROM table WHERE id");
+	///
+	/// if !query.push_in_list(ids) {
+	///     return Ok(());
+	/// }
+	///
+	/// query.build().execute(&database).await?;
+	/// ```
+	fn push_in_list<I>(&mut self, values: I) -> bool
+	where
+		I: IntoIterator,
+		I::Item: sqlx::Type<MySql> + sqlx::Encode<'args, MySql> + Send + 'args;
 }
 
-impl QueryBuilderExt for QueryBuilder<'_, MySql> {
+impl<'args> QueryBuilderExt<'args> for QueryBuilder<'args, MySql> {
 	fn push_limits(&mut self, limit: Limit, offset: Offset) -> &mut Self {
 		self.push(" LIMIT ")
 			.push_bind(*limit)
@@ -41,6 +142,30 @@ impl QueryBuilderExt for QueryBuilder<'_, MySql> {
 	fn order_by(&mut self, order: SortingOrder, columns: impl Display) -> &mut Self {
 		self.push(" ORDER BY ").push(columns).push(order.sql())
 	}
+
+	fn push_in_list<I>(&mut self, values: I) -> bool
+	where
+		I: IntoIterator,
+		I::Item: sqlx::Type<MySql> + sqlx::Encode<'args, MySql> + Send + 'args,
+	{
+		let mut values = values.into_iter().peekable();
+
+		if values.peek().is_none() {
+			return false;
+		}
+
+		self.push(" IN (");
+
+		let mut separated = self.separated(", ");
+
+		for value in values {
+			separated.push_bind(value);
+		}
+
+		self.push(")");
+
+		true
+	}
 }
 
 /// A query with `WHERE` / `AND` clauses.
@@ -125,6 +250,55 @@ impl<'q> FilteredQuery<'q> {
 		self
 	}
 
+	/// Pushes `WHERE` / `AND` clauses into the query that only match rows where `column` is
+	/// inside of `range`.
+	///
+	/// Unbounded sides of the range don't produce any clauses.
+	pub fn filter_range(&mut self, column: &str, range: TimeRange) -> &mut Self {
+		let (start, end) = range.to_sql_bounds();
+
+		for (operator, value) in start.into_iter().chain(end) {
+			self.filter(&format!(" {column}{operator}"), value);
+		}
+
+		self
+	}
+
+	/// Pushes a `WHERE` / `AND` clause into the query that only matches rows coming after a
+	/// [cursor].
+	///
+	/// `order` must be the same order the results are sorted in, and the query should be sorted
+	/// by `key_column` first, and `id_column` second.
+	///
+	/// [cursor]: crate::sqlx::Cursor
+	pub fn filter_cursor<K>(
+		&mut self,
+		key_column: &str,
+		id_column: &str,
+		order: SortingOrder,
+		key: K,
+		id: u64,
+	) -> &mut Self
+	where
+		K: sqlx::Type<MySql> + sqlx::Encode<'q, MySql> + Send + 'q,
+	{
+		self.query
+			.push(self.filter.sql())
+			.push(format_args!("({key_column}, {id_column})"))
+			.push(match order {
+				SortingOrder::Ascending => " > ",
+				SortingOrder::Descending => " < ",
+			})
+			.push("(")
+			.push_bind(key)
+			.push(", ")
+			.push_bind(id)
+			.push(")");
+
+		self.filter = Filter::And;
+		self
+	}
+
 	/// Pushes a `WHERE` / `AND` clause into the query, checking if a column is (not) `NULL`.
 	pub fn filter_is_null(&mut self, column: &str, is_null: bool) -> &mut Self {
 		self.query
@@ -8,19 +8,26 @@
 
 use std::convert::Infallible;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Context;
 use axum::async_trait;
 use axum::extract::FromRequestParts;
 use axum::http::request;
 use derive_more::Debug;
+use futures::future::BoxFuture;
+use log::LevelFilter;
+use rand::{thread_rng, Rng};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
-use sqlx::pool::PoolOptions;
-use sqlx::{MySql, Pool, Transaction};
+use sqlx::mysql::MySqlConnectOptions;
+use sqlx::pool::{PoolConnection, PoolOptions};
+use sqlx::{ConnectOptions, MySql, Pool, Transaction};
+use url::Url;
 
 use crate::authentication::Jwt;
-use crate::{Error, Result};
+use crate::metrics::Metrics;
+use crate::{steam, Error, Result};
 
 /// The minimum number of [database pool] connections.
 ///
@@ -51,10 +58,23 @@ pub struct State {
 	#[debug(skip)]
 	pub database: Pool<MySql>,
 
+	/// Connection pool for the read replica, if one is configured.
+	///
+	/// See [`State::acquire_read()`].
+	#[debug(skip)]
+	read_database: Option<Pool<MySql>>,
+
 	/// An HTTP client for making requests to other APIs.
 	#[debug(skip)]
 	pub http_client: reqwest::Client,
 
+	/// A client for making requests to the Steam Web API.
+	pub steam_api: steam::api::Client,
+
+	/// Metrics collected about the API.
+	#[debug(skip)]
+	pub metrics: Arc<Metrics>,
+
 	/// JWT state for encoding/decoding tokens.
 	#[debug(skip)]
 	jwt_state: Arc<JwtState>,
@@ -68,15 +88,19 @@ impl State {
 			url = %api_config.database_url,
 			min_connections = MIN_DB_CONNECTIONS,
 			max_connections = MAX_DB_CONNECTIONS,
+			slow_query_threshold = ?api_config.slow_query_threshold,
 			"establishing database connection",
 		};
 
 		let config = Arc::new(api_config);
-		let database = PoolOptions::new()
-			.min_connections(MIN_DB_CONNECTIONS)
-			.max_connections(MAX_DB_CONNECTIONS)
-			.connect(config.database_url.as_str())
-			.await?;
+		let database = connect(&config.database_url, &config).await?;
+		let read_database = match config.database_read_url {
+			None => None,
+			Some(ref read_url) => {
+				tracing::debug!(url = %read_url, "establishing read replica connection");
+				Some(connect(read_url, &config).await?)
+			}
+		};
 
 		sqlx::migrate!("./database/migrations")
 			.run(&database)
@@ -84,12 +108,20 @@ impl State {
 			.context("run migrations")?;
 
 		let http_client = reqwest::Client::new();
+		let steam_api = steam::api::Client::new(
+			http_client.clone(),
+			config.steam_api_key.clone(),
+			config.steam_retry_policy,
+		);
 		let jwt_state = JwtState::new(&config).map(Arc::new)?;
 
 		Ok(Self {
 			config,
 			database,
+			read_database,
 			http_client,
+			steam_api,
+			metrics: Arc::default(),
 			jwt_state,
 		})
 	}
@@ -102,6 +134,55 @@ impl State {
 		self.database.begin().await.map_err(Error::from)
 	}
 
+	/// Acquires a connection for expensive, read-only queries.
+	///
+	/// If a read replica is configured, the connection comes from it, otherwise from the primary
+	/// database. Replicas can lag behind, so this should not be used for anything that needs to
+	/// see writes made by the same request.
+	pub async fn acquire_read(&self) -> Result<PoolConnection<MySql>> {
+		self.read_database
+			.as_ref()
+			.unwrap_or(&self.database)
+			.acquire()
+			.await
+			.map_err(Error::from)
+	}
+
+	/// Runs `f` inside a database transaction, retrying it if it fails because of a deadlock or
+	/// lock wait timeout.
+	///
+	/// Every attempt gets a fresh transaction, which is committed if `f` succeeds, and rolled
+	/// back otherwise. Failed attempts are retried after a short, randomized delay. After
+	/// `max_attempts` attempts, the last error is returned.
+	pub async fn in_transaction_retry<T, F>(&self, max_attempts: u32, mut f: F) -> Result<T>
+	where
+		F: for<'c> FnMut(&'c mut Transaction<'static, MySql>) -> BoxFuture<'c, Result<T>>,
+	{
+		let mut attempt = 1;
+
+		loop {
+			let mut transaction = self.database.begin().await?;
+
+			match f(&mut transaction).await {
+				Ok(value) => {
+					transaction.commit().await?;
+					return Ok(value);
+				}
+				Err(error) if attempt < max_attempts && error.is_transient() => {
+					transaction.rollback().await?;
+
+					let backoff =
+						Duration::from_millis(thread_rng().gen_range(10..50) * u64::from(attempt));
+
+					tracing::warn!(%error, attempt, ?backoff, "transaction failed; retrying");
+					tokio::time::sleep(backoff).await;
+					attempt += 1;
+				}
+				Err(error) => return Err(error),
+			}
+		}
+	}
+
 	/// Encodes a JWT.
 	pub fn encode_jwt<T>(&self, jwt: Jwt<T>) -> Result<String>
 	where
@@ -119,6 +200,21 @@ impl State {
 	}
 }
 
+/// Creates a connection pool for the database at `url`.
+async fn connect(url: &Url, config: &crate::Config) -> anyhow::Result<Pool<MySql>> {
+	let connect_options = url
+		.as_str()
+		.parse::<MySqlConnectOptions>()?
+		.log_slow_statements(LevelFilter::Warn, config.slow_query_threshold);
+
+	PoolOptions::new()
+		.min_connections(MIN_DB_CONNECTIONS)
+		.max_connections(MAX_DB_CONNECTIONS)
+		.connect_with(connect_options)
+		.await
+		.map_err(Into::into)
+}
+
 #[async_trait]
 impl FromRequestParts<State> for State {
 	type Rejection = Infallible;
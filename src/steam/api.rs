@@ -0,0 +1,228 @@
+//! A client for the [Steam Web API].
+//!
+//! Steam's API intermittently responds with `429 Too Many Requests` or `5xx` errors, so every
+//! request sent through a [`Client`] is retried with exponential backoff, respecting any
+//! `Retry-After` header Steam sends along. Requests also go through a token bucket, so we don't
+//! exceed Steam's quota when sending a lot of requests at once.
+//!
+//! [Steam Web API]: https://steamcommunity.com/dev
+
+use std::cmp;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use derive_more::Debug;
+use reqwest::{header, RequestBuilder, Response, StatusCode};
+use tokio::time::Instant;
+
+use crate::{Error, Result};
+
+/// How many requests can be sent in a burst before the [rate limiter] kicks in.
+///
+/// [rate limiter]: TokenBucket
+const RATE_LIMIT_BURST: f64 = 20.0;
+
+/// How many requests per second the [rate limiter] lets through on average.
+///
+/// [rate limiter]: TokenBucket
+const RATE_LIMIT_PER_SECOND: f64 = 5.0;
+
+/// How requests to the Steam Web API are retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+	/// How many times a request is retried before giving up.
+	pub max_retries: u32,
+
+	/// How long to wait before the first retry.
+	///
+	/// This doubles with every retry.
+	pub initial_backoff: Duration,
+
+	/// The maximum amount of time to wait between retries.
+	pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+	/// Returns how long to wait before the given retry.
+	fn backoff(&self, retry: u32) -> Duration {
+		let backoff = self
+			.initial_backoff
+			.saturating_mul(2_u32.saturating_pow(retry.saturating_sub(1)));
+
+		cmp::min(backoff, self.max_backoff)
+	}
+}
+
+impl Default for RetryPolicy {
+	fn default() -> Self {
+		Self {
+			max_retries: 3,
+			initial_backoff: Duration::from_millis(250),
+			max_backoff: Duration::from_secs(10),
+		}
+	}
+}
+
+/// A Steam Web API client.
+#[derive(Debug, Clone)]
+pub struct Client {
+	/// The underlying HTTP client.
+	#[debug(skip)]
+	http_client: reqwest::Client,
+
+	/// Steam Web API key.
+	#[debug("*****")]
+	api_key: String,
+
+	/// How failed requests are retried.
+	retry_policy: RetryPolicy,
+
+	/// Limits how many requests we send to Steam.
+	#[debug(skip)]
+	rate_limiter: Arc<Mutex<TokenBucket>>,
+}
+
+impl Client {
+	/// Creates a new [`Client`].
+	pub fn new(http_client: reqwest::Client, api_key: String, retry_policy: RetryPolicy) -> Self {
+		Self {
+			http_client,
+			api_key,
+			retry_policy,
+			rate_limiter: Arc::new(Mutex::new(TokenBucket::new())),
+		}
+	}
+
+	/// Returns the Steam Web API key.
+	pub fn api_key(&self) -> &str {
+		&self.api_key
+	}
+
+	/// Returns the underlying HTTP client, for building requests.
+	pub const fn http_client(&self) -> &reqwest::Client {
+		&self.http_client
+	}
+
+	/// Sends a request, retrying it according to the client's [`RetryPolicy`].
+	///
+	/// Responses with a status other than `429 Too Many Requests` or `5xx` are returned as-is;
+	/// checking them is up to the caller. `Retry-After` headers are honored, but never wait
+	/// longer than [`RetryPolicy::max_backoff`].
+	#[tracing::instrument(level = "debug", name = "steam::api::send", skip_all, err)]
+	pub async fn send(&self, request: RequestBuilder) -> Result<Response> {
+		let mut retry = 0;
+
+		loop {
+			let request = request
+				.try_clone()
+				.ok_or_else(|| Error::logic("steam api requests should not be streaming"))?;
+
+			self.wait_for_rate_limit().await;
+
+			let backoff = match request.send().await {
+				Ok(response) if !is_retryable(response.status()) => return Ok(response),
+				Ok(response) => {
+					tracing::warn!(status = %response.status(), retry, "steam api request failed");
+					retry_after(&response)
+						.map(|delay| cmp::min(delay, self.retry_policy.max_backoff))
+				}
+				Err(error) if error.is_timeout() || error.is_connect() => {
+					// the URL contains our API key
+					let error = error.without_url();
+					tracing::warn!(%error, retry, "steam api request failed");
+					None
+				}
+				Err(error) => return Err(Error::external_api_call(error)),
+			};
+
+			if retry >= self.retry_policy.max_retries {
+				return Err(Error::steam_api_retries_exhausted(retry + 1));
+			}
+
+			retry += 1;
+
+			let backoff = backoff.unwrap_or_else(|| self.retry_policy.backoff(retry));
+
+			tracing::debug!(?backoff, retry, "retrying steam api request");
+			tokio::time::sleep(backoff).await;
+		}
+	}
+
+	/// Waits until the [rate limiter] lets another request through.
+	///
+	/// [rate limiter]: TokenBucket
+	async fn wait_for_rate_limit(&self) {
+		let delay = self
+			.rate_limiter
+			.lock()
+			.expect("rate limiter mutex should not be poisoned")
+			.acquire();
+
+		if !delay.is_zero() {
+			tracing::debug!(?delay, "hit steam api rate limit");
+			tokio::time::sleep(delay).await;
+		}
+	}
+}
+
+/// Whether a request that produced a response with the given status should be retried.
+fn is_retryable(status: StatusCode) -> bool {
+	status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Extracts the `Retry-After` header from a response, if it is present and in seconds.
+fn retry_after(response: &Response) -> Option<Duration> {
+	response
+		.headers()
+		.get(header::RETRY_AFTER)?
+		.to_str()
+		.ok()?
+		.parse::<u64>()
+		.map(Duration::from_secs)
+		.ok()
+}
+
+/// A token bucket for rate limiting.
+///
+/// Every request takes a token out of the bucket, and tokens are refilled at a constant rate. If
+/// the bucket is empty, requests have to wait for the next token.
+#[derive(Debug)]
+struct TokenBucket {
+	/// How many tokens are currently in the bucket.
+	///
+	/// This can go negative if multiple requests are waiting for tokens at the same time.
+	tokens: f64,
+
+	/// When the bucket was last refilled.
+	last_refill: Instant,
+}
+
+impl TokenBucket {
+	/// Creates a new, full [`TokenBucket`].
+	fn new() -> Self {
+		Self {
+			tokens: RATE_LIMIT_BURST,
+			last_refill: Instant::now(),
+		}
+	}
+
+	/// Takes a token out of the bucket and returns how long the caller has to wait before using
+	/// it.
+	fn acquire(&mut self) -> Duration {
+		let now = Instant::now();
+		let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+
+		self.tokens = f64::min(
+			self.tokens + elapsed * RATE_LIMIT_PER_SECOND,
+			RATE_LIMIT_BURST,
+		);
+		self.last_refill = now;
+		self.tokens -= 1.0;
+
+		if self.tokens >= 0.0 {
+			Duration::ZERO
+		} else {
+			Duration::from_secs_f64(-self.tokens / RATE_LIMIT_PER_SECOND)
+		}
+	}
+}
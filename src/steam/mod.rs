@@ -1,5 +1,7 @@
 //! Everything related to Steam.
 
+pub mod api;
+
 mod user;
 pub use user::User;
 
@@ -1,16 +1,20 @@
 //! Steam user information.
 
+use std::collections::HashMap;
+
 use axum::async_trait;
 use axum::extract::FromRequestParts;
 use axum::http::request;
 use axum_extra::extract::cookie::Cookie;
 use cs2kz::SteamID;
 use derive_more::Debug;
+use futures::future;
+use itertools::Itertools;
 use serde::{Deserialize, Deserializer, Serialize};
 use url::Url;
 use utoipa::ToSchema;
 
-use crate::{Error, Result, State};
+use crate::{steam, Error, Result, State};
 
 /// Steam Web API URL for fetching user information.
 const API_URL: &str = "https://api.steampowered.com/ISteamUser/GetPlayerSummaries/v0002";
@@ -18,6 +22,9 @@ const API_URL: &str = "https://api.steampowered.com/ISteamUser/GetPlayerSummarie
 /// HTTP cookie name for storing a serialized [`User`].
 const COOKIE_NAME: &str = "kz-player";
 
+/// The maximum number of SteamIDs that can be sent to Steam's API in a single request.
+const MAX_IDS_PER_REQUEST: usize = 100;
+
 /// A Steam user.
 #[derive(Debug, Serialize, ToSchema)]
 pub struct User {
@@ -47,19 +54,15 @@ pub struct User {
 
 impl User {
 	/// Fetches a user from Steam's API.
-	#[tracing::instrument(level = "debug", skip(http_client, api_config))]
-	pub async fn fetch(
-		steam_id: SteamID,
-		http_client: &reqwest::Client,
-		api_config: &crate::Config,
-	) -> Result<Self> {
+	#[tracing::instrument(level = "debug", skip(steam_api))]
+	pub async fn fetch(steam_id: SteamID, steam_api: &steam::api::Client) -> Result<Self> {
 		let url = Url::parse_with_params(API_URL, [
-			("key", api_config.steam_api_key.clone()),
+			("key", steam_api.api_key().to_owned()),
 			("steamids", steam_id.as_u64().to_string()),
 		])
 		.map_err(|err| Error::logic("failed to parse url").context(err))?;
 
-		let response = http_client.get(url).send().await?;
+		let response = steam_api.send(steam_api.http_client().get(url)).await?;
 
 		if let Err(error) = response.error_for_status_ref() {
 			let error = Error::external_api_call(error);
@@ -75,6 +78,32 @@ impl User {
 		Ok(user)
 	}
 
+	/// Fetches multiple users from Steam's API.
+	///
+	/// Steam only accepts a limited number of SteamIDs per request, so `steam_ids` is split into
+	/// chunks that are fetched concurrently. If some of these requests fail, the users that
+	/// could be fetched are still returned.
+	#[tracing::instrument(level = "debug", skip_all, fields(count = steam_ids.len()))]
+	pub async fn fetch_many(
+		steam_ids: &[SteamID],
+		steam_api: &steam::api::Client,
+	) -> HashMap<SteamID, Self> {
+		let chunks = steam_ids
+			.chunks(MAX_IDS_PER_REQUEST)
+			.map(|steam_ids| fetch_chunk(steam_ids, steam_api));
+
+		let mut users = HashMap::with_capacity(steam_ids.len());
+
+		for result in future::join_all(chunks).await {
+			match result {
+				Ok(chunk) => users.extend(chunk.into_iter().map(|user| (user.steam_id, user))),
+				Err(error) => tracing::warn!(%error, "failed to fetch steam users"),
+			}
+		}
+
+		users
+	}
+
 	/// Generates a fake user for use in tests.
 	#[cfg(test)]
 	pub fn invalid(steam_id: SteamID) -> Self {
@@ -105,6 +134,70 @@ impl User {
 	}
 }
 
+/// Fetches a single chunk of users for [`User::fetch_many()`].
+async fn fetch_chunk(steam_ids: &[SteamID], steam_api: &steam::api::Client) -> Result<Vec<User>> {
+	#[derive(Deserialize)]
+	#[allow(clippy::missing_docs_in_private_items)]
+	struct Helper1 {
+		response: Helper2,
+	}
+
+	#[derive(Deserialize)]
+	#[allow(clippy::missing_docs_in_private_items)]
+	struct Helper2 {
+		players: Vec<PlayerSummary>,
+	}
+
+	let steam_ids = steam_ids.iter().map(|steam_id| steam_id.as_u64()).join(",");
+
+	let url = Url::parse_with_params(API_URL, [
+		("key", steam_api.api_key()),
+		("steamids", steam_ids.as_str()),
+	])
+	.map_err(|err| Error::logic("failed to parse url").context(err))?;
+
+	let users = steam_api
+		.send(steam_api.http_client().get(url))
+		.await?
+		.error_for_status()
+		.map_err(Error::external_api_call)?
+		.json::<Helper1>()
+		.await?
+		.response
+		.players
+		.into_iter()
+		.map(User::from)
+		.collect();
+
+	Ok(users)
+}
+
+/// A single player as returned by Steam's `GetPlayerSummaries` endpoint.
+#[derive(Deserialize)]
+#[allow(clippy::missing_docs_in_private_items)]
+struct PlayerSummary {
+	steamid: SteamID,
+	personaname: String,
+	realname: Option<String>,
+	loccountrycode: Option<String>,
+	profileurl: Url,
+	avatar: Url,
+}
+
+impl From<PlayerSummary> for User {
+	fn from(player: PlayerSummary) -> Self {
+		Self {
+			steam_id: player.steamid,
+			steam_id64: player.steamid.as_u64().to_string(),
+			username: player.personaname,
+			realname: player.realname,
+			country: player.loccountrycode,
+			profile_url: player.profileurl,
+			avatar_url: player.avatar,
+		}
+	}
+}
+
 impl<'de> Deserialize<'de> for User {
 	#[allow(clippy::missing_docs_in_private_items)]
 	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -118,30 +211,12 @@ impl<'de> Deserialize<'de> for User {
 
 		#[derive(Deserialize)]
 		struct Helper2 {
-			players: [Helper3; 1],
-		}
-
-		#[derive(Deserialize)]
-		struct Helper3 {
-			steamid: SteamID,
-			personaname: String,
-			realname: Option<String>,
-			loccountrycode: Option<String>,
-			profileurl: Url,
-			avatar: Url,
+			players: [PlayerSummary; 1],
 		}
 
-		Helper1::deserialize(deserializer).map(|x| x.response).map(
-			|Helper2 { players: [player] }| Self {
-				steam_id: player.steamid,
-				steam_id64: player.steamid.as_u64().to_string(),
-				username: player.personaname,
-				realname: player.realname,
-				country: player.loccountrycode,
-				profile_url: player.profileurl,
-				avatar_url: player.avatar,
-			},
-		)
+		Helper1::deserialize(deserializer)
+			.map(|x| x.response)
+			.map(|Helper2 { players: [player] }| Self::from(player))
 	}
 }
 
@@ -166,6 +241,6 @@ impl FromRequestParts<State> for User {
 		tracing::Span::current().record("steam_id", format_args!("{steam_id}"));
 		tracing::debug!("fetching user from steam");
 
-		Self::fetch(steam_id, &state.http_client, &state.config).await
+		Self::fetch(steam_id, &state.steam_api).await
 	}
 }
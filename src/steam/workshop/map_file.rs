@@ -7,9 +7,10 @@
 
 use derive_more::Debug;
 use tokio::fs::File;
-use tokio::io::{self, AsyncReadExt, AsyncWriteExt};
+use tokio::io::{self, AsyncWriteExt};
 use tokio::process::Command;
 
+use crate::checksum::{self, Checksum};
 use crate::steam::workshop::WorkshopID;
 use crate::{Config, Error, Result};
 
@@ -88,14 +89,8 @@ impl MapFile {
 	}
 
 	/// Computes the crc32 checksum for this file.
-	#[tracing::instrument(level = "debug", skip(self), ret)]
-	pub async fn checksum(mut self) -> io::Result<u32> {
-		let metadata = self.file.metadata().await?;
-		let filesize = usize::try_from(metadata.len()).expect("64-bit platform");
-		let mut buf = Vec::with_capacity(filesize);
-
-		self.file.read_to_end(&mut buf).await?;
-
-		Ok(crc32fast::hash(&buf))
+	#[tracing::instrument(level = "debug", skip(self), ret(Display), err)]
+	pub async fn checksum(mut self) -> io::Result<Checksum> {
+		checksum::from_async_reader(&mut self.file).await
 	}
 }
@@ -1,47 +1,82 @@
 //! Functions for fetching information about Workshop Maps.
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value as JsonValue;
 
 use crate::steam::workshop::WorkshopID;
-use crate::{Error, Result};
+use crate::{steam, Error, Result};
 
 /// Steam Web API URL for fetching map information.
 const API_URL: &str = "https://api.steampowered.com/ISteamRemoteStorage/GetPublishedFileDetails/v1";
 
 /// Fetches the name of a Workshop Map.
-#[tracing::instrument(level = "debug", skip(http_client), ret)]
+#[tracing::instrument(level = "debug", skip(steam_api), ret)]
 pub async fn fetch_map_name(
 	workshop_id: WorkshopID,
-	http_client: &reqwest::Client,
+	steam_api: &steam::api::Client,
 ) -> Result<String> {
+	fetch_map_metadata(workshop_id, steam_api)
+		.await
+		.map(|metadata| metadata.title)
+}
+
+/// Fetches the time a Workshop Map was last updated.
+#[tracing::instrument(level = "debug", skip(steam_api), ret)]
+pub async fn get_update_time(
+	workshop_id: WorkshopID,
+	steam_api: &steam::api::Client,
+) -> Result<Option<DateTime<Utc>>> {
+	fetch_map_metadata(workshop_id, steam_api)
+		.await
+		.map(|metadata| metadata.updated_at)
+}
+
+/// Fetches metadata about a Workshop Map.
+#[tracing::instrument(level = "debug", skip(steam_api), ret)]
+pub async fn fetch_map_metadata(
+	workshop_id: WorkshopID,
+	steam_api: &steam::api::Client,
+) -> Result<WorkshopMetadata> {
 	#[derive(Serialize)]
 	#[allow(clippy::missing_docs_in_private_items)]
 	struct Params {
 		workshop_id: WorkshopID,
 	}
 
-	let response = http_client
+	let request = steam_api
+		.http_client()
 		.post(API_URL)
-		.form(&Params { workshop_id })
-		.send()
-		.await?;
+		.form(&Params { workshop_id });
+
+	let response = steam_api.send(request).await?;
 
 	if !response.status().is_success() {
 		return Err(Error::not_found("workshop map"));
 	}
 
-	let map_info = response.json::<MapInfo>().await?;
+	let metadata = response.json::<WorkshopMetadata>().await?;
 
-	Ok(map_info.title)
+	Ok(metadata)
 }
 
-#[allow(clippy::missing_docs_in_private_items)]
-struct MapInfo {
-	title: String,
+/// Metadata about a Workshop Map.
+#[derive(Debug, Clone)]
+pub struct WorkshopMetadata {
+	/// The title of the workshop item.
+	pub title: String,
+
+	/// The description of the workshop item.
+	pub description: Option<String>,
+
+	/// URL of the workshop item's preview image.
+	pub preview_url: Option<String>,
+
+	/// When the workshop item was last updated.
+	pub updated_at: Option<DateTime<Utc>>,
 }
 
-impl<'de> Deserialize<'de> for MapInfo {
+impl<'de> Deserialize<'de> for WorkshopMetadata {
 	#[allow(clippy::missing_docs_in_private_items)]
 	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
 	where
@@ -59,12 +94,39 @@ impl<'de> Deserialize<'de> for MapInfo {
 			publishedfiledetails: Vec<JsonValue>,
 		}
 
-		Helper1::deserialize(deserializer)
-			.map(|x| x.response)
-			.map(|mut x| x.publishedfiledetails.remove(0))
-			.map(|mut json| json.get_mut("title").unwrap_or(&mut JsonValue::Null).take())
-			.map(|json| json.as_str().map(ToOwned::to_owned))?
-			.map(|title| Self { title })
-			.ok_or_else(|| E::missing_field("title"))
+		#[derive(Deserialize)]
+		struct Helper3 {
+			title: String,
+
+			#[serde(default)]
+			description: Option<String>,
+
+			#[serde(default)]
+			preview_url: Option<String>,
+
+			#[serde(default, with = "chrono::serde::ts_seconds_option")]
+			time_updated: Option<DateTime<Utc>>,
+		}
+
+		let details = Helper1::deserialize(deserializer)
+			.map(|x| x.response)?
+			.publishedfiledetails
+			.into_iter()
+			.next()
+			.ok_or_else(|| E::missing_field("publishedfiledetails"))?;
+
+		let Helper3 {
+			title,
+			description,
+			preview_url,
+			time_updated,
+		} = serde_json::from_value(details).map_err(E::custom)?;
+
+		Ok(Self {
+			title,
+			description: description.filter(|description| !description.is_empty()),
+			preview_url: preview_url.filter(|url| !url.is_empty()),
+			updated_at: time_updated,
+		})
 	}
 }
@@ -3,9 +3,12 @@
 use crate::make_id;
 
 mod map_info;
-pub use map_info::fetch_map_name;
+pub use map_info::{fetch_map_metadata, fetch_map_name, get_update_time, WorkshopMetadata};
 
 mod map_file;
 pub use map_file::MapFile;
 
+mod preview;
+pub use preview::PreviewImage;
+
 make_id!(WorkshopID as u32);
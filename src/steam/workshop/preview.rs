@@ -0,0 +1,169 @@
+//! Workshop Map preview images.
+//!
+//! Preview images are downloaded from Steam's CDN and cached on disk, next to the downloaded map
+//! files. The cache is keyed by the map's workshop ID and the time it was last updated, so
+//! updating the workshop item also refreshes its preview. Items without a preview image are
+//! cached as empty files, so we don't ask Steam about them again.
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use reqwest::Response;
+use tokio::fs;
+use uuid::Uuid;
+
+use crate::steam::workshop::{self, WorkshopID};
+use crate::{steam, Config, Error, Result};
+
+/// The name of the directory, relative to the workshop artifacts directory, where previews are
+/// cached.
+const CACHE_DIR: &str = "previews";
+
+/// The maximum size of a preview image, in bytes.
+///
+/// Larger images are treated as if there was no preview image.
+const MAX_BYTES: usize = 8 * 1024 * 1024;
+
+/// A Workshop Map preview image.
+#[derive(Debug)]
+pub struct PreviewImage {
+	/// The raw image data.
+	pub bytes: Vec<u8>,
+}
+
+impl PreviewImage {
+	/// Returns the image's MIME type, based on its contents.
+	pub fn content_type(&self) -> &'static str {
+		match self.bytes.as_slice() {
+			[0x89, b'P', b'N', b'G', ..] => "image/png",
+			[0xFF, 0xD8, 0xFF, ..] => "image/jpeg",
+			[b'G', b'I', b'F', b'8', ..] => "image/gif",
+			[b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => "image/webp",
+			_ => "application/octet-stream",
+		}
+	}
+
+	/// Fetches the preview image of a Workshop Map.
+	///
+	/// `updated_on` is when the workshop item was last updated, if we know. Returns `None` if
+	/// the workshop item does not have a preview image.
+	#[tracing::instrument(level = "debug", skip(api_config, steam_api))]
+	pub async fn fetch(
+		workshop_id: WorkshopID,
+		updated_on: Option<DateTime<Utc>>,
+		api_config: &Config,
+		steam_api: &steam::api::Client,
+	) -> Result<Option<Self>> {
+		let cache_path = cache_path(workshop_id, updated_on, api_config);
+
+		if let Some(cache_path) = &cache_path {
+			if let Ok(bytes) = fs::read(cache_path).await {
+				tracing::debug!(?cache_path, "using cached preview image");
+				return Ok((!bytes.is_empty()).then_some(Self { bytes }));
+			}
+		}
+
+		let preview_url = workshop::fetch_map_metadata(workshop_id, steam_api)
+			.await?
+			.preview_url;
+
+		let bytes = match preview_url {
+			None => None,
+			Some(preview_url) => download(preview_url, steam_api).await?,
+		};
+
+		if let Some(cache_path) = &cache_path {
+			let bytes = bytes.as_deref().unwrap_or_default();
+
+			if let Err(error) = write_cache(cache_path, bytes).await {
+				tracing::warn!(%error, ?cache_path, "failed to cache preview image");
+			}
+		}
+
+		Ok(bytes.map(|bytes| Self { bytes }))
+	}
+}
+
+/// Downloads a preview image.
+///
+/// Returns `None` if the image is larger than [`MAX_BYTES`].
+async fn download(url: String, steam_api: &steam::api::Client) -> Result<Option<Vec<u8>>> {
+	let mut response = steam_api
+		.http_client()
+		.get(url)
+		.send()
+		.await
+		.and_then(Response::error_for_status)
+		.map_err(Error::external_api_call)?;
+
+	let too_large = || tracing::warn!(max_bytes = MAX_BYTES, "preview image is too large");
+
+	if response
+		.content_length()
+		.is_some_and(|len| usize::try_from(len).map_or(true, |len| len > MAX_BYTES))
+	{
+		too_large();
+		return Ok(None);
+	}
+
+	let mut bytes = Vec::new();
+
+	while let Some(chunk) = response.chunk().await.map_err(Error::external_api_call)? {
+		if bytes.len() + chunk.len() > MAX_BYTES {
+			too_large();
+			return Ok(None);
+		}
+
+		bytes.extend_from_slice(&chunk);
+	}
+
+	Ok(Some(bytes))
+}
+
+/// Returns the path a preview image should be cached at.
+///
+/// Returns `None` if there is no directory to store workshop artifacts in.
+fn cache_path(
+	workshop_id: WorkshopID,
+	updated_on: Option<DateTime<Utc>>,
+	api_config: &Config,
+) -> Option<PathBuf> {
+	#[cfg(not(feature = "production"))]
+	let out_dir = api_config.workshop_artifacts_path.as_deref()?;
+
+	#[cfg(feature = "production")]
+	let out_dir = &api_config.workshop_artifacts_path;
+
+	let updated_on = updated_on.map_or(0, |updated_on| updated_on.timestamp());
+
+	Some(
+		out_dir
+			.join(CACHE_DIR)
+			.join(format!("{workshop_id}-{updated_on}")),
+	)
+}
+
+/// Writes a preview image to the cache.
+///
+/// The image is written to a temporary file first, and then moved into place, so concurrent
+/// requests never read a partially written image.
+async fn write_cache(cache_path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+	if let Some(dir) = cache_path.parent() {
+		fs::create_dir_all(dir).await?;
+	}
+
+	let tmp_path = cache_path.with_extension(format!("{}.tmp", Uuid::new_v4()));
+
+	let result = async {
+		fs::write(&tmp_path, bytes).await?;
+		fs::rename(&tmp_path, cache_path).await
+	}
+	.await;
+
+	if result.is_err() {
+		// the file might not even exist, and we are already returning an error anyway
+		drop(fs::remove_file(&tmp_path).await);
+	}
+
+	result
+}
@@ -1,5 +1,6 @@
 //! Utilities for unit & integration tests.
 
+use std::collections::BTreeMap;
 use std::fmt::Display;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::time::Duration;
@@ -17,6 +18,9 @@ use testcontainers_modules::mariadb::Mariadb;
 use tokio::sync::oneshot;
 use tokio::task;
 use url::{Host, Url};
+use utoipa::openapi::schema::{AllOf, AnyOf, OneOf, Schema, SchemaType};
+use utoipa::openapi::RefOr;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::authentication::{self, Jwt};
@@ -293,6 +297,128 @@ impl Context {
 	}
 }
 
+/// Checks that `value` matches the OpenAPI schema generated for `T`.
+///
+/// This is used to make sure the schemas in the spec don't drift from what handlers actually
+/// return. In particular, every field in `value` has to be documented in the schema, so adding a
+/// field to a response type without updating its schema will fail.
+///
+/// `null` is always accepted, as optional fields are documented as nullable in various ways.
+pub(crate) fn validate_schema<'s, T>(value: &serde_json::Value) -> anyhow::Result<()>
+where
+	T: ToSchema<'s>,
+{
+	let (name, schema) = T::schema();
+	let spec = crate::openapi::Spec::new();
+	let components = spec
+		.components
+		.as_ref()
+		.map(|components| &components.schemas)
+		.context("spec should have components")?;
+
+	validate_schema_impl(value, &schema, components, name)
+}
+
+/// Recursive implementation of [`validate_schema()`].
+fn validate_schema_impl(
+	value: &serde_json::Value,
+	schema: &RefOr<Schema>,
+	components: &BTreeMap<String, RefOr<Schema>>,
+	path: &str,
+) -> anyhow::Result<()> {
+	let schema = match schema {
+		RefOr::T(schema) => schema,
+		RefOr::Ref(reference) => {
+			let name = reference
+				.ref_location
+				.trim_start_matches("#/components/schemas/");
+
+			let schema = components
+				.get(name)
+				.with_context(|| format!("`{path}` references unknown schema `{name}`"))?;
+
+			return validate_schema_impl(value, schema, components, path);
+		}
+	};
+
+	if value.is_null() {
+		return Ok(());
+	}
+
+	match schema {
+		Schema::Array(array) => {
+			let values = value
+				.as_array()
+				.with_context(|| format!("`{path}` should be an array"))?;
+
+			for (idx, value) in values.iter().enumerate() {
+				validate_schema_impl(value, &array.items, components, &format!("{path}[{idx}]"))?;
+			}
+		}
+
+		Schema::Object(object) => {
+			let matches_type = match object.schema_type {
+				SchemaType::Value => true,
+				SchemaType::Object => value.is_object(),
+				SchemaType::Array => value.is_array(),
+				SchemaType::String => value.is_string(),
+				SchemaType::Integer => value.is_i64() || value.is_u64(),
+				SchemaType::Number => value.is_number(),
+				SchemaType::Boolean => value.is_boolean(),
+			};
+
+			anyhow::ensure!(
+				matches_type,
+				"`{path}` should be of type {:?}, but is `{value}`",
+				object.schema_type,
+			);
+
+			let Some(fields) = value.as_object() else {
+				return Ok(());
+			};
+
+			if object.properties.is_empty() || object.additional_properties.is_some() {
+				return Ok(());
+			}
+
+			for (field, value) in fields {
+				let schema = object
+					.properties
+					.get(field)
+					.with_context(|| format!("`{path}.{field}` is not documented in the schema"))?;
+
+				validate_schema_impl(value, schema, components, &format!("{path}.{field}"))?;
+			}
+
+			for field in &object.required {
+				anyhow::ensure!(
+					fields.contains_key(field),
+					"`{path}.{field}` is required by the schema, but missing",
+				);
+			}
+		}
+
+		Schema::OneOf(OneOf { items, .. }) | Schema::AnyOf(AnyOf { items, .. }) => {
+			anyhow::ensure!(
+				items
+					.iter()
+					.any(|schema| validate_schema_impl(value, schema, components, path).is_ok()),
+				"`{path}` does not match any of the allowed schemas",
+			);
+		}
+
+		Schema::AllOf(AllOf { items, .. }) => {
+			for schema in items {
+				validate_schema_impl(value, schema, components, path)?;
+			}
+		}
+
+		_ => {}
+	}
+
+	Ok(())
+}
+
 /// This function runs before every test to set up things like logging.
 #[ctor::ctor]
 fn setup() {
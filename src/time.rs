@@ -1,13 +1,18 @@
 //! Helper types to deal with time.
 
+use std::cmp::Ordering;
+use std::ops::Bound;
+use std::str::FromStr;
 use std::time::Duration;
 
+use chrono::{DateTime, NaiveDate, Utc};
 use derive_more::{Debug, Deref, DerefMut, Display, From, Into};
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use sqlx::database::{HasArguments, HasValueRef};
 use sqlx::encode::IsNull;
 use sqlx::error::BoxDynError;
 use sqlx::MySql;
+use thiserror::Error;
 use utoipa::ToSchema;
 
 /// A transparent wrapper around [`std::time::Duration`] that will encode/decode as seconds.
@@ -55,3 +60,170 @@ impl<'de> Deserialize<'de> for Seconds {
 			.map(Self)
 	}
 }
+
+/// A range of points in time, used to filter results by e.g. their creation date.
+///
+/// Ranges are parsed from the same syntax as Rust ranges: `start..end` excludes `end`,
+/// `start..=end` includes it, and either side may be omitted to leave it unbounded. Both sides
+/// can either be RFC 3339 timestamps or plain dates, which are interpreted as midnight UTC; for
+/// example `2024-01-01..2024-02-01` covers all of January 2024.
+///
+/// The default range is unbounded on both sides, and thus contains every point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeRange {
+	/// The start of the range.
+	pub start: Bound<DateTime<Utc>>,
+
+	/// The end of the range.
+	pub end: Bound<DateTime<Utc>>,
+}
+
+impl Default for TimeRange {
+	fn default() -> Self {
+		Self {
+			start: Bound::Unbounded,
+			end: Bound::Unbounded,
+		}
+	}
+}
+
+impl TimeRange {
+	/// Creates a range from two optional exclusive bounds.
+	///
+	/// This is what the `created_after` / `created_before` query parameters mean.
+	pub fn between(after: Option<DateTime<Utc>>, before: Option<DateTime<Utc>>) -> Self {
+		Self {
+			start: after.map_or(Bound::Unbounded, Bound::Excluded),
+			end: before.map_or(Bound::Unbounded, Bound::Excluded),
+		}
+	}
+
+	/// Checks whether `time` is inside this range.
+	pub fn contains(&self, time: &DateTime<Utc>) -> bool {
+		let after_start = match self.start {
+			Bound::Included(start) => start <= *time,
+			Bound::Excluded(start) => start < *time,
+			Bound::Unbounded => true,
+		};
+
+		let before_end = match self.end {
+			Bound::Included(end) => *time <= end,
+			Bound::Excluded(end) => *time < end,
+			Bound::Unbounded => true,
+		};
+
+		after_start && before_end
+	}
+
+	/// Returns the range that is covered by both `self` and `other`.
+	pub fn intersect(self, other: Self) -> Self {
+		Self {
+			start: tighter_bound(self.start, other.start, Ordering::Greater),
+			end: tighter_bound(self.end, other.end, Ordering::Less),
+		}
+	}
+
+	/// Returns the SQL comparison operators and values for the bounds of this range.
+	///
+	/// Unbounded sides are returned as `None`, so they don't have to be filtered on at all.
+	/// The first element is the lower bound, the second element the upper bound.
+	pub const fn to_sql_bounds(&self) -> (Option<SqlBound>, Option<SqlBound>) {
+		let start = match self.start {
+			Bound::Included(start) => Some((" >= ", start)),
+			Bound::Excluded(start) => Some((" > ", start)),
+			Bound::Unbounded => None,
+		};
+
+		let end = match self.end {
+			Bound::Included(end) => Some((" <= ", end)),
+			Bound::Excluded(end) => Some((" < ", end)),
+			Bound::Unbounded => None,
+		};
+
+		(start, end)
+	}
+}
+
+/// A SQL comparison operator and the value to compare against.
+pub type SqlBound = (&'static str, DateTime<Utc>);
+
+/// Returns whichever bound is more restrictive.
+///
+/// `preferred` is the ordering of the more restrictive value, so `Greater` for lower bounds
+/// and `Less` for upper bounds.
+fn tighter_bound(
+	a: Bound<DateTime<Utc>>,
+	b: Bound<DateTime<Utc>>,
+	preferred: Ordering,
+) -> Bound<DateTime<Utc>> {
+	match (a, b) {
+		(Bound::Unbounded, bound) | (bound, Bound::Unbounded) => bound,
+		(Bound::Included(x) | Bound::Excluded(x), Bound::Included(y) | Bound::Excluded(y)) => {
+			match x.cmp(&y) {
+				Ordering::Equal if matches!(a, Bound::Excluded(_)) => a,
+				Ordering::Equal => b,
+				ordering if ordering == preferred => a,
+				_ => b,
+			}
+		}
+	}
+}
+
+/// An error for parsing a [`TimeRange`] from a string.
+#[derive(Debug, Error)]
+pub enum ParseTimeRangeError {
+	/// The string did not contain `..`.
+	#[error("time range must be of the form `start..end` or `start..=end`")]
+	MissingSeparator,
+
+	/// One of the bounds was neither a timestamp nor a date.
+	#[error("`{0}` is not a valid RFC 3339 timestamp or date")]
+	InvalidTimestamp(String),
+}
+
+impl FromStr for TimeRange {
+	type Err = ParseTimeRangeError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (start, end) = s
+			.split_once("..")
+			.ok_or(ParseTimeRangeError::MissingSeparator)?;
+
+		let start = match start {
+			"" => Bound::Unbounded,
+			start => Bound::Included(parse_timestamp(start)?),
+		};
+
+		let end = match end.strip_prefix('=') {
+			Some(end) => Bound::Included(parse_timestamp(end)?),
+			None if end.is_empty() => Bound::Unbounded,
+			None => Bound::Excluded(parse_timestamp(end)?),
+		};
+
+		Ok(Self { start, end })
+	}
+}
+
+/// Parses either an RFC 3339 timestamp or a plain date, which is interpreted as midnight UTC.
+fn parse_timestamp(s: &str) -> Result<DateTime<Utc>, ParseTimeRangeError> {
+	if let Ok(timestamp) = s.parse::<DateTime<Utc>>() {
+		return Ok(timestamp);
+	}
+
+	NaiveDate::parse_from_str(s, "%Y-%m-%d")
+		.ok()
+		.and_then(|date| date.and_hms_opt(0, 0, 0))
+		.map(|date| date.and_utc())
+		.ok_or_else(|| ParseTimeRangeError::InvalidTimestamp(s.to_owned()))
+}
+
+impl<'de> Deserialize<'de> for TimeRange {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		String::deserialize(deserializer)?
+			.parse()
+			.map_err(de::Error::custom)
+	}
+}